@@ -0,0 +1,423 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use calamine::{Data, Range};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+use crate::cache_system::CacheManager;
+use crate::document_parser::range_to_markdown_table;
+use crate::excel_parser::{push_row_window, render_cells, split_areas, used_area, CellFormatter, CellRange, RowWindow};
+use crate::structured_output::OutputFormat;
+use crate::impl_cacheable_content;
+
+/// Delimiters tried when sniffing a file, in order of preference
+const CANDIDATE_DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+
+/// Number of lines looked at when sniffing the dialect
+const SNIFF_LINES: usize = 100;
+
+/// Field delimiter and quote character of a delimited text file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvDialect {
+    pub delimiter: char,
+    pub quote: char,
+}
+
+impl CsvDialect {
+    /// Detect the dialect from a sample of the file. The delimiter is the
+    /// candidate that splits the most lines into the same number of fields;
+    /// `.tsv` files prefer tabs when no candidate does better.
+    pub fn sniff(sample: &str, extension: &str) -> Self {
+        let default_delimiter = if extension.eq_ignore_ascii_case("tsv") { '\t' } else { ',' };
+        let sample: String = sample.lines().take(SNIFF_LINES).collect::<Vec<_>>().join("\n");
+
+        let mut best = (Self { delimiter: default_delimiter, quote: '"' }, 0);
+        for delimiter in std::iter::once(default_delimiter).chain(CANDIDATE_DELIMITERS) {
+            let dialect = Self { delimiter, quote: '"' };
+            let score = consistency(&parse_records(&sample, dialect));
+            if score > best.1 {
+                best = (dialect, score);
+            }
+        }
+
+        // Single quotes are only taken when they split the sample more consistently
+        let (dialect, score) = best;
+        let single_quoted = Self { quote: '\'', ..dialect };
+        if !sample.contains('"') && consistency(&parse_records(&sample, single_quoted)) > score {
+            return single_quoted;
+        }
+        dialect
+    }
+
+    /// Short description such as `comma-delimited`
+    pub fn describe(&self) -> String {
+        let delimiter = match self.delimiter {
+            ',' => "comma".to_string(),
+            '\t' => "tab".to_string(),
+            ';' => "semicolon".to_string(),
+            '|' => "pipe".to_string(),
+            other => format!("'{}'", other),
+        };
+        match self.quote {
+            '"' => format!("{}-delimited", delimiter),
+            quote => format!("{}-delimited, {} quotes", delimiter, quote),
+        }
+    }
+}
+
+/// Number of sampled records sharing the most common field count, when that
+/// count is more than one field
+fn consistency(records: &[Vec<String>]) -> usize {
+    let mut counts = std::collections::HashMap::new();
+    for record in records {
+        *counts.entry(record.len()).or_insert(0usize) += 1;
+    }
+    counts.into_iter()
+        .filter(|&(fields, _)| fields > 1)
+        .map(|(_, records)| records)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Decode file bytes using their byte order mark, or as UTF-8 when they are
+/// valid UTF-8 and Windows-1252 otherwise. Returns the text and encoding name.
+pub fn decode_text(bytes: &[u8]) -> (String, &'static str) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return (text.into_owned(), encoding.name());
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), UTF_8.name()),
+        Err(_) => {
+            let (text, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+            (text.into_owned(), WINDOWS_1252.name())
+        }
+    }
+}
+
+/// Split delimited text into records. Quoted fields may contain delimiters,
+/// line breaks and doubled quotes; blank lines are skipped.
+pub fn parse_records(text: &str, dialect: CsvDialect) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    let end_field = |record: &mut Vec<String>, field: &mut String, quoted: &mut bool| {
+        record.push(std::mem::take(field));
+        *quoted = false;
+    };
+
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            if ch == dialect.quote {
+                if chars.next_if_eq(&dialect.quote).is_some() {
+                    field.push(ch);
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(ch);
+            }
+            continue;
+        }
+        match ch {
+            _ if ch == dialect.quote && field.is_empty() && !quoted => {
+                in_quotes = true;
+                quoted = true;
+            }
+            _ if ch == dialect.delimiter => end_field(&mut record, &mut field, &mut quoted),
+            '\r' | '\n' => {
+                if ch == '\r' {
+                    chars.next_if_eq(&'\n');
+                }
+                let blank = record.is_empty() && field.is_empty() && !quoted;
+                end_field(&mut record, &mut field, &mut quoted);
+                let line = std::mem::take(&mut record);
+                if !blank {
+                    records.push(line);
+                }
+            }
+            _ => field.push(ch),
+        }
+    }
+    if !record.is_empty() || !field.is_empty() || quoted {
+        end_field(&mut record, &mut field, &mut quoted);
+        records.push(record);
+    }
+    records
+}
+
+/// A CSV or TSV file read as a single sheet named after the file, the way
+/// spreadsheet applications open it
+#[derive(Debug, Clone)]
+pub struct CsvTable {
+    pub name: String,
+    pub records: Vec<Vec<String>>,
+    pub dialect: CsvDialect,
+    pub encoding: &'static str,
+}
+
+impl CsvTable {
+    /// Read a delimited text file, detecting its encoding and dialect
+    pub fn read(file_path: &str) -> Result<Self> {
+        let bytes = std::fs::read(file_path)
+            .with_context(|| format!("Failed to read file: {}", file_path))?;
+        let (text, encoding) = decode_text(&bytes);
+
+        let path = Path::new(file_path);
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        let dialect = CsvDialect::sniff(&text, extension);
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+
+        Ok(Self { name, records: parse_records(&text, dialect), dialect, encoding })
+    }
+
+    /// Number of columns of the widest record
+    pub fn width(&self) -> usize {
+        self.records.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// The records as a sheet of text cells starting at A1
+    pub fn range(&self) -> Range<Data> {
+        if self.records.is_empty() || self.width() == 0 {
+            return Range::empty();
+        }
+        let mut range = Range::new((0, 0), (self.records.len() as u32 - 1, self.width() as u32 - 1));
+        for (row, record) in self.records.iter().enumerate() {
+            for (column, value) in record.iter().enumerate() {
+                if !value.is_empty() {
+                    range.set_value((row as u32, column as u32), Data::String(value.clone()));
+                }
+            }
+        }
+        range
+    }
+
+    /// The records as a sheet of typed cells, for structured output: fields that
+    /// read as numbers or booleans become numbers and booleans
+    pub fn typed_range(&self) -> Range<Data> {
+        let mut range = self.range();
+        let cells: Vec<((u32, u32), Data)> = range.used_cells()
+            .filter_map(|(row, column, cell)| match cell {
+                Data::String(text) => Some(((row as u32, column as u32), typed_cell(text))),
+                _ => None,
+            })
+            .collect();
+        for (position, cell) in cells {
+            range.set_value(position, cell);
+        }
+        range
+    }
+
+    /// The sheet to render in an output format: text cells for markdown, typed otherwise
+    fn range_for(&self, output_format: OutputFormat) -> Range<Data> {
+        match output_format {
+            OutputFormat::Markdown => self.range(),
+            _ => self.typed_range(),
+        }
+    }
+
+    /// Summary for page info, such as `12 rows x 3 columns, comma-delimited, UTF-8`
+    pub fn describe(&self) -> String {
+        format!(
+            "{} rows x {} columns, {}, {}",
+            self.records.len(), self.width(), self.dialect.describe(), self.encoding
+        )
+    }
+}
+
+/// A field as a typed cell: whole and decimal numbers, `true`/`false`, or text.
+/// Numbers with leading zeros, such as `007`, stay text since they are usually codes.
+fn typed_cell(text: &str) -> Data {
+    if text.eq_ignore_ascii_case("true") || text.eq_ignore_ascii_case("false") {
+        return Data::Bool(text.eq_ignore_ascii_case("true"));
+    }
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    let integer_part = unsigned.split(['.', 'e', 'E']).next().unwrap_or_default();
+    let is_number = !integer_part.is_empty()
+        && integer_part.bytes().all(|byte| byte.is_ascii_digit())
+        && (integer_part == "0" || !integer_part.starts_with('0'));
+    if is_number {
+        if let Ok(number) = text.parse::<i64>() {
+            return Data::Int(number);
+        }
+        if let Some(number) = text.parse::<f64>().ok().filter(|number| number.is_finite()) {
+            return Data::Float(number);
+        }
+    }
+    Data::String(text.to_string())
+}
+
+/// Cache for storing rendered CSV content
+#[derive(Debug, Clone)]
+pub struct CsvCache {
+    pub content: String,
+    pub char_indices: Vec<usize>,
+    pub total_pages: Option<usize>,
+    /// Size, dialect and encoding of the file
+    pub summary: String,
+}
+
+// Implement CacheableContent for CsvCache
+impl_cacheable_content!(CsvCache, content, char_indices, total_pages);
+
+lazy_static::lazy_static! {
+    /// Global CSV cache manager
+    pub static ref CSV_CACHE_MANAGER: CacheManager<CsvCache> = CacheManager::new();
+}
+
+/// Read a delimited text file and render it as one markdown table
+pub fn extract_csv_content(file_path: &str) -> Result<CsvCache> {
+    let table = CsvTable::read(file_path)?;
+    let mut content = format!("# {}\n\n## Sheet 1: {}\n\n", file_name(file_path), table.name);
+    content.push_str(&range_to_markdown_table(&table.range(), &CellFormatter::default()));
+    content.push_str("\n\n");
+
+    let mut char_indices: Vec<usize> = content.char_indices().map(|(index, _)| index).collect();
+    char_indices.push(content.len());
+
+    Ok(CsvCache { content, char_indices, total_pages: Some(1), summary: table.describe() })
+}
+
+/// Read a whole delimited text file as one table in the given output format;
+/// markdown reads of the whole file go through the cache instead
+pub fn read_csv_sheet(file_path: &str, output_format: OutputFormat) -> Result<String> {
+    let table = CsvTable::read(file_path)?;
+    let range = table.range_for(output_format);
+    let mut content = format!("# {}\n\n## Sheet 1: {}\n\n", file_name(file_path), table.name);
+    let formatter = CellFormatter::default().with_output_format(output_format);
+    match used_area(&range) {
+        Some(area) => content.push_str(&render_cells(&range, &formatter, area.start, area.end)),
+        None => content.push_str("Empty sheet"),
+    }
+    content.push_str("\n\n");
+    Ok(content)
+}
+
+/// Read cells of a delimited text file by A1 range, such as `B2:D40` or `A:C`.
+/// A sheet name, if given, must be the file's name.
+pub fn read_csv_range(file_path: &str, range_spec: &str, output_format: OutputFormat) -> Result<String> {
+    let table = CsvTable::read(file_path)?;
+    let range = table.range_for(output_format);
+    let formatter = CellFormatter::default().with_output_format(output_format);
+    let mut markdown = format!("# {}\n\n", file_name(file_path));
+
+    let areas = split_areas(range_spec.trim());
+    if areas.is_empty() {
+        return Err(anyhow::anyhow!("Range parameter is empty"));
+    }
+    for area in areas {
+        let area = CellRange::parse(area)?;
+        if let Some(sheet) = area.sheet.as_ref().filter(|sheet| !sheet.eq_ignore_ascii_case(&table.name)) {
+            return Err(anyhow::anyhow!("Sheet '{}' not found. Available sheets: {}", sheet, table.name));
+        }
+        let qualified = CellRange { sheet: Some(table.name.clone()), ..area };
+        markdown.push_str(&format!("## Sheet 1: {}\n\n", qualified));
+        match qualified.clip_to(&range) {
+            Some((start, end)) => markdown.push_str(&render_cells(&range, &formatter, start, end)),
+            None => markdown.push_str("*No data in this range*"),
+        }
+        markdown.push_str("\n\n");
+    }
+    Ok(markdown)
+}
+
+/// Read a window of rows of a delimited text file, repeating its header row
+pub fn read_csv_rows(file_path: &str, rows_spec: &str, output_format: OutputFormat) -> Result<String> {
+    let window = RowWindow::parse(rows_spec)?;
+    let table = CsvTable::read(file_path)?;
+    let mut markdown = format!("# {}\n\n", file_name(file_path));
+    let title = format!("Sheet 1: {}", table.name);
+    let formatter = CellFormatter::default().with_output_format(output_format);
+    push_row_window(&mut markdown, &title, &table.range_for(output_format), &formatter, window, rows_spec);
+    Ok(markdown)
+}
+
+fn file_name(file_path: &str) -> String {
+    Path::new(file_path).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn csv_file(suffix: &str, bytes: &[u8]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::with_suffix(suffix).unwrap();
+        file.write_all(bytes).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_typed_cells() {
+        assert_eq!(typed_cell("42"), Data::Int(42));
+        assert_eq!(typed_cell("-1.5e3"), Data::Float(-1500.0));
+        assert_eq!(typed_cell("0.25"), Data::Float(0.25));
+        assert_eq!(typed_cell("TRUE"), Data::Bool(true));
+        assert_eq!(typed_cell("007"), Data::String("007".to_string()));
+        assert_eq!(typed_cell("inf"), Data::String("inf".to_string()));
+        assert_eq!(typed_cell("1,50"), Data::String("1,50".to_string()));
+    }
+
+    #[test]
+    fn test_sniff_dialect() {
+        let comma = "name,qty\n\"Pens, blue\",3\nInk,4\n";
+        assert_eq!(CsvDialect::sniff(comma, "csv"), CsvDialect { delimiter: ',', quote: '"' });
+
+        let semicolon = "name;price\nPen;1,50\nInk;2,25\n";
+        assert_eq!(CsvDialect::sniff(semicolon, "csv").delimiter, ';');
+
+        let tabs = "id\tnote\n1\tok, fine\n2\tgood\n";
+        assert_eq!(CsvDialect::sniff(tabs, "txt").delimiter, '\t');
+        assert_eq!(CsvDialect::sniff("single column\nvalue\n", "tsv").delimiter, '\t');
+
+        let single_quoted = "a|b\n'x|y'|1\n'z'|2\n";
+        assert_eq!(CsvDialect::sniff(single_quoted, "csv"), CsvDialect { delimiter: '|', quote: '\'' });
+    }
+
+    #[test]
+    fn test_parse_records() {
+        let dialect = CsvDialect { delimiter: ',', quote: '"' };
+        let records = parse_records("a,b,c\r\n\"x, \"\"quoted\"\"\",\"two\nlines\",\n\n1,,3", dialect);
+        assert_eq!(records, vec![
+            vec!["a", "b", "c"],
+            vec!["x, \"quoted\"", "two\nlines", ""],
+            vec!["1", "", "3"],
+        ]);
+        assert_eq!(parse_records("\"\"\n", dialect), vec![vec![""]]);
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(decode_text(b"\xEF\xBB\xBFcaf\xC3\xA9"), ("café".to_string(), "UTF-8"));
+        assert_eq!(decode_text(b"caf\xE9"), ("café".to_string(), "windows-1252"));
+        assert_eq!(decode_text(b"\xFF\xFEa\x00,\x00b\x00"), ("a,b".to_string(), "UTF-16LE"));
+    }
+
+    #[test]
+    fn test_read_csv_as_sheet() {
+        let file = csv_file(".csv", b"Region;Sales\nNorth;10\nSouth;20\nWest|East;30\n");
+        let path = file.path().to_str().unwrap();
+        let name = CsvTable::read(path).unwrap().name;
+
+        let cache = extract_csv_content(path).unwrap();
+        assert!(cache.content.contains(&format!(
+            "## Sheet 1: {}\n\n| Region | Sales |\n| --- | --- |\n| North | 10 |\n| South | 20 |\n| West\\|East | 30 |\n",
+            name
+        )), "{}", cache.content);
+        assert_eq!(cache.summary, "4 rows x 2 columns, semicolon-delimited, UTF-8");
+
+        let markdown = read_csv_rows(path, "3-", OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("(rows 3-4 of 4)\n\n| Region | Sales |\n| --- | --- |\n| South | 20 |\n| West\\|East | 30 |\n\n"), "{}", markdown);
+
+        let markdown = read_csv_range(path, "B2:B3", OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("!B2:B3\n\n| 10 |\n| --- |\n| 20 |\n\n"), "{}", markdown);
+        assert!(read_csv_range(path, "Other!A1", OutputFormat::Markdown).is_err());
+
+        let json = read_csv_sheet(path, OutputFormat::JsonRecords).unwrap();
+        assert!(json.contains("```json\n[\n  {\"Region\": \"North\", \"Sales\": 10},"), "{}", json);
+    }
+}
@@ -0,0 +1,783 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use anyhow::{bail, Context, Result};
+use cfb::CompoundFile;
+
+use crate::docx_parser::DocxNote;
+use crate::markdown_table::{escape_text, render_table};
+
+/// Signature at the start of every OLE2 compound file
+const CFB_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+/// `wIdent` of a Word File Information Block
+const WORD_IDENT: u16 = 0xA5EC;
+
+/// Lowest `nFib` written by Word 97; older versions use an incompatible FIB layout
+const MIN_WORD97_NFIB: u16 = 0x00C0;
+
+/// Indices into `FibRgLw97`
+const LW_CCP_TEXT: usize = 3;
+const LW_CCP_FTN: usize = 4;
+const LW_CCP_HDD: usize = 5;
+const LW_CCP_ATN: usize = 7;
+const LW_CCP_EDN: usize = 8;
+
+/// Indices into `FibRgFcLcb97`
+const FC_PLCFFND_REF: usize = 2;
+const FC_PLCFFND_TXT: usize = 3;
+const FC_PLCF_HDD: usize = 11;
+const FC_PLCF_BTE_PAPX: usize = 13;
+const FC_CLX: usize = 33;
+const FC_PLCFEND_REF: usize = 46;
+const FC_PLCFEND_TXT: usize = 47;
+
+/// Paragraph property modifiers that mark table paragraphs
+const SPRM_P_F_IN_TABLE: u16 = 0x2416;
+const SPRM_P_F_TTP: u16 = 0x2417;
+const SPRM_P_F_INNER_TABLE_CELL: u16 = 0x244B;
+const SPRM_P_F_INNER_TTP: u16 = 0x244C;
+const SPRM_T_DEF_TABLE: u16 = 0xD608;
+
+/// Size of a formatted disk page holding paragraph properties
+const FKP_SIZE: usize = 512;
+
+/// `PIDSI_PAGECOUNT` in the SummaryInformation property set
+const PIDSI_PAGECOUNT: u32 = 14;
+const VT_I4: u32 = 3;
+
+/// Windows-1252 mapping of bytes 0x80–0x9F, used by compressed text pieces
+const CP1252_HIGH: [u16; 32] = [
+    0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0x008D, 0x017D, 0x008F, 0x0090, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+    0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+];
+
+/// Text of a Word 97-2003 (`.doc`) document, rendered as markdown.
+///
+/// Text is read through the piece table, so fast-saved documents come out in
+/// reading order. Character formatting, styles and tracked changes are not read.
+#[derive(Debug, Clone, Default)]
+pub struct DocDocument {
+    /// Main text split on page and section breaks; entry `i` holds page `i + 1`
+    pub pages: Vec<String>,
+    pub headers: Vec<String>,
+    pub footers: Vec<String>,
+    pub footnotes: Vec<DocxNote>,
+    pub endnotes: Vec<DocxNote>,
+    /// Page count saved by Word in the SummaryInformation stream
+    pub stored_page_count: Option<usize>,
+}
+
+impl DocDocument {
+    /// Parse a Word 97-2003 compound file
+    pub fn read(buffer: &[u8]) -> Result<Self> {
+        let mut container = CompoundFile::open(Cursor::new(buffer))
+            .with_context(|| "Failed to open DOC compound file")?;
+        let word = read_stream(&mut container, "/WordDocument")?;
+        let fib = Fib::parse(&word)?;
+        let table = read_stream(&mut container, fib.table_stream)?;
+
+        let clx = fib.table_part(&table, FC_CLX)
+            .with_context(|| "DOC file has no piece table")?;
+        let pieces = parse_piece_table(clx)?;
+        let units = read_text_units(&word, &pieces)?;
+        let papx = PapxIndex::new(&word, fib.table_part(&table, FC_PLCF_BTE_PAPX));
+
+        // Stories follow each other in CP order: main text, footnotes, headers, comments, endnotes
+        let main_end = fib.ccp_text;
+        let footnotes_start = main_end;
+        let headers_start = footnotes_start + fib.ccp_ftn;
+        let endnotes_start = headers_start + fib.ccp_hdd + fib.ccp_atn;
+        let story = |start: usize, length: usize| {
+            let end = (start + length).min(units.len());
+            &units[start.min(end)..end]
+        };
+
+        let footnote_refs = note_references(&fib, &table, FC_PLCFFND_REF, "");
+        let endnote_refs = note_references(&fib, &table, FC_PLCFEND_REF, "e");
+        let mut labels: HashMap<usize, String> = HashMap::new();
+        labels.extend(footnote_refs.iter().cloned());
+        labels.extend(endnote_refs.iter().cloned());
+
+        let mut renderer = StoryRenderer::new(&papx, &labels);
+        renderer.render(story(0, main_end));
+        let pages = renderer.finish();
+
+        let footnotes = render_notes(&fib, &table, FC_PLCFFND_TXT, &footnote_refs, story(footnotes_start, fib.ccp_ftn), &papx);
+        let endnotes = render_notes(&fib, &table, FC_PLCFEND_TXT, &endnote_refs, story(endnotes_start, fib.ccp_edn), &papx);
+        let (headers, footers) = render_headers_footers(&fib, &table, story(headers_start, fib.ccp_hdd), &papx);
+
+        Ok(Self {
+            pages,
+            headers,
+            footers,
+            footnotes,
+            endnotes,
+            stored_page_count: read_summary_page_count(&mut container),
+        })
+    }
+
+    /// Number of pages split on the explicit breaks found; `stored_page_count`
+    /// holds the application's own count
+    pub fn page_count(&self) -> usize {
+        self.pages.len().max(1)
+    }
+}
+
+/// Whether a buffer holds an OLE2 compound file, such as a Word 97-2003 document
+pub fn is_ole2_document(buffer: &[u8]) -> bool {
+    buffer.starts_with(&CFB_SIGNATURE)
+}
+
+fn read_stream<F: Read + std::io::Seek>(container: &mut CompoundFile<F>, name: &str) -> Result<Vec<u8>> {
+    let mut stream = container.open_stream(name)
+        .with_context(|| format!("DOC file has no {} stream", name.trim_start_matches('/')))?;
+    let mut data = Vec::new();
+    stream.read_to_end(&mut data)
+        .with_context(|| format!("Failed to read DOC stream: {}", name))?;
+    Ok(data)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// The parts of the File Information Block needed to locate text
+struct Fib {
+    table_stream: &'static str,
+    ccp_text: usize,
+    ccp_ftn: usize,
+    ccp_hdd: usize,
+    ccp_atn: usize,
+    ccp_edn: usize,
+    /// (offset, length) pairs of `FibRgFcLcb`, locating structures in the table stream
+    fc_lcb: Vec<(u32, u32)>,
+}
+
+impl Fib {
+    fn parse(word: &[u8]) -> Result<Self> {
+        let truncated = || anyhow::anyhow!("Truncated DOC File Information Block");
+
+        let ident = u16_at(word, 0).ok_or_else(truncated)?;
+        if ident != WORD_IDENT {
+            bail!("Not a Word document (wIdent {:#06x})", ident);
+        }
+        let n_fib = u16_at(word, 2).ok_or_else(truncated)?;
+        if n_fib < MIN_WORD97_NFIB {
+            bail!("Word 6.0/95 documents are not supported (nFib {:#06x})", n_fib);
+        }
+        let flags = u16_at(word, 0x0A).ok_or_else(truncated)?;
+        if flags & 0x0100 != 0 {
+            bail!("Encrypted DOC files are not supported");
+        }
+        let table_stream = if flags & 0x0200 != 0 { "/1Table" } else { "/0Table" };
+
+        // FibBase is followed by three length-prefixed arrays: rgW, rgLw and rgFcLcb
+        let csw = u16_at(word, 32).ok_or_else(truncated)? as usize;
+        let cslw_offset = 34 + csw * 2;
+        let cslw = u16_at(word, cslw_offset).ok_or_else(truncated)? as usize;
+        let lw_start = cslw_offset + 2;
+        let cb_offset = lw_start + cslw * 4;
+        let cb_rg_fc_lcb = u16_at(word, cb_offset).ok_or_else(truncated)? as usize;
+        let fc_start = cb_offset + 2;
+
+        let lw = |index: usize| -> usize {
+            if index < cslw { u32_at(word, lw_start + index * 4).unwrap_or(0) as usize } else { 0 }
+        };
+        let fc_lcb = (0..cb_rg_fc_lcb)
+            .map_while(|index| {
+                let offset = fc_start + index * 8;
+                Some((u32_at(word, offset)?, u32_at(word, offset + 4)?))
+            })
+            .collect();
+
+        Ok(Self {
+            table_stream,
+            ccp_text: lw(LW_CCP_TEXT),
+            ccp_ftn: lw(LW_CCP_FTN),
+            ccp_hdd: lw(LW_CCP_HDD),
+            ccp_atn: lw(LW_CCP_ATN),
+            ccp_edn: lw(LW_CCP_EDN),
+            fc_lcb,
+        })
+    }
+
+    /// Slice of the table stream located by entry `index` of `FibRgFcLcb`, if present
+    fn table_part<'t>(&self, table: &'t [u8], index: usize) -> Option<&'t [u8]> {
+        let &(fc, lcb) = self.fc_lcb.get(index)?;
+        if lcb == 0 {
+            return None;
+        }
+        table.get(fc as usize..fc as usize + lcb as usize)
+    }
+}
+
+/// A run of text in the WordDocument stream, covering CPs `cp_start..cp_end`
+struct Piece {
+    cp_start: u32,
+    cp_end: u32,
+    fc: u32,
+    /// Stored as one Windows-1252 byte per character instead of UTF-16
+    compressed: bool,
+}
+
+/// Read the piece table (`Pcdt`) from the `Clx`, skipping any leading property runs
+fn parse_piece_table(clx: &[u8]) -> Result<Vec<Piece>> {
+    let mut position = 0;
+    while let Some(&kind) = clx.get(position) {
+        match kind {
+            0x01 => {
+                let cb = u16_at(clx, position + 1).with_context(|| "Truncated DOC piece table")?;
+                position += 3 + cb as usize;
+            }
+            0x02 => {
+                let lcb = u32_at(clx, position + 1).with_context(|| "Truncated DOC piece table")? as usize;
+                let plc = clx.get(position + 5..position + 5 + lcb)
+                    .with_context(|| "Truncated DOC piece table")?;
+                let count = lcb.saturating_sub(4) / 12;
+                let descriptors = (count + 1) * 4;
+
+                return Ok((0..count)
+                    .filter_map(|index| {
+                        let raw_fc = u32_at(plc, descriptors + index * 8 + 2)?;
+                        Some(Piece {
+                            cp_start: u32_at(plc, index * 4)?,
+                            cp_end: u32_at(plc, (index + 1) * 4)?,
+                            fc: raw_fc & 0x3FFF_FFFF,
+                            compressed: raw_fc & 0x4000_0000 != 0,
+                        })
+                    })
+                    .collect());
+            }
+            other => bail!("Invalid DOC piece table entry {:#04x}", other),
+        }
+    }
+    bail!("DOC file has no piece table")
+}
+
+/// One UTF-16 code unit of document text and the stream offset it was read from.
+/// Each unit is one CP, so story boundaries can be applied by index.
+#[derive(Debug, Clone, Copy)]
+struct TextUnit {
+    unit: u16,
+    fc: u32,
+}
+
+/// Decode all pieces into text units in CP order
+fn read_text_units(word: &[u8], pieces: &[Piece]) -> Result<Vec<TextUnit>> {
+    let mut units = Vec::new();
+    for piece in pieces {
+        let length = piece.cp_end.saturating_sub(piece.cp_start) as usize;
+        if piece.compressed {
+            let start = piece.fc as usize / 2;
+            let bytes = word.get(start..start + length)
+                .with_context(|| "DOC piece table points outside the WordDocument stream")?;
+            units.extend(bytes.iter().enumerate().map(|(index, &byte)| TextUnit {
+                unit: match byte {
+                    0x80..=0x9F => CP1252_HIGH[(byte - 0x80) as usize],
+                    _ => byte as u16,
+                },
+                fc: (start + index) as u32,
+            }));
+        } else {
+            let start = piece.fc as usize;
+            let bytes = word.get(start..start + length * 2)
+                .with_context(|| "DOC piece table points outside the WordDocument stream")?;
+            units.extend(bytes.chunks_exact(2).enumerate().map(|(index, pair)| TextUnit {
+                unit: u16::from_le_bytes([pair[0], pair[1]]),
+                fc: (start + index * 2) as u32,
+            }));
+        }
+    }
+    Ok(units)
+}
+
+/// CPs of a PLC structure: `count + 1` positions followed by `count` data elements
+fn plc_positions(plc: &[u8], data_size: usize) -> Vec<usize> {
+    let count = plc.len().saturating_sub(4) / (4 + data_size);
+    (0..=count).filter_map(|index| u32_at(plc, index * 4).map(|cp| cp as usize)).collect()
+}
+
+/// Positions of note references in the main text, labelled `1`, `2`… (or `e1`, `e2`… for endnotes)
+fn note_references(fib: &Fib, table: &[u8], index: usize, label_prefix: &str) -> Vec<(usize, String)> {
+    let Some(plc) = fib.table_part(table, index) else {
+        return Vec::new();
+    };
+    let positions = plc_positions(plc, 2);
+    positions[..positions.len().saturating_sub(1)].iter()
+        .enumerate()
+        .map(|(number, &cp)| (cp, format!("{}{}", label_prefix, number + 1)))
+        .collect()
+}
+
+/// Render the text of each note; the note story is split by the `Plcf*Txt` positions
+fn render_notes(
+    fib: &Fib,
+    table: &[u8],
+    index: usize,
+    references: &[(usize, String)],
+    story: &[TextUnit],
+    papx: &PapxIndex,
+) -> Vec<DocxNote> {
+    let Some(plc) = fib.table_part(table, index) else {
+        return Vec::new();
+    };
+    let positions: Vec<usize> = plc.chunks_exact(4)
+        .map(|cp| u32::from_le_bytes([cp[0], cp[1], cp[2], cp[3]]) as usize)
+        .collect();
+    let no_labels = HashMap::new();
+
+    references.iter()
+        .zip(positions.windows(2))
+        .filter_map(|((_, label), range)| {
+            let units = story.get(range[0]..range[1].min(story.len()))?;
+            let mut renderer = StoryRenderer::new(papx, &no_labels);
+            renderer.render(units);
+            Some(DocxNote { label: label.clone(), text: renderer.finish().join("\n\n") })
+        })
+        .collect()
+}
+
+/// Render the header story, split by `PlcfHdd` into headers and footers with repeats removed
+fn render_headers_footers(fib: &Fib, table: &[u8], story: &[TextUnit], papx: &PapxIndex) -> (Vec<String>, Vec<String>) {
+    let mut headers: Vec<String> = Vec::new();
+    let mut footers: Vec<String> = Vec::new();
+    let Some(plc) = fib.table_part(table, FC_PLCF_HDD) else {
+        return (headers, footers);
+    };
+    let positions: Vec<usize> = plc.chunks_exact(4)
+        .map(|cp| u32::from_le_bytes([cp[0], cp[1], cp[2], cp[3]]) as usize)
+        .collect();
+    let no_labels = HashMap::new();
+
+    // The first six stories are note separators; then each section has six stories:
+    // even header, odd header, even footer, odd footer, first-page header, first-page footer
+    for (index, range) in positions.windows(2).enumerate().skip(6) {
+        let Some(units) = story.get(range[0]..range[1].min(story.len())) else { continue };
+        let mut renderer = StoryRenderer::new(papx, &no_labels);
+        renderer.render(units);
+        let text = renderer.finish().join("\n\n");
+
+        let target = match (index - 6) % 6 {
+            0 | 1 | 4 => &mut headers,
+            _ => &mut footers,
+        };
+        if !text.is_empty() && !target.contains(&text) {
+            target.push(text);
+        }
+    }
+    (headers, footers)
+}
+
+/// Table-related paragraph properties
+#[derive(Debug, Clone, Copy, Default)]
+struct ParagraphProperties {
+    in_table: bool,
+    /// Row end mark of an outer table
+    row_end: bool,
+    /// Cell or row end mark of a nested table
+    inner_cell: bool,
+    inner_row_end: bool,
+}
+
+/// Lookup of paragraph properties through `PlcBtePapx` and its formatted disk pages
+struct PapxIndex<'a> {
+    word: &'a [u8],
+    /// (first FC, end FC, FKP page number)
+    pages: Vec<(u32, u32, u32)>,
+}
+
+impl<'a> PapxIndex<'a> {
+    fn new(word: &'a [u8], plc: Option<&[u8]>) -> Self {
+        let pages = plc
+            .map(|plc| {
+                let count = plc.len().saturating_sub(4) / 8;
+                let positions = (count + 1) * 4;
+                (0..count)
+                    .filter_map(|index| {
+                        Some((
+                            u32_at(plc, index * 4)?,
+                            u32_at(plc, (index + 1) * 4)?,
+                            u32_at(plc, positions + index * 4)? & 0x003F_FFFF,
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { word, pages }
+    }
+
+    /// Properties of the paragraph whose mark is stored at `fc`, if they can be found
+    fn lookup(&self, fc: u32) -> Option<ParagraphProperties> {
+        let &(_, _, page_number) = self.pages.iter().find(|(start, end, _)| *start <= fc && fc < *end)?;
+        let page_start = page_number as usize * FKP_SIZE;
+        let page = self.word.get(page_start..page_start + FKP_SIZE)?;
+
+        let runs = page[FKP_SIZE - 1] as usize;
+        let run = (0..runs).find(|&index| {
+            matches!((u32_at(page, index * 4), u32_at(page, (index + 1) * 4)),
+                (Some(start), Some(end)) if start <= fc && fc < end)
+        })?;
+
+        let offset = *page.get((runs + 1) * 4 + run * 13)? as usize * 2;
+        if offset == 0 {
+            return Some(ParagraphProperties::default());
+        }
+        let grpprl = match *page.get(offset)? as usize {
+            0 => {
+                let size = *page.get(offset + 1)? as usize * 2;
+                page.get(offset + 2..offset + 2 + size)?
+            }
+            size => page.get(offset + 1..offset + 2 * size)?,
+        };
+
+        // The property list starts with the paragraph style index
+        Some(parse_paragraph_sprms(grpprl.get(2..)?))
+    }
+}
+
+fn parse_paragraph_sprms(mut sprms: &[u8]) -> ParagraphProperties {
+    let mut properties = ParagraphProperties::default();
+    while let Some(opcode) = u16_at(sprms, 0) {
+        let operand = &sprms[2..];
+        let length = match opcode >> 13 {
+            0 | 1 => 1,
+            2 | 4 | 5 => 2,
+            3 => 4,
+            7 => 3,
+            // Variable-length operands start with their size
+            _ if opcode == SPRM_T_DEF_TABLE => u16_at(operand, 0).map_or(operand.len(), |cb| cb as usize + 1),
+            _ => operand.first().map_or(operand.len(), |&cb| cb as usize + 1),
+        };
+        let value = operand.first().copied().unwrap_or(0) != 0;
+        match opcode {
+            SPRM_P_F_IN_TABLE => properties.in_table = value,
+            SPRM_P_F_TTP => properties.row_end = value,
+            SPRM_P_F_INNER_TABLE_CELL => properties.inner_cell = value,
+            SPRM_P_F_INNER_TTP => properties.inner_row_end = value,
+            _ => {}
+        }
+        sprms = operand.get(length..).unwrap_or_default();
+    }
+    properties
+}
+
+/// Turns the special characters of a text story into markdown blocks and pages
+struct StoryRenderer<'r> {
+    papx: &'r PapxIndex<'r>,
+    /// Note reference labels by CP
+    labels: &'r HashMap<usize, String>,
+    pages: Vec<Vec<String>>,
+    paragraph: Vec<u16>,
+    /// For each open field, whether its result (rather than its instructions) is being read
+    fields: Vec<bool>,
+    table: Vec<Vec<String>>,
+    row: Vec<String>,
+    /// Paragraphs of the current table cell
+    cell: Vec<String>,
+    previous_unit: u16,
+}
+
+impl<'r> StoryRenderer<'r> {
+    fn new(papx: &'r PapxIndex<'r>, labels: &'r HashMap<usize, String>) -> Self {
+        Self {
+            papx,
+            labels,
+            pages: vec![Vec::new()],
+            paragraph: Vec::new(),
+            fields: Vec::new(),
+            table: Vec::new(),
+            row: Vec::new(),
+            cell: Vec::new(),
+            previous_unit: 0,
+        }
+    }
+
+    /// Render text units, starting at CP 0 of the story
+    fn render(&mut self, units: &[TextUnit]) {
+        for (index, text_unit) in units.iter().enumerate() {
+            let unit = text_unit.unit;
+            let visible = self.fields.iter().all(|&result| result);
+            match unit {
+                0x0D => self.end_paragraph(text_unit.fc),
+                0x07 => self.end_cell(text_unit.fc),
+                0x0C => {
+                    self.end_paragraph(text_unit.fc);
+                    self.flush_table();
+                    self.pages.push(Vec::new());
+                }
+                // Column break
+                0x0E => self.end_paragraph(text_unit.fc),
+                0x13 => self.fields.push(false),
+                0x14 => {
+                    if let Some(result) = self.fields.last_mut() {
+                        *result = true;
+                    }
+                }
+                0x15 => {
+                    self.fields.pop();
+                }
+                _ if !visible => {}
+                0x02 => {
+                    if let Some(label) = self.labels.get(&index) {
+                        self.paragraph.extend(format!("[^{}]", label).encode_utf16());
+                    }
+                }
+                0x09 | 0x0B => self.paragraph.push(unit),
+                // Non-breaking hyphen and space
+                0x1E => self.paragraph.push(u16::from(b'-')),
+                0xA0 => self.paragraph.push(u16::from(b' ')),
+                // Optional hyphens, pictures, drawn objects and other control characters
+                0x00..=0x1F => {}
+                _ => self.paragraph.push(unit),
+            }
+            self.previous_unit = unit;
+        }
+    }
+
+    /// Pages of markdown blocks, with any open table flushed
+    fn finish(mut self) -> Vec<String> {
+        if !self.paragraph.is_empty() {
+            self.end_paragraph(u32::MAX);
+        }
+        self.flush_table();
+        self.pages.into_iter().map(|blocks| blocks.join("\n\n")).collect()
+    }
+
+    fn take_paragraph(&mut self) -> String {
+        let text = String::from_utf16_lossy(&std::mem::take(&mut self.paragraph));
+        text.trim().to_string()
+    }
+
+    fn end_paragraph(&mut self, fc: u32) {
+        let properties = self.papx.lookup(fc).unwrap_or_default();
+        let text = self.take_paragraph();
+        if properties.in_table {
+            // A cell with several paragraphs: all but the last end with a paragraph mark
+            if !text.is_empty() {
+                self.cell.push(text);
+            }
+            return;
+        }
+
+        self.flush_table();
+        if !text.is_empty() {
+            let block = text.replace('\u{0B}', "  \n");
+            self.pages.last_mut().expect("at least one page").push(block);
+        }
+    }
+
+    fn end_cell(&mut self, fc: u32) {
+        let text = self.take_paragraph();
+        let properties = match self.papx.lookup(fc) {
+            Some(properties) => properties,
+            None => ParagraphProperties {
+                // Without paragraph properties, a mark right after a cell mark ends the row
+                row_end: text.is_empty() && self.cell.is_empty() && self.previous_unit == 0x07 && !self.row.is_empty(),
+                ..ParagraphProperties::default()
+            },
+        };
+
+        if properties.inner_row_end {
+            return;
+        }
+        if !text.is_empty() {
+            self.cell.push(text);
+        }
+        if properties.inner_cell {
+            return;
+        }
+        if properties.row_end {
+            self.table.push(std::mem::take(&mut self.row));
+            self.cell.clear();
+            return;
+        }
+
+        let cell = std::mem::take(&mut self.cell).join("\n").replace('\u{0B}', "\n");
+        self.row.push(escape_text(&cell));
+    }
+
+    fn flush_table(&mut self) {
+        if !self.row.is_empty() {
+            self.table.push(std::mem::take(&mut self.row));
+        }
+        if self.table.is_empty() {
+            return;
+        }
+
+        let table = render_table(std::mem::take(&mut self.table));
+        self.pages.last_mut().expect("at least one page").push(table);
+    }
+}
+
+/// Page count from the `\x05SummaryInformation` property set stream
+fn read_summary_page_count<F: Read + std::io::Seek>(container: &mut CompoundFile<F>) -> Option<usize> {
+    let data = read_stream(container, "/\u{5}SummaryInformation").ok()?;
+
+    // Property set header, then the offset of the first section
+    let section = u32_at(&data, 44)? as usize;
+    // A corrupt count cannot run past the entries the stream actually holds
+    let properties = (u32_at(&data, section + 4)? as usize).min(data.len().saturating_sub(section + 8) / 8);
+    (0..properties).find_map(|index| {
+        let entry = section + 8 + index * 8;
+        if u32_at(&data, entry)? != PIDSI_PAGECOUNT {
+            return None;
+        }
+        let value = section + u32_at(&data, entry + 4)? as usize;
+        if u32_at(&data, value)? != VT_I4 {
+            return None;
+        }
+        usize::try_from(u32_at(&data, value + 4)? as i32).ok().filter(|&pages| pages > 0)
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Build a minimal Word 97 FIB followed by `text` as one compressed and one UTF-16 piece
+    pub(crate) fn build_doc(compressed: &str, unicode: &str, ccp_ftn: usize, table_parts: &[(usize, Vec<u8>)]) -> Vec<u8> {
+        let mut word = Vec::new();
+        word.extend(WORD_IDENT.to_le_bytes());
+        word.extend(0x00C1u16.to_le_bytes());
+        word.resize(0x0A, 0);
+        word.extend(0x0200u16.to_le_bytes());
+        word.resize(32, 0);
+
+        let ccp_text = compressed.chars().count() + unicode.encode_utf16().count() - ccp_ftn;
+        word.extend(14u16.to_le_bytes());
+        word.extend([0u8; 28]);
+        word.extend(22u16.to_le_bytes());
+        let mut lw = [0u32; 22];
+        lw[LW_CCP_TEXT] = ccp_text as u32;
+        lw[LW_CCP_FTN] = ccp_ftn as u32;
+        lw.iter().for_each(|value| word.extend(value.to_le_bytes()));
+
+        // Table stream: the requested parts, then the piece table
+        let mut table = Vec::new();
+        let mut fc_lcb = vec![(0u32, 0u32); 93];
+        for (index, part) in table_parts {
+            fc_lcb[*index] = (table.len() as u32, part.len() as u32);
+            table.extend(part);
+        }
+
+        word.extend(93u16.to_le_bytes());
+        let fc_lcb_offset = word.len();
+        word.resize(fc_lcb_offset + 93 * 8, 0);
+
+        let compressed_fc = word.len() as u32;
+        word.extend(compressed.chars().map(|c| c as u8));
+        let unicode_fc = word.len() as u32;
+        unicode.encode_utf16().for_each(|unit| word.extend(unit.to_le_bytes()));
+
+        let split = compressed.chars().count() as u32;
+        let end = split + unicode.encode_utf16().count() as u32;
+        let mut plc = Vec::new();
+        [0, split, end].iter().for_each(|cp: &u32| plc.extend(cp.to_le_bytes()));
+        for fc in [(compressed_fc * 2) | 0x4000_0000, unicode_fc] {
+            plc.extend([0u8; 2]);
+            plc.extend(fc.to_le_bytes());
+            plc.extend([0u8; 2]);
+        }
+        let mut clx = vec![0x02];
+        clx.extend((plc.len() as u32).to_le_bytes());
+        clx.extend(plc);
+        fc_lcb[FC_CLX] = (table.len() as u32, clx.len() as u32);
+        table.extend(clx);
+
+        for (index, (fc, lcb)) in fc_lcb.iter().enumerate() {
+            let offset = fc_lcb_offset + index * 8;
+            word[offset..offset + 4].copy_from_slice(&fc.to_le_bytes());
+            word[offset + 4..offset + 8].copy_from_slice(&lcb.to_le_bytes());
+        }
+
+        let mut container = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        container.create_stream("/WordDocument").unwrap().write_all(&word).unwrap();
+        container.create_stream("/1Table").unwrap().write_all(&table).unwrap();
+        container.flush().unwrap();
+        container.into_inner().into_inner()
+    }
+
+    fn cps(positions: &[u32], data_size: usize) -> Vec<u8> {
+        let mut plc = Vec::new();
+        positions.iter().for_each(|cp| plc.extend(cp.to_le_bytes()));
+        plc.extend(vec![0u8; data_size * positions.len().saturating_sub(1)]);
+        plc
+    }
+
+    #[test]
+    fn test_doc_document_text_pages_and_tables() {
+        let buffer = build_doc(
+            "Report\r\x13 HYPERLINK \"https://example.com\" \x14Example\x15 site\x0b2024\r\x0cName\x07Qty\x07\x07",
+            "Caf\u{e9}\x07\u{2153}|2\x07\x07Done \u{2013} ok\r",
+            0,
+            &[],
+        );
+        assert!(is_ole2_document(&buffer));
+
+        let document = DocDocument::read(&buffer).unwrap();
+        assert_eq!(
+            document.pages,
+            vec![
+                "Report\n\nExample site  \n2024".to_string(),
+                "| Name | Qty |\n| --- | --- |\n| Café | \u{2153}\\|2 |\n\nDone \u{2013} ok".to_string(),
+            ]
+        );
+        assert_eq!(document.page_count(), 2);
+        assert!(document.footnotes.is_empty());
+    }
+
+    #[test]
+    fn test_doc_document_footnotes() {
+        // Main text "See\x02 here.\r" (CPs 0..11), then the footnote story "\x02 Source.\r\r"
+        let buffer = build_doc(
+            "See\x02 here.\r",
+            "\x02 Source.\r\r",
+            11,
+            &[(FC_PLCFFND_REF, cps(&[3, 12], 2)), (FC_PLCFFND_TXT, cps(&[0, 10, 11], 0))],
+        );
+
+        let document = DocDocument::read(&buffer).unwrap();
+        assert_eq!(document.pages, vec!["See[^1] here.".to_string()]);
+        assert_eq!(document.footnotes, vec![DocxNote { label: "1".to_string(), text: "Source.".to_string() }]);
+    }
+
+    #[test]
+    fn test_doc_document_rejects_other_files() {
+        assert!(!is_ole2_document(b"PK\x03\x04"));
+        assert!(DocDocument::read(b"not a compound file").is_err());
+
+        let mut container = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        container.create_stream("/WordDocument").unwrap().write_all(&[0u8; 64]).unwrap();
+        container.flush().unwrap();
+        let error = DocDocument::read(&container.into_inner().into_inner()).unwrap_err();
+        assert!(error.to_string().contains("Not a Word document"));
+    }
+
+    #[test]
+    fn test_summary_page_count() {
+        let summary = |properties: u32, property: u32| {
+            // Header with the first section at offset 48, holding one 32-bit property
+            let mut data = vec![0u8; 44];
+            data.extend(48u32.to_le_bytes());
+            data.extend(0u32.to_le_bytes());
+            data.extend(properties.to_le_bytes());
+            data.extend(property.to_le_bytes());
+            data.extend(16u32.to_le_bytes());
+            data.extend(VT_I4.to_le_bytes());
+            data.extend(7u32.to_le_bytes());
+            let mut container = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+            container.create_stream("/\u{5}SummaryInformation").unwrap().write_all(&data).unwrap();
+            read_summary_page_count(&mut container)
+        };
+        assert_eq!(summary(1, PIDSI_PAGECOUNT), Some(7));
+        // A corrupt property count stops at the end of the stream
+        assert_eq!(summary(u32::MAX, PIDSI_PAGECOUNT + 1), None);
+    }
+}
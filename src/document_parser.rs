@@ -1,16 +1,31 @@
 use std::path::Path;
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufReader, Read};
 
 use anyhow::{Result, Context};
-use calamine::{Reader, open_workbook, Xlsx, Data};
+use calamine::{Reader, open_workbook_auto, Sheets, Data, SheetVisible};
 use crate::fast_pdf_extractor::FastPdfExtractor;
+use crate::pdf_outline::PdfOutline;
+use crate::pdf_metadata::PdfMetadata;
+use crate::doc_parser::{is_ole2_document, DocDocument};
+use crate::odf_parser::{is_odf_text, OdtDocument};
+use crate::excel_parser::{
+    read_excel_range, read_excel_rows, read_excel_sheets, sheet_title, sheet_visibility, used_area,
+    visibility_label, CellFormatter, CellRange, FormulaMode, WorkbookFormats,
+};
+use crate::structured_output::OutputFormat;
+use crate::docx_parser::{
+    notes_to_markdown, read_docx_app_page_count, scan_docx_page_layout,
+    DocxMarkdownRenderer, DocxNote, DocxPackage, ReviewMode,
+};
 use crate::shared_utils::{parse_pages_parameter, validate_file_path, get_or_cache_pdf_content};
 use crate::powerpoint_parser::{
     process_powerpoint_with_slides, 
     get_powerpoint_slide_info,
 };
 use crate::cache_system::CacheManager;
+use crate::markdown_table::text_table;
+use crate::csv_parser::{extract_csv_content, read_csv_range, read_csv_rows, read_csv_sheet, CSV_CACHE_MANAGER};
 use crate::impl_cacheable_content;
 
 /// Result of document processing with page-based support
@@ -24,6 +39,23 @@ pub struct DocumentProcessingResult {
     pub error: Option<String>,
 }
 
+/// Options that change how a document is read, beyond page selection
+#[derive(Debug, Clone, Default)]
+pub struct DocumentReadOptions {
+    /// How tracked changes and comments are shown in DOCX files
+    pub review_mode: ReviewMode,
+    /// Spreadsheets and CSV/TSV files: an A1 range such as `Sheet2!B3:H200`, or a defined name
+    pub range: Option<String>,
+    /// Spreadsheets and CSV/TSV files: a window of worksheet rows such as `1-500`; the header row repeats
+    pub rows: Option<String>,
+    /// Spreadsheets only: whether cached values, formulas or both are shown
+    pub formula_mode: FormulaMode,
+    /// Spreadsheets and CSV/TSV files: a markdown table, or typed CSV or JSON
+    pub output_format: OutputFormat,
+    /// PDFs only: an outline title or path such as `Chapter 7 > Error Codes`, read instead of pages
+    pub section: Option<String>,
+}
+
 /// Simplified result for document page information
 #[derive(Debug, Clone)]
 pub struct DocumentPageInfoResult {
@@ -103,6 +135,10 @@ pub struct ExcelCache {
     pub char_indices: Vec<usize>,
     pub total_sheets: Option<usize>,
     pub sheet_names: Vec<String>,
+    /// Used area of each sheet, `None` for empty sheets
+    pub sheet_dimensions: Vec<Option<CellRange>>,
+    /// Whether each sheet is visible, hidden or very hidden
+    pub sheet_visibility: Vec<SheetVisible>,
 }
 
 // Implement CacheableContent for ExcelCache
@@ -113,25 +149,40 @@ lazy_static::lazy_static! {
     pub static ref EXCEL_CACHE_MANAGER: CacheManager<ExcelCache> = CacheManager::new();
 }
 
+/// Open a workbook with the calamine reader matching its format (xlsx, xlsm, xlsb, xls, xla)
+pub fn open_excel_workbook(file_path: &str) -> Result<Sheets<BufReader<File>>> {
+    open_workbook_auto(file_path)
+        .with_context(|| format!("Failed to open Excel file: {}", file_path))
+}
+
 /// Function to extract Excel content and create cache
 fn extract_excel_content(file_path: &str) -> Result<ExcelCache> {
-    let mut workbook: Xlsx<_> = open_workbook(file_path)
-        .with_context(|| format!("Failed to open Excel file: {}", file_path))?;
+    let mut workbook = open_excel_workbook(file_path)?;
+    let formats = WorkbookFormats::open(file_path);
     
     let sheet_names = workbook.sheet_names().to_owned();
+    let sheet_visibility = sheet_visibility(&workbook);
     let total_sheets = sheet_names.len();
     
     let mut markdown = format!("# {}\n\n", Path::new(file_path).file_name().unwrap().to_string_lossy());
+    let mut sheet_dimensions = Vec::with_capacity(total_sheets);
     
-    // Process each sheet
+    // Process each sheet; hidden sheets are only read when selected by page
     for (index, sheet_name) in sheet_names.iter().enumerate() {
-        markdown.push_str(&format!("## Sheet {}: {}\n\n", index + 1, sheet_name));
+        let visible = sheet_visibility[index];
+        markdown.push_str(&format!("## Sheet {}: {}\n\n", index + 1, sheet_title(sheet_name, visible)));
         
         if let Ok(range) = workbook.worksheet_range(sheet_name.as_str()) {
-            markdown.push_str(&range_to_markdown_table(&range));
+            if visible == SheetVisible::Visible {
+                markdown.push_str(&range_to_markdown_table(&range, &formats.sheet(sheet_name)));
+            } else {
+                markdown.push_str(&format!("*Hidden sheet not shown; select page {} to read it*", index + 1));
+            }
             markdown.push_str("\n\n");
+            sheet_dimensions.push(used_area(&range));
         } else {
             markdown.push_str("*Sheet could not be read*\n\n");
+            sheet_dimensions.push(None);
         }
     }
     
@@ -150,24 +201,28 @@ fn extract_excel_content(file_path: &str) -> Result<ExcelCache> {
         char_indices,
         total_sheets: Some(total_sheets),
         sheet_names,
+        sheet_dimensions,
+        sheet_visibility,
     })
 }
 
 /// Function to extract specific sheets from Excel
 fn extract_excel_sheets(file_path: &str, sheet_numbers: &[usize]) -> Result<String> {
-    let mut workbook: Xlsx<_> = open_workbook(file_path)
-        .with_context(|| format!("Failed to open Excel file: {}", file_path))?;
+    let mut workbook = open_excel_workbook(file_path)?;
+    let formats = WorkbookFormats::open(file_path);
     
     let sheet_names = workbook.sheet_names().to_owned();
+    let visibility = sheet_visibility(&workbook);
     let mut markdown = format!("# {}\n\n", Path::new(file_path).file_name().unwrap().to_string_lossy());
     
     for &sheet_index in sheet_numbers {
         if sheet_index > 0 && sheet_index <= sheet_names.len() {
             let sheet_name = &sheet_names[sheet_index - 1];
-            markdown.push_str(&format!("## Sheet {}: {}\n\n", sheet_index, sheet_name));
+            let title = sheet_title(sheet_name, visibility[sheet_index - 1]);
+            markdown.push_str(&format!("## Sheet {}: {}\n\n", sheet_index, title));
             
             if let Ok(range) = workbook.worksheet_range(sheet_name.as_str()) {
-                markdown.push_str(&range_to_markdown_table(&range));
+                markdown.push_str(&range_to_markdown_table(&range, &formats.sheet(sheet_name)));
                 markdown.push_str("\n\n");
             } else {
                 markdown.push_str("*Sheet could not be read*\n\n");
@@ -184,6 +239,8 @@ pub struct DocxCache {
     pub content: String,
     pub char_indices: Vec<usize>,
     pub total_pages: Option<usize>,
+    /// Page count saved by the authoring application, which may differ from `total_pages`
+    pub saved_pages: Option<usize>,
 }
 
 // Implement CacheableContent for DocxCache
@@ -198,7 +255,7 @@ lazy_static::lazy_static! {
 /// Function to extract DOCX content and create cache
 fn extract_docx_content(file_path: &str) -> Result<DocxCache> {
     let markdown = read_docx_to_markdown(file_path)?;
-    let total_pages = get_docx_page_count(file_path).unwrap_or(1);
+    let (total_pages, saved_pages) = get_docx_page_count(file_path).unwrap_or((1, None));
     
     // Pre-compute character byte indices for efficient slicing
     let mut char_indices = Vec::new();
@@ -214,14 +271,113 @@ fn extract_docx_content(file_path: &str) -> Result<DocxCache> {
         content: markdown,
         char_indices,
         total_pages: Some(total_pages),
+        saved_pages,
     })
 }
 
-/// Function to extract specific pages from DOCX (currently returns full content)
-fn extract_docx_pages(file_path: &str, _page_numbers: &[usize]) -> Result<String> {
-    // For now, DOCX doesn't support true page-level extraction
-    // Return the full content
-    read_docx_to_markdown(file_path)
+/// Function to extract specific pages from DOCX
+fn extract_docx_pages(file_path: &str, page_numbers: &[usize], review_mode: ReviewMode) -> Result<String> {
+    let buffer = read_docx_buffer(file_path)?;
+    if let Some(document) = read_paged_document(&buffer) {
+        let document = document?;
+        let mut markdown = format!("# {}\n\n", Path::new(file_path).file_name().unwrap().to_string_lossy());
+        push_page_sections(&mut markdown, &document.pages, page_numbers, &document.footnotes, &document.endnotes);
+        return Ok(markdown);
+    }
+    
+    let package = DocxPackage::read(&buffer)
+        .map_err(|e| anyhow::anyhow!("Failed to parse DOCX content: {}", e))?;
+    let layout = scan_docx_page_layout(&buffer)?;
+    
+    let pages = DocxMarkdownRenderer::new(&package.docx)
+        .with_hyperlinks(&package.hyperlinks)
+        .with_page_layout(&layout)
+        .review_mode(review_mode)
+        .render_pages(layout.total_pages);
+    
+    let mut markdown = format!("# {}\n\n", Path::new(file_path).file_name().unwrap().to_string_lossy());
+    push_page_sections(&mut markdown, &pages, page_numbers, &package.footnotes, &package.endnotes);
+    Ok(markdown)
+}
+
+/// A Word 97-2003 or OpenDocument text file, already split into pages
+struct PagedDocument {
+    pages: Vec<String>,
+    headers: Vec<String>,
+    footers: Vec<String>,
+    footnotes: Vec<DocxNote>,
+    endnotes: Vec<DocxNote>,
+    page_count: usize,
+    saved_page_count: Option<usize>,
+}
+
+/// Read word processing files that are not DOCX packages; `None` means the buffer is a DOCX file
+fn read_paged_document(buffer: &[u8]) -> Option<Result<PagedDocument>> {
+    if is_ole2_document(buffer) {
+        return Some(DocDocument::read(buffer)
+            .map(|document| PagedDocument {
+                page_count: document.page_count(),
+                saved_page_count: document.stored_page_count,
+                pages: document.pages,
+                headers: document.headers,
+                footers: document.footers,
+                footnotes: document.footnotes,
+                endnotes: document.endnotes,
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to parse DOC content: {}", e)));
+    }
+    if is_odf_text(buffer) {
+        return Some(OdtDocument::read(buffer)
+            .map(|document| PagedDocument {
+                page_count: document.page_count(),
+                saved_page_count: document.stored_page_count,
+                pages: document.pages,
+                headers: document.headers,
+                footers: document.footers,
+                footnotes: document.footnotes,
+                endnotes: document.endnotes,
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to parse ODT content: {}", e)));
+    }
+    None
+}
+
+/// Append the requested pages as `## Page N` sections, followed by the
+/// definitions of the notes referenced on them
+fn push_page_sections(
+    markdown: &mut String,
+    pages: &[String],
+    page_numbers: &[usize],
+    footnotes: &[DocxNote],
+    endnotes: &[DocxNote],
+) {
+    for &page_number in page_numbers {
+        markdown.push_str(&format!("## Page {}\n\n", page_number));
+        match page_number.checked_sub(1).and_then(|index| pages.get(index)) {
+            Some(content) if !content.is_empty() => {
+                markdown.push_str(content);
+                markdown.push_str("\n\n");
+            }
+            _ => markdown.push_str("*No content found on this page*\n\n"),
+        }
+    }
+    
+    // Only include definitions for notes referenced on the returned pages
+    let referenced = |note: &&DocxNote| markdown.contains(&format!("[^{}]", note.label));
+    let footnotes: Vec<_> = footnotes.iter().filter(referenced).cloned().collect();
+    let endnotes: Vec<_> = endnotes.iter().filter(referenced).cloned().collect();
+    push_docx_notes(markdown, &footnotes, &endnotes);
+}
+
+/// Read the raw bytes of a DOCX file
+fn read_docx_buffer(file_path: &str) -> Result<Vec<u8>> {
+    let mut file = File::open(file_path)
+        .with_context(|| format!("Failed to open DOCX file: {}", file_path))?;
+    
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)
+        .with_context(|| "Failed to read DOCX file content")?;
+    Ok(buffer)
 }
 
 /// Read Excel file and convert to markdown
@@ -229,17 +385,21 @@ pub fn read_excel_to_markdown(file_path: &str) -> Result<String> {
     let mut markdown = format!("# {}\n\n", Path::new(file_path).file_name().unwrap().to_string_lossy());
     
     // Open the workbook
-    let mut workbook: Xlsx<_> = open_workbook(file_path)
-        .with_context(|| format!("Failed to open Excel file: {}", file_path))?;
+    let mut workbook = open_excel_workbook(file_path)?;
+    let formats = WorkbookFormats::open(file_path);
     
-    // Process each sheet
-    for sheet_name in workbook.sheet_names().to_owned() {
+    // Process each visible sheet
+    let visibility = sheet_visibility(&workbook);
+    for (sheet_name, visible) in workbook.sheet_names().to_owned().into_iter().zip(visibility) {
+        if visible != SheetVisible::Visible {
+            continue;
+        }
         // Add sheet as a header
         markdown.push_str(&format!("## Sheet: {}\n\n", sheet_name));
         
         // Read the sheet data
         if let Ok(range) = workbook.worksheet_range(&sheet_name) {
-            markdown.push_str(&range_to_markdown_table(&range));
+            markdown.push_str(&range_to_markdown_table(&range, &formats.sheet(&sheet_name)));
             markdown.push_str("\n\n");
         }
     }
@@ -247,72 +407,67 @@ pub fn read_excel_to_markdown(file_path: &str) -> Result<String> {
     Ok(markdown)
 }
 
-/// Convert Excel range to markdown table
-pub fn range_to_markdown_table(range: &calamine::Range<Data>) -> String {
-    let height = range.height();
-    if height == 0 {
-        return "Empty sheet".to_string();
+/// Convert Excel range to markdown table, showing cells with their number formats
+pub fn range_to_markdown_table(range: &calamine::Range<Data>, formatter: &CellFormatter) -> String {
+    match (range.start(), range.end()) {
+        (Some(start), Some(end)) if range.height() > 0 => cells_to_markdown_table(range, formatter, start, end),
+        _ => "Empty sheet".to_string(),
     }
-    
-    let width = range.width();
-    let mut table = String::new();
-    
-    // Header row
-    table.push_str("| ");
-    for col in 0..width {
-        if let Some(cell) = range.get_value((0, col as u32)) {
-            table.push_str(&format!("{} | ", cell));
-        } else {
-            table.push_str(" | ");
-        }
+}
+
+/// Convert the cells between two corners of a range (absolute, inclusive) to a
+/// markdown table, leaving out hidden rows and columns
+pub fn cells_to_markdown_table(
+    range: &calamine::Range<Data>,
+    formatter: &CellFormatter,
+    start: (u32, u32),
+    end: (u32, u32),
+) -> String {
+    let mut table = text_table(formatter.format_rows(range, start, end));
+    if table.is_empty() {
+        table.push_str("*No data in this range*");
     }
-    table.push_str("\n");
-    
-    // Separator row
-    table.push_str("| ");
-    for _ in 0..width {
-        table.push_str("--- | ");
+    if let Some(note) = formatter.hidden_note(start, end) {
+        table.push_str(&format!("\n\n{}", note));
     }
-    table.push_str("\n");
-    
-    // Data rows
-    for row in 1..height {
-        table.push_str("| ");
-        for col in 0..width {
-            if let Some(cell) = range.get_value((row as u32, col as u32)) {
-                table.push_str(&format!("{} | ", cell));
-            } else {
-                table.push_str(" | ");
-            }
-        }
-        table.push_str("\n");
+    if let Some(annotations) = formatter.annotations_note(&[(start.0, end.0)], (start.1, end.1)) {
+        table.push_str(&format!("\n\n{}", annotations));
     }
-    
     table
 }
 
 /// Read DOCX file and convert to markdown
 pub fn read_docx_to_markdown(file_path: &str) -> Result<String> {
+    read_docx_to_markdown_with_review_mode(file_path, ReviewMode::Accept)
+}
+
+/// Read DOCX file and convert to markdown, showing tracked changes as selected
+pub fn read_docx_to_markdown_with_review_mode(file_path: &str, review_mode: ReviewMode) -> Result<String> {
     let mut markdown = format!("# {}\n\n", Path::new(file_path).file_name().unwrap().to_string_lossy());
+    let buffer = read_docx_buffer(file_path)?;
     
-    // Read the file into a buffer
-    let mut file = File::open(file_path)
-        .with_context(|| format!("Failed to open DOCX file: {}", file_path))?;
-    
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)
-        .with_context(|| "Failed to read DOCX file content")?;
+    // Word 97-2003 and OpenDocument files have no tracked changes to review
+    if let Some(document) = read_paged_document(&buffer) {
+        let document = document?;
+        push_word_sections(
+            &mut markdown,
+            &document.pages.join("\n\n"),
+            &document.headers,
+            &document.footers,
+        );
+        push_docx_notes(&mut markdown, &document.footnotes, &document.endnotes);
+        return Ok(markdown);
+    }
     
-    // Use the docx-rs crate to parse the document
-    // Note: The docx-rs API in the linter errors requires adjustments
-    match docx_rs::read_docx(&buffer) {
-        Ok(doc) => {
-            // Simple text extraction for now - we'll improve this in a later step
-            // This is a simplified version as the detailed parsing has linter errors
-            let text = extract_text_from_docx(&doc);
-            markdown.push_str("## Content\n\n");
-            markdown.push_str(&text);
-            markdown.push_str("\n\n");
+    // Parse the package, including the headers, footers and notes docx-rs does not expose
+    match DocxPackage::read(&buffer) {
+        Ok(package) => {
+            let text = DocxMarkdownRenderer::new(&package.docx)
+                .with_hyperlinks(&package.hyperlinks)
+                .review_mode(review_mode)
+                .render();
+            push_word_sections(&mut markdown, &text, &package.headers, &package.footers);
+            push_docx_notes(&mut markdown, &package.footnotes, &package.endnotes);
         },
         Err(e) => {
             return Err(anyhow::anyhow!("Failed to parse DOCX content: {}", e));
@@ -322,11 +477,40 @@ pub fn read_docx_to_markdown(file_path: &str) -> Result<String> {
     Ok(markdown)
 }
 
-/// Extract text from DOCX document (simplified version)
-fn extract_text_from_docx(_doc: &docx_rs::Docx) -> String {
-    // This is a simplified placeholder implementation
-    // We'll need to implement a proper text extraction based on the docx-rs API
-    "[DOCX content extraction - implementation needed based on docx-rs API]".to_string()
+/// Append the body text and any headers and footers of a Word document
+fn push_word_sections(markdown: &mut String, text: &str, headers: &[String], footers: &[String]) {
+    markdown.push_str("## Content\n\n");
+    markdown.push_str(text);
+    markdown.push_str("\n\n");
+    
+    if !headers.is_empty() {
+        markdown.push_str("## Headers\n\n");
+        markdown.push_str(&headers.join("\n\n"));
+        markdown.push_str("\n\n");
+    }
+    if !footers.is_empty() {
+        markdown.push_str("## Footers\n\n");
+        markdown.push_str(&footers.join("\n\n"));
+        markdown.push_str("\n\n");
+    }
+}
+
+/// Append footnote and endnote definitions as markdown footnotes
+fn push_docx_notes(
+    markdown: &mut String,
+    footnotes: &[DocxNote],
+    endnotes: &[DocxNote],
+) {
+    if !footnotes.is_empty() {
+        markdown.push_str("## Footnotes\n\n");
+        markdown.push_str(&notes_to_markdown(footnotes));
+        markdown.push_str("\n\n");
+    }
+    if !endnotes.is_empty() {
+        markdown.push_str("## Endnotes\n\n");
+        markdown.push_str(&notes_to_markdown(endnotes));
+        markdown.push_str("\n\n");
+    }
 }
 
 /// Process a document based on its file extension with page-based selection
@@ -334,6 +518,16 @@ fn extract_text_from_docx(_doc: &docx_rs::Docx) -> String {
 pub fn process_document_with_pages(
     resolved_file_path: &str,
     pages: Option<String>,
+) -> DocumentProcessingResult {
+    process_document_with_options(resolved_file_path, pages, &DocumentReadOptions::default())
+}
+
+/// Process a document with page-based selection and read options
+/// Expects a resolved file path
+pub fn process_document_with_options(
+    resolved_file_path: &str,
+    pages: Option<String>,
+    options: &DocumentReadOptions,
 ) -> DocumentProcessingResult {
     log::debug!("🔍 process_document_with_pages: ENTRY - file_path={}, pages={:?}", 
                resolved_file_path, pages);
@@ -357,20 +551,85 @@ pub fn process_document_with_pages(
     };
     
     log::debug!("🔍 process_document_with_pages: Processing file type: '{}'", file_type);
+    let is_spreadsheet = matches!(file_type.as_str(), "xlsx" | "xlsm" | "xlsb" | "xls" | "xla" | "ods");
+    let is_delimited = matches!(file_type.as_str(), "csv" | "tsv");
+    for (parameter, value) in [("range", &options.range), ("rows", &options.rows)] {
+        if value.is_some() && !is_spreadsheet && !is_delimited {
+            return DocumentProcessingResult::error(
+                file_path_string,
+                format!("The {} parameter is only supported for spreadsheets and CSV/TSV files, not {} files", parameter, file_type),
+            );
+        }
+    }
+    if options.formula_mode != FormulaMode::Values && !is_spreadsheet {
+        return DocumentProcessingResult::error(
+            file_path_string,
+            format!("The formula_mode parameter is only supported for spreadsheets, not {} files", file_type),
+        );
+    }
+    if options.output_format != OutputFormat::Markdown {
+        if !is_spreadsheet && !is_delimited {
+            return DocumentProcessingResult::error(
+                file_path_string,
+                format!("The output_format parameter is only supported for spreadsheets and CSV/TSV files, not {} files", file_type),
+            );
+        }
+        if options.formula_mode != FormulaMode::Values {
+            return DocumentProcessingResult::error(
+                file_path_string,
+                format!("formula_mode {} cannot be combined with output_format {}", options.formula_mode, options.output_format),
+            );
+        }
+    }
+    if options.range.is_some() && options.rows.is_some() {
+        return DocumentProcessingResult::error(
+            file_path_string,
+            "Use either the range or the rows parameter, not both".to_string(),
+        );
+    }
+    if options.section.is_some() {
+        if file_type != "pdf" {
+            return DocumentProcessingResult::error(
+                file_path_string,
+                format!("The section parameter is only supported for PDF files, not {} files", file_type),
+            );
+        }
+        if pages != "all" {
+            return DocumentProcessingResult::error(
+                file_path_string,
+                "Use either the pages or the section parameter, not both".to_string(),
+            );
+        }
+    }
+    
     match file_type.as_str() {
-        "xlsx" | "xls" => {
+        "xlsx" | "xlsm" | "xlsb" | "xls" | "xla" | "ods" => {
             log::debug!("🔍 process_document_with_pages: Calling process_excel_with_pages");
-            process_excel_with_pages(resolved_file_path, &pages)
+            match (&options.range, &options.rows) {
+                (Some(range), _) => process_excel_range(resolved_file_path, &pages, range, options),
+                (None, Some(rows)) => process_excel_rows(resolved_file_path, &pages, rows, options),
+                (None, None) if options.formula_mode != FormulaMode::Values || options.output_format != OutputFormat::Markdown => {
+                    process_excel_sheets(resolved_file_path, &pages, options)
+                }
+                (None, None) => process_excel_with_pages(resolved_file_path, &pages),
+            }
         },
-        "pdf" => {
-            log::debug!("🔍 process_document_with_pages: Calling process_pdf_with_pages");
-            process_pdf_with_pages(resolved_file_path, &pages)
+        "csv" | "tsv" => {
+            log::debug!("🔍 process_document_with_pages: Calling process_csv_with_pages");
+            process_csv_with_pages(resolved_file_path, &pages, options)
+        },
+        "pdf" => match &options.section {
+            Some(section) => process_pdf_section(resolved_file_path, section),
+            None => {
+                log::debug!("🔍 process_document_with_pages: Calling process_pdf_with_pages");
+                process_pdf_with_pages(resolved_file_path, &pages)
+            }
         },
-        "docx" | "doc" => {
+        "docx" | "doc" | "odt" => {
             log::debug!("🔍 process_document_with_pages: Calling process_docx_with_pages");
-            process_docx_with_pages(resolved_file_path, &pages)
+            process_docx_with_pages(resolved_file_path, &pages, options.review_mode)
         },
-        "pptx" | "ppt" => {
+        "pptx" | "ppt" | "odp" => {
             log::debug!("🔍 process_document_with_pages: Calling process_powerpoint_with_pages_wrapper");
             process_powerpoint_with_pages_wrapper(resolved_file_path, &pages)
         },
@@ -436,6 +695,147 @@ fn process_excel_with_pages(file_path: &str, pages: &str) -> DocumentProcessingR
     )
 }
 
+/// Process a cell range of an Excel file; unqualified ranges are read from the selected sheets
+fn process_excel_range(file_path: &str, pages: &str, range: &str, options: &DocumentReadOptions) -> DocumentProcessingResult {
+    process_excel_selection(file_path, pages, &format!("range {}", range), |sheets| {
+        read_excel_range(file_path, range, sheets, options.formula_mode, options.output_format)
+            .map_err(|e| format!("Invalid range parameter: {}", e))
+    })
+}
+
+/// Process a window of rows from each selected sheet of an Excel file
+fn process_excel_rows(file_path: &str, pages: &str, rows: &str, options: &DocumentReadOptions) -> DocumentProcessingResult {
+    process_excel_selection(file_path, pages, &format!("rows {}", rows), |sheets| {
+        read_excel_rows(file_path, rows, sheets, options.formula_mode, options.output_format)
+            .map_err(|e| format!("Invalid rows parameter: {}", e))
+    })
+}
+
+/// Process the selected sheets of an Excel file with their formulas or as typed
+/// CSV or JSON; plain markdown reads go through the cached path of `process_excel_with_pages`
+fn process_excel_sheets(file_path: &str, pages: &str, options: &DocumentReadOptions) -> DocumentProcessingResult {
+    let selection = if options.output_format != OutputFormat::Markdown {
+        format!("output_format {}", options.output_format)
+    } else {
+        format!("formula_mode {}", options.formula_mode)
+    };
+    process_excel_selection(file_path, pages, &selection, |sheets| {
+        read_excel_sheets(file_path, sheets, options.formula_mode, options.output_format)
+            .map_err(|e| format!("Failed to read sheets: {}", e))
+    })
+}
+
+/// Resolve the selected sheets, then read part of each with `read_selection`
+fn process_excel_selection(
+    file_path: &str,
+    pages: &str,
+    selection: &str,
+    read_selection: impl FnOnce(&[usize]) -> Result<(String, Vec<usize>), String>,
+) -> DocumentProcessingResult {
+    let file_path_string = file_path.to_string();
+    
+    let excel_cache = match EXCEL_CACHE_MANAGER.get_or_cache(file_path, extract_excel_content) {
+        Ok(cache) => cache,
+        Err(e) => return DocumentProcessingResult::error(
+            file_path_string,
+            format!("Failed to get Excel content: {}", e),
+        ),
+    };
+    let total_sheets = excel_cache.total_sheets.unwrap_or(0);
+    
+    let mut requested_sheet_indices = match parse_pages_parameter(pages, total_sheets) {
+        Ok(indices) => indices,
+        Err(e) => return DocumentProcessingResult::error(
+            file_path_string,
+            format!("Invalid pages parameter: {}", e),
+        ),
+    };
+    // Hidden sheets are only read when selected explicitly
+    if pages == "all" {
+        requested_sheet_indices.retain(|&sheet| {
+            excel_cache.sheet_visibility.get(sheet - 1) == Some(&SheetVisible::Visible)
+        });
+    }
+    
+    match read_selection(&requested_sheet_indices) {
+        Ok((content, returned_sheets)) => DocumentProcessingResult::success(
+            content,
+            Some(total_sheets),
+            format!("{} ({})", pages, selection),
+            returned_sheets,
+            file_path_string,
+        ),
+        Err(e) => DocumentProcessingResult::error(file_path_string, e),
+    }
+}
+
+/// Process a CSV or TSV file, which is read as a single sheet
+fn process_csv_with_pages(file_path: &str, pages: &str, options: &DocumentReadOptions) -> DocumentProcessingResult {
+    let file_path_string = file_path.to_string();
+    
+    let csv_cache = match CSV_CACHE_MANAGER.get_or_cache(file_path, extract_csv_content) {
+        Ok(cache) => cache,
+        Err(e) => return DocumentProcessingResult::error(
+            file_path_string,
+            format!("Failed to get CSV content: {}", e),
+        ),
+    };
+    
+    let requested_sheet_indices = match parse_pages_parameter(pages, 1) {
+        Ok(indices) => indices,
+        Err(e) => return DocumentProcessingResult::error(
+            file_path_string,
+            format!("Invalid pages parameter: {}", e),
+        ),
+    };
+    
+    let (content, requested_pages) = match (&options.range, &options.rows) {
+        (Some(range), _) => (
+            read_csv_range(file_path, range, options.output_format).map_err(|e| format!("Invalid range parameter: {}", e)),
+            format!("{} (range {})", pages, range),
+        ),
+        (None, Some(rows)) => (
+            read_csv_rows(file_path, rows, options.output_format).map_err(|e| format!("Invalid rows parameter: {}", e)),
+            format!("{} (rows {})", pages, rows),
+        ),
+        (None, None) if options.output_format != OutputFormat::Markdown => (
+            read_csv_sheet(file_path, options.output_format).map_err(|e| format!("Failed to read file: {}", e)),
+            format!("{} (output_format {})", pages, options.output_format),
+        ),
+        (None, None) => (Ok(csv_cache.content), pages.to_string()),
+    };
+    
+    match content {
+        Ok(content) => DocumentProcessingResult::success(
+            content,
+            csv_cache.total_pages,
+            requested_pages,
+            requested_sheet_indices,
+            file_path_string,
+        ),
+        Err(e) => DocumentProcessingResult::error(file_path_string, e),
+    }
+}
+
+/// Process the pages of a PDF spanned by an outline entry
+fn process_pdf_section(file_path: &str, section: &str) -> DocumentProcessingResult {
+    let found = PdfOutline::read(file_path).and_then(|outline| outline.find_section(section));
+    let section = match found {
+        Ok(section) => section,
+        Err(e) => return DocumentProcessingResult::error(
+            file_path.to_string(),
+            format!("Invalid section parameter: {}", e),
+        ),
+    };
+    
+    let pages = format!("{}-{}", section.first_page, section.last_page);
+    let mut result = process_pdf_with_pages(file_path, &pages);
+    if result.error.is_none() {
+        result.requested_pages = format!("{} (section {})", pages, section.path);
+    }
+    result
+}
+
 /// Process PDF file with specific pages
 fn process_pdf_with_pages(file_path: &str, pages: &str) -> DocumentProcessingResult {
     log::debug!("🔍 process_pdf_with_pages: ENTRY - file_path={}, pages={}", file_path, pages);
@@ -550,7 +950,7 @@ fn process_pdf_with_pages(file_path: &str, pages: &str) -> DocumentProcessingRes
 }
 
 /// Process DOCX file with specific pages
-fn process_docx_with_pages(file_path: &str, pages: &str) -> DocumentProcessingResult {
+fn process_docx_with_pages(file_path: &str, pages: &str, review_mode: ReviewMode) -> DocumentProcessingResult {
     let file_path_string = file_path.to_string();
     
     // Get or cache DOCX content
@@ -576,12 +976,20 @@ fn process_docx_with_pages(file_path: &str, pages: &str) -> DocumentProcessingRe
         ),
     };
     
-    // For DOCX, we currently return the full content regardless of page selection
-    // since true page-level extraction is not yet implemented
-    let content = if pages == "all" {
+    // The cache holds the accepted text; other review modes are rendered on demand
+    let content = if pages == "all" && review_mode == ReviewMode::Accept {
         docx_cache.content.clone()
+    } else if pages == "all" {
+        match read_docx_to_markdown_with_review_mode(file_path, review_mode) {
+            Ok(content) => content,
+            Err(e) => return DocumentProcessingResult::error(
+                file_path_string,
+                format!("Failed to read DOCX content: {}", e),
+            ),
+        }
     } else {
-        match DOCX_CACHE_MANAGER.extract_units(&docx_cache, &requested_page_indices, file_path, extract_docx_pages) {
+        let extract_pages = |path: &str, page_numbers: &[usize]| extract_docx_pages(path, page_numbers, review_mode);
+        match DOCX_CACHE_MANAGER.extract_units(&docx_cache, &requested_page_indices, file_path, extract_pages) {
             Ok(content) => content,
             Err(e) => return DocumentProcessingResult::error(
                 file_path_string,
@@ -639,14 +1047,30 @@ pub fn get_document_page_info(resolved_file_path: &str) -> DocumentPageInfoResul
     };
     
     match file_type.as_str() {
-        "xlsx" | "xls" => {
+        "xlsx" | "xlsm" | "xlsb" | "xls" | "xla" | "ods" => {
             // Use Excel cache to get sheet information
             match EXCEL_CACHE_MANAGER.get_or_cache(resolved_file_path, extract_excel_content) {
                 Ok(excel_cache) => {
                     let total_sheets = excel_cache.total_sheets.unwrap_or(0);
                     let sheet_list = excel_cache.sheet_names.iter()
                         .enumerate()
-                        .map(|(i, name)| format!("  {}: {}", i + 1, name))
+                        .map(|(i, name)| {
+                            let name = match excel_cache.sheet_visibility.get(i).and_then(|&visible| visibility_label(visible)) {
+                                Some(label) => format!("{} [{}]", name, label),
+                                None => name.clone(),
+                            };
+                            match excel_cache.sheet_dimensions.get(i).cloned().flatten() {
+                                Some(area) => format!(
+                                    "  {}: {} ({} rows x {} columns, {})",
+                                    i + 1,
+                                    name,
+                                    area.end.0 - area.start.0 + 1,
+                                    area.end.1 - area.start.1 + 1,
+                                    area,
+                                ),
+                                None => format!("  {}: {} (empty)", i + 1, name),
+                            }
+                        })
                         .collect::<Vec<_>>()
                         .join("\n");
                     
@@ -662,15 +1086,33 @@ pub fn get_document_page_info(resolved_file_path: &str) -> DocumentPageInfoResul
                 ),
             }
         },
+        "csv" | "tsv" => {
+            match CSV_CACHE_MANAGER.get_or_cache(resolved_file_path, extract_csv_content) {
+                Ok(csv_cache) => DocumentPageInfoResult::success(
+                    file_path_string,
+                    csv_cache.total_pages,
+                    format!("{} file with 1 sheet ({})", file_type.to_uppercase(), csv_cache.summary),
+                ),
+                Err(e) => DocumentPageInfoResult::error(
+                    file_path_string,
+                    format!("Failed to analyze {} file: {}", file_type.to_uppercase(), e),
+                ),
+            }
+        },
         "pdf" => {
             // Use the cache to get PDF content and page count
             match get_or_cache_pdf_content(resolved_file_path) {
                 Ok(pdf_cache) => {
                     if let Some(page_count) = pdf_cache.total_pages {
+                        let mut page_info = format!("PDF file with {} pages", page_count);
+                        match PdfMetadata::read(resolved_file_path) {
+                            Ok(metadata) => page_info.push_str(&format!("\n\n{}", metadata.to_text())),
+                            Err(e) => log::warn!("Failed to read PDF metadata: {}", e),
+                        }
                         DocumentPageInfoResult::success(
                             file_path_string,
                             Some(page_count),
-                            format!("PDF file with {} pages", page_count),
+                            page_info,
                         )
                     } else {
                         DocumentPageInfoResult::error(
@@ -685,16 +1127,19 @@ pub fn get_document_page_info(resolved_file_path: &str) -> DocumentPageInfoResul
                 ),
             }
         },
-        "docx" | "doc" => {
+        "docx" | "doc" | "odt" => {
             // Use DOCX cache to get page information
             match DOCX_CACHE_MANAGER.get_or_cache(resolved_file_path, extract_docx_content) {
                 Ok(docx_cache) => {
                     let page_count = docx_cache.total_pages.unwrap_or(1);
-                    DocumentPageInfoResult::success(
-                        file_path_string,
-                        Some(page_count),
-                        format!("DOCX file with {} estimated pages", page_count),
-                    )
+                    let mut page_info = format!("{} file with {} pages", file_type.to_uppercase(), page_count);
+                    if let Some(saved_pages) = docx_cache.saved_pages.filter(|&saved| saved != page_count) {
+                        page_info.push_str(&format!(
+                            " (split on page and section breaks; the file's own estimate is {} pages)",
+                            saved_pages
+                        ));
+                    }
+                    DocumentPageInfoResult::success(file_path_string, Some(page_count), page_info)
                 },
                 Err(e) => {
                     log::warn!("Failed to get DOCX content: {}", e);
@@ -706,7 +1151,7 @@ pub fn get_document_page_info(resolved_file_path: &str) -> DocumentPageInfoResul
                 }
             }
         },
-        "pptx" | "ppt" => {
+        "pptx" | "ppt" | "odp" => {
             let ppt_result = get_powerpoint_slide_info(resolved_file_path);
             
             // Convert PowerPointPageInfoResult to DocumentPageInfoResult
@@ -727,42 +1172,29 @@ pub fn get_document_page_info(resolved_file_path: &str) -> DocumentPageInfoResul
     }
 }
 
-/// Get the actual page count for a DOCX file
-fn get_docx_page_count(file_path: &str) -> Result<usize> {
-    use std::fs::File;
-    use std::io::Read;
-    
-    // Read the file into a buffer
-    let mut file = File::open(file_path)
-        .with_context(|| format!("Failed to open DOCX file: {}", file_path))?;
-    
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)
-        .with_context(|| "Failed to read DOCX file content")?;
+/// Get the page count for a DOCX file, with the count saved by the authoring application
+///
+/// Pages are split on the page and section breaks found in the document body, so the
+/// count matches the pages that can be read. The `<Pages>` count saved in
+/// `docProps/app.xml` comes from the application's own layout and is returned
+/// separately as an estimate; Word 97-2003 files save it in their SummaryInformation
+/// stream, and OpenDocument text files in `meta.xml`.
+fn get_docx_page_count(file_path: &str) -> Result<(usize, Option<usize>)> {
+    let buffer = read_docx_buffer(file_path)?;
+    if let Some(document) = read_paged_document(&buffer) {
+        return Ok(document.map(|document| (document.page_count, document.saved_page_count)).unwrap_or((1, None)));
+    }
     
-    // Use the docx-rs crate to parse the document
-    match docx_rs::read_docx(&buffer) {
-        Ok(docx) => {
-            // Count paragraphs as a rough estimate for page count
-            let paragraph_count = docx.document.children.iter()
-                .filter(|child| matches!(child, docx_rs::DocumentChild::Paragraph(_)))
-                .count();
-            
-            // Rough heuristic: assume 25-30 paragraphs per page for typical documents
-            let estimated_pages = if paragraph_count > 25 {
-                (paragraph_count / 25).max(1)
-            } else {
-                1
-            };
-            
-            Ok(estimated_pages)
-        },
+    let break_pages = match scan_docx_page_layout(&buffer) {
+        Ok(layout) => layout.total_pages,
         Err(e) => {
-            // If we can't parse the document structure, fall back to treating it as 1 page
-            log::warn!("Failed to parse DOCX structure for page counting: {}", e);
-            Ok(1)
+            // If we can't read the document structure, fall back to treating it as 1 page
+            log::warn!("Failed to scan DOCX page breaks: {}", e);
+            1
         }
-    }
+    };
+    
+    Ok((break_pages, read_docx_app_page_count(&buffer)))
 }
 
 #[cfg(test)]
@@ -823,7 +1255,262 @@ mod tests {
         
         let result = get_docx_page_count(temp_file.path().to_str().unwrap());
         // Should return Ok(1) as fallback for invalid files
-        assert_eq!(result.unwrap(), 1);
+        assert_eq!(result.unwrap(), (1, None));
+    }
+
+    #[test]
+    fn test_read_docx_to_markdown_extracts_body_text() {
+        use docx_rs::{Docx, Paragraph, Run};
+        use tempfile::NamedTempFile;
+        
+        let temp_file = NamedTempFile::with_suffix(".docx").unwrap();
+        Docx::new()
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Quarterly report")))
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Revenue grew by 12%")))
+            .build()
+            .pack(temp_file.reopen().unwrap())
+            .unwrap();
+        
+        let markdown = read_docx_to_markdown(temp_file.path().to_str().unwrap()).unwrap();
+        assert!(markdown.contains("## Content"));
+        assert!(markdown.contains("Quarterly report\n\nRevenue grew by 12%"));
+        assert!(!markdown.contains("implementation needed"));
+    }
+
+    #[test]
+    fn test_process_docx_with_pages_splits_on_page_breaks() {
+        use docx_rs::{BreakType, Docx, Paragraph, Run};
+        use tempfile::NamedTempFile;
+        
+        let temp_file = NamedTempFile::with_suffix(".docx").unwrap();
+        Docx::new()
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Introduction")))
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Chapter one").add_break(BreakType::Page)))
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Chapter two")))
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Appendix")).page_break_before(true))
+            .build()
+            .pack(temp_file.reopen().unwrap())
+            .unwrap();
+        let file_path = temp_file.path().to_str().unwrap();
+        
+        let info = get_document_page_info(file_path);
+        assert_eq!(info.total_pages, Some(3));
+        
+        let result = process_docx_with_pages(file_path, "2", ReviewMode::Accept);
+        assert!(result.error.is_none());
+        assert_eq!(result.returned_pages, vec![2]);
+        assert!(result.content.contains("## Page 2\n\nChapter two"));
+        assert!(!result.content.contains("Introduction"));
+        assert!(!result.content.contains("Appendix"));
+        
+        let result = process_docx_with_pages(file_path, "1,3", ReviewMode::Accept);
+        assert!(result.content.contains("## Page 1\n\nIntroduction\n\nChapter one"));
+        assert!(result.content.contains("## Page 3\n\nAppendix"));
+    }
+
+    #[test]
+    fn test_docx_page_count_ignores_saved_estimate() {
+        use docx_rs::{BreakType, Docx, Paragraph, Run};
+        use tempfile::NamedTempFile;
+        
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        Docx::new()
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("First").add_break(BreakType::Page)))
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Second")))
+            .build()
+            .pack(&mut buffer)
+            .unwrap();
+        let app_xml = "<Properties><Pages>12</Pages></Properties>";
+        let package = crate::docx_parser::replace_docx_part(&buffer.into_inner(), "docProps/app.xml", app_xml).unwrap();
+        let temp_file = NamedTempFile::with_suffix(".docx").unwrap();
+        std::fs::write(temp_file.path(), package).unwrap();
+        let file_path = temp_file.path().to_str().unwrap();
+        
+        // The saved count is only an estimate; pages that cannot be rendered are not reported
+        let info = get_document_page_info(file_path);
+        assert_eq!(info.total_pages, Some(2));
+        assert!(info.page_info.contains("estimate is 12 pages"), "{}", info.page_info);
+        
+        let result = process_docx_with_pages(file_path, "all", ReviewMode::Accept);
+        assert_eq!(result.returned_pages, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_process_document_with_pages_reads_macro_enabled_workbook() {
+        use tempfile::NamedTempFile;
+        
+        let temp_file = NamedTempFile::with_suffix(".xlsm").unwrap();
+        std::fs::copy("tests/test.xlsx", temp_file.path()).unwrap();
+        let file_path = temp_file.path().to_str().unwrap();
+        
+        let result = process_document_with_pages(file_path, None);
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert!(result.content.contains("## Sheet 1: "));
+        assert!(result.content.contains("| --- |"));
+        
+        let info = get_document_page_info(file_path);
+        assert!(info.page_info.starts_with("Excel file with"));
+    }
+    
+    #[test]
+    fn test_process_document_with_options_reads_excel_range() {
+        let file = crate::excel_parser::tests::xlsx_file(
+            &[("Sheet1", vec![vec!["a"]]), ("Sheet2", vec![vec!["Name", "Score"], vec!["Ada", "9"], vec!["Bob", "7"]])],
+            &[],
+        );
+        let file_path = file.path().to_str().unwrap();
+        let options = DocumentReadOptions { range: Some("B1:B2".to_string()), ..Default::default() };
+        
+        let result = process_document_with_options(file_path, Some("2".to_string()), &options);
+        assert!(result.error.is_none());
+        assert_eq!(result.total_pages, Some(2));
+        assert_eq!(result.returned_pages, vec![2]);
+        assert!(result.content.contains("## Sheet 2: Sheet2!B1:B2\n\n| Score |\n| --- |\n| 9 |\n"));
+        assert!(!result.content.contains("Bob"));
+        
+        let info = get_document_page_info(file_path);
+        assert!(info.page_info.ends_with("  1: Sheet1 (1 rows x 1 columns, A1)\n  2: Sheet2 (3 rows x 2 columns, A1:B3)"));
+        
+        let options = DocumentReadOptions { rows: Some("3-".to_string()), ..Default::default() };
+        let result = process_document_with_options(file_path, Some("2".to_string()), &options);
+        assert!(result.content.contains("(rows 3-3 of 3)\n\n| Name | Score |\n| --- | --- |\n| Bob | 7 |\n"));
+        
+        let result = process_document_with_options("tests/test.xlsx", None, &DocumentReadOptions {
+            range: Some("A1:".to_string()),
+            ..Default::default()
+        });
+        assert!(result.error.unwrap().starts_with("Invalid range parameter"));
+        
+        let options = DocumentReadOptions { formula_mode: FormulaMode::Formulas, ..Default::default() };
+        let result = process_document_with_options(file_path, Some("2".to_string()), &options);
+        assert_eq!(result.requested_pages, "2 (formula_mode formulas)");
+        assert!(result.content.contains("| Ada | 9 |"));
+        let docx = tempfile::NamedTempFile::with_suffix(".docx").unwrap();
+        let result = process_document_with_options(docx.path().to_str().unwrap(), None, &options);
+        assert!(result.error.unwrap().contains("only supported for spreadsheets"));
+    }
+
+    #[test]
+    fn test_hidden_excel_sheets_are_flagged_and_skipped() {
+        let file = crate::excel_parser::tests::XlsxBuilder::new()
+            .sheet("Report", vec![vec!["Total"], vec!["42"]])
+            .sheet("Scratch", vec![vec!["tmp"], vec!["scratch value"]])
+            .sheet_state("hidden")
+            .sheet("Macros", vec![])
+            .sheet_state("veryHidden")
+            .file();
+        let file_path = file.path().to_str().unwrap();
+        
+        let info = get_document_page_info(file_path);
+        assert!(info.page_info.ends_with(
+            "  1: Report (2 rows x 1 columns, A1:A2)\n  2: Scratch [hidden] (2 rows x 1 columns, A1:A2)\n  3: Macros [very hidden] (empty)"
+        ), "{}", info.page_info);
+        
+        let result = process_document_with_pages(file_path, None);
+        assert!(result.content.contains("## Sheet 2: Scratch (hidden)\n\n*Hidden sheet not shown; select page 2 to read it*"));
+        assert!(!result.content.contains("scratch value"));
+        
+        let result = process_document_with_pages(file_path, Some("2".to_string()));
+        assert!(result.content.contains("## Sheet 2: Scratch (hidden)\n\n| tmp |\n"));
+        
+        let options = DocumentReadOptions { rows: Some("1-".to_string()), ..Default::default() };
+        let result = process_document_with_options(file_path, None, &options);
+        assert_eq!(result.returned_pages, vec![1]);
+    }
+
+    #[test]
+    fn test_process_document_with_pages_reads_legacy_doc() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+        
+        let mut temp_file = NamedTempFile::with_suffix(".doc").unwrap();
+        let buffer = crate::doc_parser::tests::build_doc("Minutes\r\x0cActions\r", "", 0, &[]);
+        temp_file.write_all(&buffer).unwrap();
+        let file_path = temp_file.path().to_str().unwrap();
+        
+        let info = get_document_page_info(file_path);
+        assert_eq!(info.total_pages, Some(2));
+        assert_eq!(info.page_info, "DOC file with 2 pages");
+        
+        let result = process_document_with_pages(file_path, None);
+        assert!(result.error.is_none());
+        assert!(result.content.contains("## Content\n\nMinutes\n\nActions"));
+        
+        let result = process_document_with_pages(file_path, Some("2".to_string()));
+        assert!(result.content.contains("## Page 2\n\nActions"));
+        assert!(!result.content.contains("Minutes"));
+    }
+
+    #[test]
+    fn test_process_document_with_pages_reads_opendocument_files() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+        use crate::odf_parser::tests::odf_package;
+        
+        let namespaces = r#"xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:draw="urn:oasis:names:tc:opendocument:xmlns:drawing:1.0""#;
+        let text = format!(
+            r#"<office:document-content {namespaces}><office:body><office:text><text:p>Minutes</text:p><text:soft-page-break/><text:p>Actions</text:p></office:text></office:body></office:document-content>"#
+        );
+        let mut odt_file = NamedTempFile::with_suffix(".odt").unwrap();
+        odt_file.write_all(&odf_package("application/vnd.oasis.opendocument.text", &[("content.xml", &text)])).unwrap();
+        let file_path = odt_file.path().to_str().unwrap();
+        
+        let info = get_document_page_info(file_path);
+        assert_eq!(info.page_info, "ODT file with 2 pages");
+        let result = process_document_with_pages(file_path, Some("2".to_string()));
+        assert!(result.content.contains("## Page 2\n\nActions"));
+        assert!(!result.content.contains("Minutes"));
+        
+        let slides = format!(
+            r#"<office:document-content {namespaces}><office:body><office:presentation><draw:page><draw:frame><draw:text-box><text:p>Agenda</text:p></draw:text-box></draw:frame></draw:page><draw:page><draw:frame><draw:text-box><text:p>Budget</text:p></draw:text-box></draw:frame></draw:page></office:presentation></office:body></office:document-content>"#
+        );
+        let mut odp_file = NamedTempFile::with_suffix(".odp").unwrap();
+        odp_file.write_all(&odf_package("application/vnd.oasis.opendocument.presentation", &[("content.xml", &slides)])).unwrap();
+        let file_path = odp_file.path().to_str().unwrap();
+        
+        let info = get_document_page_info(file_path);
+        assert_eq!(info.total_pages, Some(2));
+        assert_eq!(info.page_info, "OpenDocument presentation with 2 slides");
+        let result = process_document_with_pages(file_path, Some("2".to_string()));
+        assert!(result.content.contains("Budget"));
+        assert!(!result.content.contains("Agenda"));
+    }
+
+    #[test]
+    fn test_process_document_with_options_reads_csv_files() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+        
+        let mut csv_file = NamedTempFile::with_suffix(".csv").unwrap();
+        csv_file.write_all(b"Item;Qty\nPen;3\n\"Paper; A4\";10\nInk;1\n").unwrap();
+        let file_path = csv_file.path().to_str().unwrap();
+        let name = csv_file.path().file_stem().unwrap().to_str().unwrap().to_string();
+        
+        let info = get_document_page_info(file_path);
+        assert_eq!(info.total_pages, Some(1));
+        assert_eq!(info.page_info, "CSV file with 1 sheet (4 rows x 2 columns, semicolon-delimited, UTF-8)");
+        
+        let result = process_document_with_pages(file_path, None);
+        assert!(result.error.is_none());
+        assert!(result.content.contains(&format!("## Sheet 1: {}\n\n| Item | Qty |\n| --- | --- |\n| Pen | 3 |\n| Paper; A4 | 10 |", name)));
+        
+        let options = DocumentReadOptions { rows: Some("3-4".to_string()), ..Default::default() };
+        let result = process_document_with_options(file_path, None, &options);
+        assert!(result.content.contains("| Item | Qty |\n| --- | --- |\n| Paper; A4 | 10 |\n| Ink | 1 |"));
+        assert!(!result.content.contains("Pen"));
+        
+        let options = DocumentReadOptions { range: Some("B2:B3".to_string()), ..Default::default() };
+        let result = process_document_with_options(file_path, None, &options);
+        assert!(result.content.contains("| 3 |\n| --- |\n| 10 |"));
+        
+        let options = DocumentReadOptions { output_format: OutputFormat::Csv, ..Default::default() };
+        let result = process_document_with_options(file_path, None, &options);
+        assert_eq!(result.requested_pages, "all (output_format csv)");
+        assert!(result.content.contains("```csv\nItem,Qty\nPen,3\nPaper; A4,10\nInk,1\n```"), "{}", result.content);
+        
+        let options = DocumentReadOptions { formula_mode: FormulaMode::Formulas, ..Default::default() };
+        assert!(process_document_with_options(file_path, None, &options).error.is_some());
+        assert!(process_document_with_pages(file_path, Some("2".to_string())).error.is_some());
     }
 
     #[test]
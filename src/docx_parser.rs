@@ -0,0 +1,1851 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use zip::{ZipArchive, ZipWriter};
+use docx_rs::{
+    Bold, Break, BreakType, Comment, CommentChild, CommentRangeEnd, Delete, DeleteChild, DeleteText, Document,
+    DocumentChild, Docx, FromXML, Hyperlink, HyperlinkData, Insert, InsertChild, Italic, Level,
+    Paragraph, ParagraphChild, Run, RunChild, StructuredDataTag, StructuredDataTagChild, Style,
+    Table, TableCell, TableCellContent, TableCellProperty, TableChild, TableRowChild, TableRowProperty,
+};
+
+use crate::markdown_table::{escape_markdown, render_table};
+
+/// Maximum depth followed through `basedOn` style inheritance
+const MAX_STYLE_DEPTH: usize = 16;
+
+/// How tracked changes and comments in a DOCX body are rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReviewMode {
+    /// Final text, with all tracked changes accepted
+    #[default]
+    Accept,
+    /// Original text, with all tracked changes rejected
+    Reject,
+    /// Both versions, marking insertions, deletions and comment threads inline
+    /// using CriticMarkup (`{++ ++}`, `{-- --}`, `{== ==}{>> <<}`)
+    Annotate,
+}
+
+impl FromStr for ReviewMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "accept" => Ok(Self::Accept),
+            "reject" => Ok(Self::Reject),
+            "annotate" => Ok(Self::Annotate),
+            other => Err(format!(
+                "Invalid review_mode '{}': expected 'accept', 'reject' or 'annotate'",
+                other
+            )),
+        }
+    }
+}
+
+/// A piece of paragraph text sharing the same inline formatting
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TextSpan {
+    pub(crate) text: String,
+    pub(crate) bold: bool,
+    pub(crate) italic: bool,
+}
+
+/// How a paragraph is rendered, derived from its style and numbering
+#[derive(Debug, Clone, PartialEq)]
+enum ParagraphKind {
+    Body,
+    Heading(usize),
+    ListItem { level: usize, ordered: bool, number: usize },
+}
+
+/// Vertical merge state of a table cell (`w:vMerge`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VerticalMerge {
+    None,
+    Restart,
+    Continue,
+}
+
+/// A table cell placed on the table grid
+struct GridCell<'c> {
+    cell: &'c TableCell,
+    column: usize,
+    span: usize,
+    merge: VerticalMerge,
+}
+
+/// Walks a parsed DOCX document and renders its body as markdown
+pub struct DocxMarkdownRenderer<'a> {
+    docx: &'a Docx,
+    blocks: Vec<String>,
+    /// Page number of each entry in `blocks`
+    block_pages: Vec<usize>,
+    /// Page on which each top-level body element starts, when paging is known
+    layout_pages: &'a [usize],
+    current_page: usize,
+    /// Current counter for each (numbering id, level) pair
+    list_counters: HashMap<(usize, usize), usize>,
+    /// Level of the list item rendered last, if the previous block was a list item
+    last_list_level: Option<usize>,
+    review_mode: ReviewMode,
+    /// Comments whose highlighted range has been opened but not yet closed
+    open_comments: Vec<usize>,
+    /// Hyperlink relationship id to target URL
+    hyperlinks: HashMap<String, String>,
+    /// Bookmarks targeted by internal links, rendered as HTML anchors
+    link_anchors: HashSet<String>,
+}
+
+impl<'a> DocxMarkdownRenderer<'a> {
+    pub fn new(docx: &'a Docx) -> Self {
+        Self {
+            docx,
+            blocks: Vec::new(),
+            block_pages: Vec::new(),
+            layout_pages: &[],
+            current_page: 1,
+            list_counters: HashMap::new(),
+            last_list_level: None,
+            review_mode: ReviewMode::Accept,
+            open_comments: Vec::new(),
+            hyperlinks: docx.hyperlinks.iter()
+                .map(|(id, target, _)| (id.clone(), target.clone()))
+                .collect(),
+            link_anchors: document_link_anchors(&docx.document.children),
+        }
+    }
+
+    /// Resolve hyperlink relationship ids with the relationships of the rendered part,
+    /// as read by [`read_docx_hyperlinks`]
+    pub fn with_hyperlinks(mut self, hyperlinks: &HashMap<String, String>) -> Self {
+        self.hyperlinks.extend(hyperlinks.iter().map(|(id, target)| (id.clone(), target.clone())));
+        self
+    }
+
+    /// Choose how tracked changes and comments are rendered
+    pub fn review_mode(mut self, review_mode: ReviewMode) -> Self {
+        self.review_mode = review_mode;
+        self
+    }
+
+    /// Assign top-level body elements to the pages found by [`scan_docx_page_layout`]
+    pub fn with_page_layout(mut self, layout: &'a DocxPageLayout) -> Self {
+        self.layout_pages = &layout.block_pages;
+        self
+    }
+
+    /// Render the document body, one markdown block per non-empty paragraph
+    pub fn render(mut self) -> String {
+        self.render_body();
+        self.blocks.join("\n\n")
+    }
+
+    /// Render another part of the package, such as a header or a footnote
+    pub fn render_part(mut self, part: &Document) -> String {
+        self.render_children(&part.children);
+        self.blocks.join("\n\n")
+    }
+
+    /// Render the document body split into pages; entry `i` holds page `i + 1`
+    pub fn render_pages(mut self, total_pages: usize) -> Vec<String> {
+        self.render_body();
+
+        let page_count = self.block_pages.iter().copied().max().unwrap_or(0).max(total_pages);
+        let mut pages = vec![Vec::new(); page_count];
+        for (block, page) in self.blocks.iter().zip(&self.block_pages) {
+            pages[page - 1].push(block.as_str());
+        }
+        pages.into_iter().map(|blocks| blocks.join("\n\n")).collect()
+    }
+
+    fn render_body(&mut self) {
+        let docx = self.docx;
+        self.render_children(&docx.document.children);
+    }
+
+    fn render_children(&mut self, children: &[DocumentChild]) {
+        let mut block_index = 0;
+        for child in children {
+            // Only these children correspond to body elements counted by the page scan
+            if matches!(
+                child,
+                DocumentChild::Paragraph(_)
+                    | DocumentChild::Table(_)
+                    | DocumentChild::StructuredDataTag(_)
+                    | DocumentChild::TableOfContents(_)
+            ) {
+                if let Some(&page) = self.layout_pages.get(block_index) {
+                    self.current_page = page;
+                }
+                block_index += 1;
+            }
+            self.render_document_child(child);
+        }
+    }
+
+    fn render_document_child(&mut self, child: &DocumentChild) {
+        match child {
+            DocumentChild::Paragraph(paragraph) => self.render_paragraph(paragraph),
+            DocumentChild::Table(table) => self.render_table(table),
+            DocumentChild::StructuredDataTag(sdt) => self.render_structured_data_tag(sdt),
+            DocumentChild::CommentEnd(end) => {
+                // A range ending between paragraphs gets its thread as a separate block
+                let mut spans = Vec::new();
+                self.close_comment(end, &mut spans);
+                self.push_block(spans_to_markdown(&spans));
+            }
+            _ => {}
+        }
+    }
+
+    fn render_structured_data_tag(&mut self, sdt: &StructuredDataTag) {
+        let mut spans = Vec::new();
+        for child in &sdt.children {
+            match child {
+                StructuredDataTagChild::Paragraph(paragraph) => self.render_paragraph(paragraph),
+                StructuredDataTagChild::Table(table) => self.render_table(table),
+                StructuredDataTagChild::StructuredDataTag(inner) => self.render_structured_data_tag(inner),
+                StructuredDataTagChild::Run(run) => collect_run(run, false, &mut spans),
+                _ => {}
+            }
+        }
+        self.push_block(spans_to_markdown(&spans));
+    }
+
+    fn render_paragraph(&mut self, paragraph: &Paragraph) {
+        // Resolve the kind first so empty numbered paragraphs still advance their counters
+        let kind = self.paragraph_kind(paragraph);
+
+        let mut spans = Vec::new();
+        self.collect_inline(&paragraph.children, &mut spans);
+        let text = spans_to_markdown(&spans);
+        if text.trim().is_empty() {
+            return;
+        }
+
+        match kind {
+            ParagraphKind::Body => self.push_block(text),
+            ParagraphKind::Heading(level) => {
+                let title = text.replace("  \n", " ").replace('\n', " ");
+                self.push_block(format!("{} {}", "#".repeat(level), title.trim()));
+            }
+            ParagraphKind::ListItem { level, ordered, number } => {
+                self.push_list_item(level, ordered, number, &text);
+            }
+        }
+    }
+
+    fn push_block(&mut self, block: String) {
+        if !block.trim().is_empty() {
+            self.blocks.push(block);
+            self.block_pages.push(self.current_page);
+            self.last_list_level = None;
+        }
+    }
+
+    /// Append a list item, joining it to the list rendered by the previous block
+    fn push_list_item(&mut self, level: usize, ordered: bool, number: usize, text: &str) {
+        // Markdown cannot skip nesting levels, so clamp to one below the previous item
+        let level = match self.last_list_level {
+            Some(previous) => level.min(previous + 1),
+            None => 0,
+        };
+
+        let indent = "    ".repeat(level);
+        let marker = if ordered { format!("{}.", number) } else { "-".to_string() };
+        let continuation = format!("\n{}{}", indent, " ".repeat(marker.len() + 1));
+        let item = format!("{}{} {}", indent, marker, text.replace('\n', &continuation));
+
+        // Lists continuing on a new page start a new block so pages can be split cleanly
+        let same_page = self.block_pages.last() == Some(&self.current_page);
+        match (self.last_list_level, self.blocks.last_mut()) {
+            (Some(_), Some(list)) if same_page => {
+                list.push('\n');
+                list.push_str(&item);
+            }
+            _ => {
+                self.blocks.push(item);
+                self.block_pages.push(self.current_page);
+            }
+        }
+        self.last_list_level = Some(level);
+    }
+
+    /// Render a table as a markdown table, or as HTML when it contains nested tables
+    fn render_table(&mut self, table: &Table) {
+        let rows = table_layout(table, self.review_mode);
+        if rows.iter().all(|row| row.is_empty()) {
+            return;
+        }
+
+        let nested = rows.iter().flatten().any(|grid_cell| {
+            grid_cell.cell.children.iter().any(|content| matches!(content, TableCellContent::Table(_)))
+        });
+        let block = if nested {
+            self.table_to_html(table)
+        } else {
+            self.table_to_markdown(&rows, table.grid.len())
+        };
+        self.push_block(block);
+    }
+
+    /// Lay the table out on its grid. Spanned columns and vertically merged
+    /// continuation cells are left empty, since markdown cannot merge cells.
+    fn table_to_markdown(&mut self, rows: &[Vec<GridCell>], grid_width: usize) -> String {
+        let width = rows.iter().flatten()
+            .map(|grid_cell| grid_cell.column + grid_cell.span)
+            .max()
+            .unwrap_or(0)
+            .max(grid_width);
+
+        let mut grid = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut cells = vec![String::new(); width];
+            for grid_cell in row {
+                if grid_cell.merge != VerticalMerge::Continue {
+                    cells[grid_cell.column] = self.render_cell_blocks(&grid_cell.cell.children)
+                        .iter()
+                        .map(|block| escape_markdown(block))
+                        .collect::<Vec<_>>()
+                        .join("<br>");
+                }
+            }
+            grid.push(cells);
+        }
+
+        render_table(grid)
+    }
+
+    /// Render a table as HTML, mapping `gridSpan` to `colspan` and `vMerge` to `rowspan`
+    fn table_to_html(&mut self, table: &Table) -> String {
+        let rows = table_layout(table, self.review_mode);
+        let mut html = String::from("<table>\n");
+
+        for (row_index, row) in rows.iter().enumerate() {
+            let tag = if row_index == 0 { "th" } else { "td" };
+            html.push_str("<tr>");
+            for grid_cell in row {
+                if grid_cell.merge == VerticalMerge::Continue {
+                    continue;
+                }
+
+                let mut attributes = String::new();
+                if grid_cell.span > 1 {
+                    attributes.push_str(&format!(" colspan=\"{}\"", grid_cell.span));
+                }
+                if grid_cell.merge == VerticalMerge::Restart {
+                    let row_span = 1 + rows[row_index + 1..].iter()
+                        .take_while(|below| below.iter().any(|c| {
+                            c.column == grid_cell.column && c.merge == VerticalMerge::Continue
+                        }))
+                        .count();
+                    if row_span > 1 {
+                        attributes.push_str(&format!(" rowspan=\"{}\"", row_span));
+                    }
+                }
+
+                let mut contents = Vec::new();
+                for content in &grid_cell.cell.children {
+                    match content {
+                        TableCellContent::Table(inner) => contents.push(self.table_to_html(inner)),
+                        other => {
+                            for block in self.render_cell_blocks(std::slice::from_ref(other)) {
+                                contents.push(escape_html(&block).replace('\n', "<br>"));
+                            }
+                        }
+                    }
+                }
+                html.push_str(&format!("<{tag}{attributes}>{}</{tag}>", contents.join("<br>")));
+            }
+            html.push_str("</tr>\n");
+        }
+
+        html.push_str("</table>");
+        html
+    }
+
+    /// Render table cell contents to markdown blocks, sharing list counters with the body
+    fn render_cell_blocks(&mut self, contents: &[TableCellContent]) -> Vec<String> {
+        let outer_blocks = std::mem::take(&mut self.blocks);
+        let outer_pages = std::mem::take(&mut self.block_pages);
+        let outer_list_level = self.last_list_level.take();
+
+        for content in contents {
+            match content {
+                TableCellContent::Paragraph(paragraph) => self.render_paragraph(paragraph),
+                TableCellContent::Table(table) => self.render_table(table),
+                TableCellContent::StructuredDataTag(sdt) => self.render_structured_data_tag(sdt),
+                TableCellContent::TableOfContents(_) => {}
+            }
+        }
+
+        let cell_blocks = std::mem::replace(&mut self.blocks, outer_blocks);
+        self.block_pages = outer_pages;
+        self.last_list_level = outer_list_level;
+        cell_blocks
+    }
+
+    /// Collect the text of paragraph children according to the review mode
+    fn collect_inline(&mut self, children: &[ParagraphChild], spans: &mut Vec<TextSpan>) {
+        for child in children {
+            match child {
+                ParagraphChild::Run(run) => collect_run(run, false, spans),
+                ParagraphChild::Insert(insert) => self.collect_insert(insert, spans),
+                ParagraphChild::Delete(delete) => self.collect_delete(delete, spans),
+                ParagraphChild::Hyperlink(hyperlink) => self.collect_hyperlink(hyperlink, spans),
+                ParagraphChild::BookmarkStart(bookmark) if self.link_anchors.contains(&bookmark.name) => {
+                    push_plain(spans, &format!("<a id=\"{}\"></a>", escape_html(&bookmark.name)));
+                }
+                ParagraphChild::StructuredDataTag(sdt) => {
+                    for sdt_child in &sdt.children {
+                        if let StructuredDataTagChild::Run(run) = sdt_child {
+                            collect_run(run, false, spans);
+                        }
+                    }
+                }
+                ParagraphChild::CommentStart(start) => self.open_comment(start.id, spans),
+                ParagraphChild::CommentEnd(end) => self.close_comment(end, spans),
+                _ => {}
+            }
+        }
+    }
+
+    /// Hyperlink as `[text](url)`; internal links point at the bookmark's anchor
+    fn collect_hyperlink(&mut self, hyperlink: &Hyperlink, spans: &mut Vec<TextSpan>) {
+        let mut text_spans = Vec::new();
+        self.collect_inline(&hyperlink.children, &mut text_spans);
+
+        let target = match &hyperlink.link {
+            // `path` is only set on documents built in memory, read documents carry the id
+            HyperlinkData::External { rid, path } if path.is_empty() => self.hyperlinks.get(rid).cloned(),
+            HyperlinkData::External { path, .. } => Some(path.clone()),
+            HyperlinkData::Anchor { anchor } => Some(format!("#{}", anchor)),
+        };
+        let text = spans_to_markdown(&text_spans).replace("  \n", " ").replace('\n', " ");
+        match target {
+            Some(target) if !text.is_empty() => {
+                push_plain(spans, &format!("[{}]({})", escape_link_text(&text), link_destination(&target)));
+            }
+            _ => extend_spans(spans, text_spans),
+        }
+    }
+
+    /// Tracked insertion: kept when accepting, dropped when rejecting
+    fn collect_insert(&mut self, insert: &Insert, spans: &mut Vec<TextSpan>) {
+        if self.review_mode == ReviewMode::Reject {
+            return;
+        }
+
+        let mut inserted = Vec::new();
+        for child in &insert.children {
+            match child {
+                InsertChild::Run(run) => collect_run(run, false, &mut inserted),
+                InsertChild::Delete(delete) => self.collect_delete(delete, &mut inserted),
+                InsertChild::CommentStart(start) => self.open_comment(start.id, &mut inserted),
+                InsertChild::CommentEnd(end) => self.close_comment(end, &mut inserted),
+            }
+        }
+
+        if self.review_mode == ReviewMode::Annotate {
+            self.push_revision(spans, inserted, "++", "Inserted", &insert.author, &insert.date);
+        } else {
+            extend_spans(spans, inserted);
+        }
+    }
+
+    /// Tracked deletion: dropped when accepting, restored when rejecting
+    fn collect_delete(&mut self, delete: &Delete, spans: &mut Vec<TextSpan>) {
+        if self.review_mode == ReviewMode::Accept {
+            return;
+        }
+
+        let mut deleted = Vec::new();
+        for child in &delete.children {
+            match child {
+                DeleteChild::Run(run) => collect_run(run, true, &mut deleted),
+                DeleteChild::CommentStart(start) => self.open_comment(start.id, &mut deleted),
+                DeleteChild::CommentEnd(end) => self.close_comment(end, &mut deleted),
+            }
+        }
+
+        if self.review_mode == ReviewMode::Annotate {
+            self.push_revision(spans, deleted, "--", "Deleted", &delete.author, &delete.date);
+        } else {
+            extend_spans(spans, deleted);
+        }
+    }
+
+    /// Wrap revised text in CriticMarkup followed by its author and date
+    fn push_revision(
+        &self,
+        spans: &mut Vec<TextSpan>,
+        revised: Vec<TextSpan>,
+        marker: &str,
+        action: &str,
+        author: &str,
+        date: &str,
+    ) {
+        if revised.iter().all(|span| span.text.is_empty()) {
+            return;
+        }
+        push_plain(spans, &format!("{{{}", marker));
+        extend_spans(spans, revised);
+        push_plain(spans, &format!("{}}}{{>>{}<<}}", marker, attribution(action, author, date)));
+    }
+
+    /// Start highlighting the range of a top-level comment in annotate mode
+    fn open_comment(&mut self, id: usize, spans: &mut Vec<TextSpan>) {
+        if self.review_mode != ReviewMode::Annotate {
+            return;
+        }
+        // Replies are shown with the thread of their parent comment
+        if self.find_comment(id).is_some_and(|comment| comment.parent_comment_id.is_none()) {
+            push_plain(spans, "{==");
+            self.open_comments.push(id);
+        }
+    }
+
+    /// Close a comment range and show its thread: the comment followed by its replies
+    fn close_comment(&mut self, end: &CommentRangeEnd, spans: &mut Vec<TextSpan>) {
+        if self.review_mode != ReviewMode::Annotate {
+            return;
+        }
+        let Some(id) = comment_end_id(end) else {
+            return;
+        };
+        let Some(comment) = self.find_comment(id) else { return };
+        if comment.parent_comment_id.is_some() {
+            return;
+        }
+
+        if let Some(position) = self.open_comments.iter().position(|&open| open == id) {
+            self.open_comments.remove(position);
+            push_plain(spans, "==}");
+        }
+
+        let docx = self.docx;
+        let replies = docx.comments.inner().iter().filter(|reply| reply.parent_comment_id == Some(id));
+        for (index, message) in std::iter::once(comment).chain(replies).enumerate() {
+            let action = if index == 0 { "Comment" } else { "Reply" };
+            let text = self.comment_text(message);
+            push_plain(spans, &format!("{{>>{}: {}<<}}", attribution(action, &message.author, &message.date), text));
+        }
+    }
+
+    fn find_comment(&self, id: usize) -> Option<&'a Comment> {
+        self.docx.comments.inner().iter().find(|comment| comment.id == id)
+    }
+
+    /// Plain text of a comment, with its paragraphs joined by spaces
+    fn comment_text(&mut self, comment: &Comment) -> String {
+        let mut paragraphs = Vec::new();
+        for child in &comment.children {
+            if let CommentChild::Paragraph(paragraph) = child {
+                let mut spans = Vec::new();
+                self.collect_inline(&paragraph.children, &mut spans);
+                let text = spans_to_markdown(&spans);
+                if !text.is_empty() {
+                    paragraphs.push(text.replace("  \n", " ").replace('\n', " "));
+                }
+            }
+        }
+        paragraphs.join(" ")
+    }
+
+    /// Classify a paragraph as heading, list item or body text
+    fn paragraph_kind(&mut self, paragraph: &Paragraph) -> ParagraphKind {
+        if let Some(level) = self.heading_level(paragraph) {
+            return ParagraphKind::Heading(level);
+        }
+
+        let Some((num_id, level)) = self.numbering_reference(paragraph) else {
+            return ParagraphKind::Body;
+        };
+        let Some(definition) = self.level_definition(num_id, level) else {
+            return ParagraphKind::Body;
+        };
+
+        let ordered = match definition.format.val.as_str() {
+            "none" => return ParagraphKind::Body,
+            "bullet" => false,
+            _ => true,
+        };
+
+        let start = self.level_start(num_id, level, definition);
+        let counter = self.list_counters.entry((num_id, level)).or_insert(start.saturating_sub(1));
+        *counter += 1;
+        let number = *counter;
+
+        // Starting a new item restarts numbering of the levels nested below it
+        self.list_counters.retain(|&(id, l), _| id != num_id || l <= level);
+
+        ParagraphKind::ListItem { level, ordered, number }
+    }
+
+    /// Heading level from the paragraph style (Heading 1-6, Title, Subtitle) or outline level
+    fn heading_level(&self, paragraph: &Paragraph) -> Option<usize> {
+        if let Some(style) = &paragraph.property.style {
+            for style_id in self.style_chain(&style.val) {
+                if let Some(level) = heading_level_for_style(&style_id, self.find_style(&style_id)) {
+                    return Some(level);
+                }
+            }
+        }
+
+        paragraph.property.outline_lvl.as_ref()
+            .filter(|outline| outline.v < 6)
+            .map(|outline| outline.v + 1)
+    }
+
+    /// Numbering id and level applied directly or through the paragraph style
+    fn numbering_reference(&self, paragraph: &Paragraph) -> Option<(usize, usize)> {
+        let mut direct_level = None;
+        if let Some(numbering) = &paragraph.property.numbering_property {
+            direct_level = numbering.level.as_ref().map(|l| l.val);
+            if let Some(id) = &numbering.id {
+                // numId 0 explicitly removes numbering inherited from the style
+                return (id.id != 0).then_some((id.id, direct_level.unwrap_or(0)));
+            }
+        }
+
+        let style_id = &paragraph.property.style.as_ref()?.val;
+        for id in self.style_chain(style_id) {
+            let Some(style) = self.find_style(&id) else { continue };
+            if let Some(numbering) = &style.paragraph_property.numbering_property
+                && let Some(num_id) = &numbering.id
+            {
+                let level = direct_level
+                    .or_else(|| numbering.level.as_ref().map(|l| l.val))
+                    .unwrap_or(0);
+                return (num_id.id != 0).then_some((num_id.id, level));
+            }
+        }
+        None
+    }
+
+    /// Look up the level definition for a numbering instance, honouring level overrides
+    fn level_definition(&self, num_id: usize, level: usize) -> Option<&'a Level> {
+        let numberings = &self.docx.numberings;
+        let numbering = numberings.numberings.iter().find(|n| n.id == num_id)?;
+
+        if let Some(overridden) = numbering.level_overrides.iter()
+            .filter(|o| o.level == level)
+            .find_map(|o| o.override_level.as_ref())
+        {
+            return Some(overridden);
+        }
+
+        numberings.abstract_nums.iter()
+            .find(|a| a.id == numbering.abstract_num_id)?
+            .levels.iter()
+            .find(|l| l.level == level)
+    }
+
+    /// First number of a list level, taking `startOverride` into account
+    fn level_start(&self, num_id: usize, level: usize, definition: &Level) -> usize {
+        let override_start = self.docx.numberings.numberings.iter()
+            .find(|n| n.id == num_id)
+            .and_then(|n| n.level_overrides.iter().find(|o| o.level == level))
+            .and_then(|o| o.override_start);
+
+        override_start
+            .or_else(|| level_start_value(definition))
+            .unwrap_or(1)
+    }
+
+    fn find_style(&self, style_id: &str) -> Option<&'a Style> {
+        self.docx.styles.styles.iter().find(|s| s.style_id == style_id)
+    }
+
+    /// The style id followed by the ids of the styles it is based on
+    fn style_chain(&self, style_id: &str) -> Vec<String> {
+        let mut chain = vec![style_id.to_string()];
+        while chain.len() < MAX_STYLE_DEPTH {
+            let Some(style) = self.find_style(chain.last().unwrap()) else { break };
+            let Some(base) = style_based_on(style) else {
+                break;
+            };
+            if chain.contains(&base) {
+                break;
+            }
+            chain.push(base);
+        }
+        chain
+    }
+}
+
+/// Map a style to a markdown heading level using its built-in name or well-known id
+fn heading_level_for_style(style_id: &str, style: Option<&Style>) -> Option<usize> {
+    if let Some(style) = style {
+        // Built-in style names are stored in English regardless of the UI language
+        let name = style_name(style).unwrap_or_default().to_lowercase();
+        match name.as_str() {
+            "title" => return Some(1),
+            "subtitle" => return Some(2),
+            _ => {}
+        }
+        if style.name.is_heading()
+            && let Some(level) = style.name.get_heading_number()
+        {
+            return Some(level.clamp(1, 6));
+        }
+    }
+
+    match style_id.to_lowercase().as_str() {
+        "title" => Some(1),
+        "subtitle" => Some(2),
+        id => id.strip_prefix("heading")
+            .and_then(|n| n.parse::<usize>().ok())
+            .map(|n| n.clamp(1, 6)),
+    }
+}
+
+/// Place each row's cells on the table grid, honouring `gridBefore`, `gridSpan` and `vMerge`
+fn table_layout(table: &Table, review_mode: ReviewMode) -> Vec<Vec<GridCell<'_>>> {
+    let mut rows = Vec::new();
+    for TableChild::TableRow(row) in &table.rows {
+        // Tracked row insertions and deletions
+        let removed = match review_mode {
+            ReviewMode::Accept => row.property.del.is_some(),
+            ReviewMode::Reject => row.property.ins.is_some(),
+            ReviewMode::Annotate => false,
+        };
+        if removed {
+            continue;
+        }
+
+        let mut column = row_grid_before(&row.property);
+
+        let mut cells = Vec::new();
+        for TableRowChild::TableCell(cell) in &row.cells {
+            let (span, merge) = cell_grid(&cell.property);
+            cells.push(GridCell { cell, column, span, merge });
+            column += span;
+        }
+        rows.push(cells);
+    }
+    rows
+}
+
+/// The serialized form of a docx-rs element, for the fields docx-rs keeps
+/// private. Only the accessors below read it, and `test_docx_rs_private_fields`
+/// pins the shape they expect so that a renamed field fails the tests rather
+/// than reading nothing.
+fn serialized<T: serde::Serialize>(element: &T) -> serde_json::Value {
+    serde_json::to_value(element).unwrap_or_default()
+}
+
+/// Id of the comment whose range ends here
+fn comment_end_id(end: &CommentRangeEnd) -> Option<usize> {
+    serialized(end)["id"].as_u64().map(|id| id as usize)
+}
+
+/// Text removed by a tracked deletion
+fn deleted_text(text: &DeleteText) -> Option<String> {
+    serialized(text)["text"].as_str().map(str::to_string)
+}
+
+/// First number of a list level as defined, before any `startOverride`
+fn level_start_value(level: &Level) -> Option<usize> {
+    serialized(&level.start).as_u64().map(|start| start as usize)
+}
+
+/// Id of the style a style is based on
+fn style_based_on(style: &Style) -> Option<String> {
+    serialized(style.based_on.as_ref()?).as_str().map(str::to_string)
+}
+
+/// Name of a style as stored, which is English for built-in styles
+fn style_name(style: &Style) -> Option<String> {
+    serialized(&style.name).as_str().map(str::to_string)
+}
+
+/// Grid columns skipped before the first cell of a row
+fn row_grid_before(property: &TableRowProperty) -> usize {
+    serialized(property)["gridBefore"].as_u64().unwrap_or(0) as usize
+}
+
+/// Grid columns spanned by a cell and how it merges with the cells above it
+fn cell_grid(property: &TableCellProperty) -> (usize, VerticalMerge) {
+    let property = serialized(property);
+    let span = property["gridSpan"].as_u64().unwrap_or(1).max(1) as usize;
+    let merge = match property["verticalMerge"].as_str() {
+        Some("restart") => VerticalMerge::Restart,
+        Some("continue") => VerticalMerge::Continue,
+        _ => VerticalMerge::None,
+    };
+    (span, merge)
+}
+
+/// Escape text for inclusion in HTML
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Escape brackets that would end the text of a markdown link early
+pub(crate) fn escape_link_text(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+/// Link destination, wrapped in angle brackets when it contains spaces or parentheses
+pub(crate) fn link_destination(target: &str) -> String {
+    if target.contains([' ', '(', ')']) {
+        format!("<{}>", target.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        target.to_string()
+    }
+}
+
+/// Names of the bookmarks that internal hyperlinks in the body point at
+fn document_link_anchors(children: &[DocumentChild]) -> HashSet<String> {
+    fn visit_paragraph(paragraph: &Paragraph, anchors: &mut HashSet<String>) {
+        for child in &paragraph.children {
+            if let ParagraphChild::Hyperlink(hyperlink) = child
+                && let HyperlinkData::Anchor { anchor } = &hyperlink.link
+            {
+                anchors.insert(anchor.clone());
+            }
+        }
+    }
+
+    fn visit_table(table: &Table, anchors: &mut HashSet<String>) {
+        for TableChild::TableRow(row) in &table.rows {
+            for TableRowChild::TableCell(cell) in &row.cells {
+                for content in &cell.children {
+                    match content {
+                        TableCellContent::Paragraph(paragraph) => visit_paragraph(paragraph, anchors),
+                        TableCellContent::Table(inner) => visit_table(inner, anchors),
+                        TableCellContent::StructuredDataTag(sdt) => visit_sdt(sdt, anchors),
+                        TableCellContent::TableOfContents(_) => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn visit_sdt(sdt: &StructuredDataTag, anchors: &mut HashSet<String>) {
+        for child in &sdt.children {
+            match child {
+                StructuredDataTagChild::Paragraph(paragraph) => visit_paragraph(paragraph, anchors),
+                StructuredDataTagChild::Table(table) => visit_table(table, anchors),
+                StructuredDataTagChild::StructuredDataTag(inner) => visit_sdt(inner, anchors),
+                _ => {}
+            }
+        }
+    }
+
+    let mut anchors = HashSet::new();
+    for child in children {
+        match child {
+            DocumentChild::Paragraph(paragraph) => visit_paragraph(paragraph, &mut anchors),
+            DocumentChild::Table(table) => visit_table(table, &mut anchors),
+            DocumentChild::StructuredDataTag(sdt) => visit_sdt(sdt, &mut anchors),
+            _ => {}
+        }
+    }
+    anchors
+}
+
+/// Render the body of a parsed DOCX document as markdown
+pub fn docx_to_markdown(docx: &Docx) -> String {
+    DocxMarkdownRenderer::new(docx).render()
+}
+
+/// A footnote or endnote, labelled by its markdown footnote marker
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocxNote {
+    pub label: String,
+    pub text: String,
+}
+
+/// A parsed DOCX package together with the parts docx-rs does not expose:
+/// the headers and footers of every section, footnotes and endnotes
+pub struct DocxPackage {
+    pub docx: Docx,
+    /// Hyperlink targets of the body, from `word/_rels/document.xml.rels`
+    pub hyperlinks: HashMap<String, String>,
+    pub headers: Vec<String>,
+    pub footers: Vec<String>,
+    pub footnotes: Vec<DocxNote>,
+    pub endnotes: Vec<DocxNote>,
+}
+
+impl DocxPackage {
+    /// Parse a DOCX package, turning note references in the body into `[^n]` markers
+    pub fn read(buffer: &[u8]) -> Result<Self> {
+        let document_xml = read_docx_part(buffer, "word/document.xml")?;
+        let references = inline_note_references(&document_xml)?;
+
+        // docx-rs drops note references, so parse a copy of the package with inline markers
+        let docx = if references.footnotes.is_empty() && references.endnotes.is_empty() {
+            docx_rs::read_docx(buffer)
+        } else {
+            let patched = replace_docx_part(buffer, "word/document.xml", &references.xml)?;
+            docx_rs::read_docx(&patched)
+        }
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        let hyperlinks = read_docx_hyperlinks(buffer, "word/document.xml");
+        let headers = render_numbered_parts(&docx, buffer, "word/header");
+        let footers = render_numbered_parts(&docx, buffer, "word/footer");
+        let footnotes = render_notes(&docx, buffer, "word/footnotes.xml", &references.footnotes);
+        let endnotes = render_notes(&docx, buffer, "word/endnotes.xml", &references.endnotes);
+
+        Ok(Self { docx, hyperlinks, headers, footers, footnotes, endnotes })
+    }
+}
+
+/// Format notes as markdown footnote definitions
+pub fn notes_to_markdown(notes: &[DocxNote]) -> String {
+    notes.iter()
+        .map(|note| {
+            // Further paragraphs of a definition are indented to stay part of it
+            format!("[^{}]: {}", note.label, note.text.replace("\n\n", "\n\n    "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Body XML with note references replaced by marker text, and the referenced note ids
+struct NoteReferences {
+    xml: String,
+    /// (note id, marker label) in order of first reference
+    footnotes: Vec<(String, String)>,
+    endnotes: Vec<(String, String)>,
+}
+
+/// Replace `w:footnoteReference` and `w:endnoteReference` with `w:t` elements holding
+/// the markdown marker. Footnotes are numbered `[^1]`, `[^2]`… and endnotes `[^e1]`…
+/// in order of appearance.
+fn inline_note_references(xml: &str) -> Result<NoteReferences> {
+    let mut reader = Reader::from_str(xml);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mut footnotes: Vec<(String, String)> = Vec::new();
+    let mut endnotes: Vec<(String, String)> = Vec::new();
+
+    loop {
+        let event = match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(event) => event,
+            Err(e) => return Err(anyhow::anyhow!("Failed to parse DOCX body XML: {}", e)),
+        };
+
+        let reference = match &event {
+            Event::Empty(e) | Event::Start(e) => match e.local_name().as_ref() {
+                b"footnoteReference" => Some((e, &mut footnotes, "")),
+                b"endnoteReference" => Some((e, &mut endnotes, "e")),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let Some((element, notes, label_prefix)) = reference else {
+            writer.write_event(event.borrow())?;
+            continue;
+        };
+
+        let id = attribute_value(element, b"id").unwrap_or_default();
+        let label = match notes.iter().find(|(note_id, _)| *note_id == id) {
+            Some((_, label)) => label.clone(),
+            None => {
+                let label = format!("{}{}", label_prefix, notes.len() + 1);
+                notes.push((id, label.clone()));
+                label
+            }
+        };
+
+        let text_tag = match element.name().prefix() {
+            Some(prefix) => format!("{}:t", String::from_utf8_lossy(prefix.as_ref())),
+            None => "t".to_string(),
+        };
+        let is_start = matches!(event, Event::Start(_));
+        writer.write_event(Event::Start(BytesStart::new(text_tag.as_str())))?;
+        writer.write_event(Event::Text(BytesText::new(&format!("[^{}]", label))))?;
+        writer.write_event(Event::End(BytesEnd::new(text_tag.as_str())))?;
+
+        // A non-empty reference element has no meaningful content; skip to its end
+        if is_start {
+            let end = element.to_end().into_owned();
+            reader.read_to_end(end.name())?;
+        }
+    }
+
+    let xml = String::from_utf8(writer.into_inner().into_inner())
+        .with_context(|| "DOCX body XML is not valid UTF-8")?;
+    Ok(NoteReferences { xml, footnotes, endnotes })
+}
+
+/// Copy a DOCX package, replacing the contents of one part
+pub(crate) fn replace_docx_part(buffer: &[u8], part_name: &str, contents: &str) -> Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut archive = ZipArchive::new(Cursor::new(buffer))
+        .with_context(|| "Failed to open DOCX package")?;
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index)?;
+        if file.name() == part_name {
+            let name = file.name().to_string();
+            drop(file);
+            writer.start_file(name, zip::write::SimpleFileOptions::default())?;
+            writer.write_all(contents.as_bytes())?;
+        } else {
+            writer.raw_copy_file(file)?;
+        }
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
+
+/// Render parts such as `word/header1.xml`, `word/header2.xml`… in numeric order,
+/// skipping empty parts and repeats of identical content
+fn render_numbered_parts(docx: &Docx, buffer: &[u8], prefix: &str) -> Vec<String> {
+    let Ok(archive) = ZipArchive::new(Cursor::new(buffer)) else {
+        return Vec::new();
+    };
+
+    let mut parts: Vec<(usize, String)> = archive.file_names()
+        .filter_map(|name| {
+            let number = name.strip_prefix(prefix)?.strip_suffix(".xml")?;
+            let number = if number.is_empty() { 0 } else { number.parse().ok()? };
+            Some((number, name.to_string()))
+        })
+        .collect();
+    parts.sort();
+
+    let mut rendered: Vec<String> = Vec::new();
+    for (_, name) in parts {
+        let Ok(xml) = read_docx_part(buffer, &name) else { continue };
+        match Document::from_xml(xml.as_bytes()) {
+            Ok(part) => {
+                let text = DocxMarkdownRenderer::new(docx)
+                    .with_hyperlinks(&read_docx_hyperlinks(buffer, &name))
+                    .render_part(&part);
+                if !text.is_empty() && !rendered.contains(&text) {
+                    rendered.push(text);
+                }
+            }
+            Err(e) => log::warn!("Failed to parse DOCX part {}: {}", name, e),
+        }
+    }
+    rendered
+}
+
+/// Render the referenced notes of `word/footnotes.xml` or `word/endnotes.xml`
+fn render_notes(docx: &Docx, buffer: &[u8], part_name: &str, references: &[(String, String)]) -> Vec<DocxNote> {
+    if references.is_empty() {
+        return Vec::new();
+    }
+    let Ok(xml) = read_docx_part(buffer, part_name) else {
+        return Vec::new();
+    };
+    let fragments = match split_note_fragments(&xml) {
+        Ok(fragments) => fragments,
+        Err(e) => {
+            log::warn!("Failed to parse DOCX part {}: {}", part_name, e);
+            return Vec::new();
+        }
+    };
+
+    let hyperlinks = read_docx_hyperlinks(buffer, part_name);
+    references.iter()
+        .filter_map(|(id, label)| {
+            let fragment = fragments.get(id)?;
+            let part = Document::from_xml(fragment.as_bytes()).ok()?;
+            let text = DocxMarkdownRenderer::new(docx).with_hyperlinks(&hyperlinks).render_part(&part);
+            Some(DocxNote { label: label.clone(), text })
+        })
+        .collect()
+}
+
+/// Split a notes part into standalone XML documents, one per note id.
+/// Each note is wrapped in the root element so namespace prefixes stay bound.
+fn split_note_fragments(xml: &str) -> Result<HashMap<String, String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut fragments = HashMap::new();
+    let mut root: Option<(usize, usize, String)> = None;
+    let mut note: Option<(String, usize)> = None;
+    let mut depth = 0usize;
+
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = reader.read_event()
+            .map_err(|e| anyhow::anyhow!("Failed to parse notes XML: {}", e))?;
+        let end = reader.buffer_position() as usize;
+
+        match event {
+            Event::Start(e) => {
+                depth += 1;
+                if depth == 1 {
+                    root = Some((start, end, String::from_utf8_lossy(e.name().as_ref()).into_owned()));
+                } else if depth == 2 {
+                    note = attribute_value(&e, b"id").map(|id| (id, start));
+                }
+            }
+            Event::End(_) => {
+                if depth == 2
+                    && let (Some((id, note_start)), Some((root_start, root_end, root_name))) = (note.take(), &root)
+                {
+                    let fragment = format!(
+                        "{}{}</{}>",
+                        &xml[*root_start..*root_end],
+                        &xml[note_start..end],
+                        root_name
+                    );
+                    fragments.insert(id, fragment);
+                }
+                depth = depth.saturating_sub(1);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(fragments)
+}
+
+/// Page boundaries of a DOCX body, taken from the raw `word/document.xml`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocxPageLayout {
+    /// Page on which each top-level paragraph, table or content control starts
+    pub block_pages: Vec<usize>,
+    /// Number of pages found by following page and section breaks
+    pub total_pages: usize,
+}
+
+/// A page-relevant event in document order
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PageEvent {
+    Block,
+    Content,
+    Break,
+    SectionEnd,
+}
+
+/// Read a part of the DOCX zip package as text
+pub fn read_docx_part(buffer: &[u8], part_name: &str) -> Result<String> {
+    let mut archive = ZipArchive::new(Cursor::new(buffer))
+        .with_context(|| "Failed to open DOCX package")?;
+    let mut part = archive.by_name(part_name)
+        .with_context(|| format!("Missing DOCX part: {}", part_name))?;
+
+    let mut xml = String::new();
+    part.read_to_string(&mut xml)
+        .with_context(|| format!("Failed to read DOCX part: {}", part_name))?;
+    Ok(xml)
+}
+
+/// Hyperlink targets of a part, keyed by relationship id, from the part's
+/// relationships file (e.g. `word/_rels/document.xml.rels` for `word/document.xml`)
+pub fn read_docx_hyperlinks(buffer: &[u8], part_name: &str) -> HashMap<String, String> {
+    let (directory, file_name) = part_name.rsplit_once('/').unwrap_or(("", part_name));
+    let rels_name = if directory.is_empty() {
+        format!("_rels/{}.rels", file_name)
+    } else {
+        format!("{}/_rels/{}.rels", directory, file_name)
+    };
+    let Ok(xml) = read_docx_part(buffer, &rels_name) else {
+        return HashMap::new();
+    };
+
+    let mut reader = Reader::from_str(&xml);
+    let mut hyperlinks = HashMap::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e)) | Ok(Event::Start(e)) if e.local_name().as_ref() == b"Relationship" => {
+                let is_hyperlink = attribute_value(&e, b"Type").is_some_and(|kind| kind.ends_with("/hyperlink"));
+                if is_hyperlink
+                    && let (Some(id), Some(target)) = (attribute_value(&e, b"Id"), attribute_value(&e, b"Target"))
+                {
+                    let target = quick_xml::escape::unescape(&target).map(|t| t.into_owned()).unwrap_or(target);
+                    hyperlinks.insert(id, target);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                log::warn!("Failed to parse {}: {}", rels_name, e);
+                break;
+            }
+            _ => {}
+        }
+    }
+    hyperlinks
+}
+
+/// Page count stored by the authoring application in `docProps/app.xml`
+pub fn read_docx_app_page_count(buffer: &[u8]) -> Option<usize> {
+    let xml = read_docx_part(buffer, "docProps/app.xml").ok()?;
+    let mut reader = Reader::from_str(&xml);
+    let mut in_pages = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => in_pages = e.local_name().as_ref() == b"Pages",
+            Ok(Event::Text(text)) if in_pages => {
+                return std::str::from_utf8(&text).ok()?.trim().parse().ok().filter(|&pages| pages > 0);
+            }
+            Ok(Event::End(_)) => in_pages = false,
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+/// Locate page boundaries in the document body.
+///
+/// docx-rs drops `w:lastRenderedPageBreak`, so the body XML is scanned directly for
+/// explicit page breaks, rendered page breaks, `pageBreakBefore` and section breaks.
+/// Consecutive breaks without text in between count as a single page boundary, since
+/// Word records a rendered break right after each explicit one.
+pub fn scan_docx_page_layout(buffer: &[u8]) -> Result<DocxPageLayout> {
+    let xml = read_docx_part(buffer, "word/document.xml")?;
+    let (events, section_starts_page) = collect_page_events(&xml)?;
+
+    let mut layout = DocxPageLayout { block_pages: Vec::new(), total_pages: 1 };
+    let mut page = 1;
+    let mut pending_break = false;
+    let mut block_has_content = false;
+    let mut section = 0;
+
+    for event in events {
+        match event {
+            PageEvent::Block => {
+                layout.block_pages.push(page);
+                block_has_content = false;
+            }
+            PageEvent::Content => {
+                if pending_break {
+                    page += 1;
+                    pending_break = false;
+                }
+                // A block starts on the page holding its first text
+                if !block_has_content {
+                    if let Some(block_page) = layout.block_pages.last_mut() {
+                        *block_page = page;
+                    }
+                    block_has_content = true;
+                }
+            }
+            PageEvent::Break => pending_break = true,
+            PageEvent::SectionEnd => {
+                // The break type is stored on the section that follows
+                section += 1;
+                if section_starts_page.get(section).copied().unwrap_or(true) {
+                    pending_break = true;
+                }
+            }
+        }
+    }
+
+    layout.total_pages = page;
+    Ok(layout)
+}
+
+/// Walk the body XML, returning page events and whether each section starts a new page
+fn collect_page_events(xml: &str) -> Result<(Vec<PageEvent>, Vec<bool>)> {
+    let mut reader = Reader::from_str(xml);
+    let mut events = Vec::new();
+    let mut section_starts_page = Vec::new();
+
+    // Depth of open paragraphs, tables and content controls; docx-rs lists the outermost ones
+    let mut block_depth = 0usize;
+    let mut deleted_depth = 0usize;
+    let mut in_text = false;
+    let mut section_depth = 0usize;
+    let mut paragraph_ends_section = false;
+
+    loop {
+        let (element, is_empty) = match reader.read_event() {
+            Ok(Event::Start(e)) => (e, false),
+            Ok(Event::Empty(e)) => (e, true),
+            Ok(Event::End(e)) => {
+                let name = e.local_name();
+                match name.as_ref() {
+                    b"p" | b"tbl" | b"sdt" => {
+                        block_depth = block_depth.saturating_sub(1);
+                        if paragraph_ends_section && block_depth == 0 {
+                            events.push(PageEvent::SectionEnd);
+                            paragraph_ends_section = false;
+                        }
+                    }
+                    b"t" => in_text = false,
+                    b"del" | b"moveFrom" => deleted_depth = deleted_depth.saturating_sub(1),
+                    b"sectPr" => section_depth = section_depth.saturating_sub(1),
+                    _ => {}
+                }
+                continue;
+            }
+            Ok(Event::Text(text)) => {
+                if in_text && deleted_depth == 0 && !text.iter().all(u8::is_ascii_whitespace) {
+                    events.push(PageEvent::Content);
+                }
+                continue;
+            }
+            Ok(Event::GeneralRef(_)) => {
+                if in_text && deleted_depth == 0 {
+                    events.push(PageEvent::Content);
+                }
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow::anyhow!("Failed to parse DOCX body XML: {}", e)),
+            _ => continue,
+        };
+
+        match element.local_name().as_ref() {
+            b"p" | b"tbl" | b"sdt" => {
+                if block_depth == 0 {
+                    events.push(PageEvent::Block);
+                }
+                if !is_empty {
+                    block_depth += 1;
+                }
+            }
+            b"t" => in_text = !is_empty,
+            b"del" | b"moveFrom" if !is_empty => deleted_depth += 1,
+            b"br" if deleted_depth == 0 && attribute_value(&element, b"type").as_deref() == Some("page") => {
+                events.push(PageEvent::Break);
+            }
+            b"lastRenderedPageBreak" if deleted_depth == 0 => events.push(PageEvent::Break),
+            b"pageBreakBefore" if is_toggle_on(&element) => events.push(PageEvent::Break),
+            b"sectPr" => {
+                // Ignore the previous properties recorded inside `w:sectPrChange`
+                if section_depth == 0 {
+                    section_starts_page.push(true);
+                    if block_depth > 0 {
+                        paragraph_ends_section = true;
+                    }
+                }
+                if !is_empty {
+                    section_depth += 1;
+                }
+            }
+            b"type" if section_depth == 1 => {
+                let value = attribute_value(&element, b"val").unwrap_or_default();
+                if let Some(starts_page) = section_starts_page.last_mut() {
+                    *starts_page = !matches!(value.as_str(), "continuous" | "nextColumn");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok((events, section_starts_page))
+}
+
+/// Value of an attribute matched by local name, e.g. `w:type`
+fn attribute_value(element: &BytesStart, local_name: &[u8]) -> Option<String> {
+    element.attributes().flatten()
+        .find(|attribute| attribute.key.local_name().as_ref() == local_name)
+        .map(|attribute| String::from_utf8_lossy(&attribute.value).into_owned())
+}
+
+/// Whether an on/off property such as `<w:pageBreakBefore/>` is switched on
+fn is_toggle_on(element: &BytesStart) -> bool {
+    !matches!(attribute_value(element, b"val").as_deref(), Some("0" | "false" | "off"))
+}
+
+/// "Inserted by Jane (2024-05-01)": who made a revision or comment, and when
+fn attribution(action: &str, author: &str, date: &str) -> String {
+    let author = if author.trim().is_empty() { "unknown author" } else { author.trim() };
+    // Revision dates are ISO 8601 timestamps; the day is enough for review
+    match date.split('T').next().filter(|day| !day.is_empty()) {
+        Some(day) => format!("{} by {} ({})", action, author, day),
+        None => format!("{} by {}", action, author),
+    }
+}
+
+/// Append unformatted text, such as review markup
+pub(crate) fn push_plain(spans: &mut Vec<TextSpan>, text: &str) {
+    extend_spans(spans, vec![TextSpan { text: text.to_string(), bold: false, italic: false }]);
+}
+
+/// Append spans, merging neighbours that share the same formatting
+pub(crate) fn extend_spans(spans: &mut Vec<TextSpan>, more: Vec<TextSpan>) {
+    for span in more {
+        match spans.last_mut() {
+            Some(last) if last.bold == span.bold && last.italic == span.italic => last.text.push_str(&span.text),
+            _ => spans.push(span),
+        }
+    }
+}
+
+/// Collect the text of a single run, mapping tabs and breaks to their text equivalents.
+/// Deleted text (`w:delText`) is only included when `include_deleted` is set.
+fn collect_run(run: &Run, include_deleted: bool, spans: &mut Vec<TextSpan>) {
+    let property = &run.run_property;
+    if property.vanish.is_some() {
+        return;
+    }
+
+    let bold = property.bold.as_ref() == Some(&Bold::new());
+    let italic = property.italic.as_ref() == Some(&Italic::new());
+    let mut text = String::new();
+
+    for child in &run.children {
+        match child {
+            RunChild::Text(t) => text.push_str(&t.text),
+            RunChild::DeleteText(t) if include_deleted => {
+                if let Some(deleted) = deleted_text(t) {
+                    text.push_str(&deleted);
+                }
+            }
+            RunChild::Tab(_) | RunChild::PTab(_) => text.push('\t'),
+            RunChild::Break(br) => {
+                if *br == Break::new(BreakType::Page) || *br == Break::new(BreakType::Column) {
+                    text.push_str("\n\n");
+                } else {
+                    text.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !text.is_empty() {
+        extend_spans(spans, vec![TextSpan { text, bold, italic }]);
+    }
+}
+
+/// Convert formatted spans into markdown, keeping whitespace outside emphasis markers
+pub(crate) fn spans_to_markdown(spans: &[TextSpan]) -> String {
+    let mut markdown = String::new();
+
+    for span in spans {
+        let marker = match (span.bold, span.italic) {
+            (true, true) => "***",
+            (true, false) => "**",
+            (false, true) => "*",
+            (false, false) => "",
+        };
+
+        // Emphasis cannot span paragraph breaks, so format each line separately
+        let lines: Vec<&str> = span.text.split('\n').collect();
+        for (index, line) in lines.iter().enumerate() {
+            if index > 0 {
+                markdown.push('\n');
+            }
+            let core = line.trim();
+            if marker.is_empty() || core.is_empty() {
+                markdown.push_str(line);
+            } else {
+                let leading = &line[..line.len() - line.trim_start().len()];
+                let trailing = &line[line.trim_end().len()..];
+                markdown.push_str(leading);
+                markdown.push_str(marker);
+                markdown.push_str(core);
+                markdown.push_str(marker);
+                markdown.push_str(trailing);
+            }
+        }
+    }
+
+    // Single line breaks become markdown hard breaks; blank lines separate blocks.
+    // Leading whitespace is dropped so tab-indented lines are not read as code blocks.
+    let lines: Vec<&str> = markdown.split('\n').map(|line| line.trim_start()).collect();
+    let mut result = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        result.push_str(line);
+        if index + 1 < lines.len() {
+            let next_is_blank = lines[index + 1].trim().is_empty();
+            if !line.trim().is_empty() && !next_is_blank {
+                result.push_str("  ");
+            }
+            result.push('\n');
+        }
+    }
+
+    result.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use docx_rs::{
+        read_docx, AbstractNumbering, Delete, IndentLevel, Insert, LevelJc, LevelText, NumberFormat,
+        Comment, Footer, Footnote, Header, HyperlinkType, Numbering, NumberingId, Start, StyleType, TableRow,
+        VMergeType,
+    };
+
+    /// Pack a generated document and parse it back, as `read_docx_to_markdown` would
+    fn round_trip(docx: Docx) -> Docx {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        docx.build().pack(&mut buffer).unwrap();
+        read_docx(&buffer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn test_docx_to_markdown_paragraphs_and_runs() {
+        let docx = round_trip(
+            Docx::new()
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text("First paragraph")))
+                .add_paragraph(Paragraph::new())
+                .add_paragraph(
+                    Paragraph::new()
+                        .add_run(Run::new().add_text("Plain "))
+                        .add_run(Run::new().add_text("bold").bold())
+                        .add_run(Run::new().add_text(" and "))
+                        .add_run(Run::new().add_text("italic ").italic()),
+                ),
+        );
+
+        let markdown = docx_to_markdown(&docx);
+        assert_eq!(markdown, "First paragraph\n\nPlain **bold** and *italic*");
+    }
+
+    #[test]
+    fn test_docx_to_markdown_breaks_and_tabs() {
+        let docx = round_trip(
+            Docx::new().add_paragraph(
+                Paragraph::new().add_run(
+                    Run::new()
+                        .add_text("Name:")
+                        .add_tab()
+                        .add_text("Value")
+                        .add_break(BreakType::TextWrapping)
+                        .add_text("Next line"),
+                ),
+            ),
+        );
+
+        let markdown = docx_to_markdown(&docx);
+        assert_eq!(markdown, "Name:\tValue  \nNext line");
+    }
+
+    #[test]
+    fn test_docx_to_markdown_skips_deleted_text() {
+        let docx = round_trip(
+            Docx::new().add_paragraph(
+                Paragraph::new()
+                    .add_run(Run::new().add_text("Kept "))
+                    .add_insert(Insert::new(Run::new().add_text("inserted")))
+                    .add_delete(Delete::new().add_run(Run::new().add_delete_text("removed"))),
+            ),
+        );
+
+        let markdown = docx_to_markdown(&docx);
+        assert_eq!(markdown, "Kept inserted");
+    }
+
+    #[test]
+    fn test_docx_to_markdown_headings_from_styles() {
+        let docx = round_trip(
+            Docx::new()
+                .add_style(Style::new("Title", StyleType::Paragraph).name("Title"))
+                .add_style(Style::new("Heading2", StyleType::Paragraph).name("heading 2"))
+                .add_style(Style::new("Custom", StyleType::Paragraph).name("Custom Heading").based_on("Heading2"))
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Annual Report")).style("Title"))
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Overview")).style("Heading2"))
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Inherited")).style("Custom"))
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Outline")).outline_lvl(2))
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Body text"))),
+        );
+
+        let markdown = docx_to_markdown(&docx);
+        assert_eq!(markdown, "# Annual Report\n\n## Overview\n\n## Inherited\n\n### Outline\n\nBody text");
+    }
+
+    #[test]
+    fn test_docx_to_markdown_nested_lists() {
+        let level = |level: usize, format: &str, text: &str| {
+            Level::new(level, Start::new(1), NumberFormat::new(format), LevelText::new(text), LevelJc::new("left"))
+        };
+        let item = |text: &str, num_id: usize, level: usize| {
+            Paragraph::new()
+                .add_run(Run::new().add_text(text))
+                .numbering(NumberingId::new(num_id), IndentLevel::new(level))
+        };
+
+        let docx = round_trip(
+            Docx::new()
+                .add_abstract_numbering(
+                    AbstractNumbering::new(10)
+                        .add_level(level(0, "decimal", "%1."))
+                        .add_level(level(1, "bullet", "•")),
+                )
+                .add_numbering(Numbering::new(10, 10))
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Steps:")))
+                .add_paragraph(item("Prepare", 10, 0))
+                .add_paragraph(item("Tools", 10, 1))
+                .add_paragraph(item("Materials", 10, 1))
+                .add_paragraph(item("Execute", 10, 0))
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Done."))),
+        );
+
+        let markdown = docx_to_markdown(&docx);
+        assert_eq!(
+            markdown,
+            "Steps:\n\n1. Prepare\n    - Tools\n    - Materials\n2. Execute\n\nDone."
+        );
+    }
+
+    fn text_cell(text: &str) -> TableCell {
+        TableCell::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text(text)))
+    }
+
+    #[test]
+    fn test_docx_to_markdown_tables_with_merged_cells() {
+        let docx = round_trip(
+            Docx::new()
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Pricing")))
+                .add_table(Table::new(vec![
+                    TableRow::new(vec![text_cell("Plan"), text_cell("Limits").grid_span(2)]),
+                    TableRow::new(vec![
+                        text_cell("Basic").vertical_merge(VMergeType::Restart),
+                        text_cell("10 | 20"),
+                        TableCell::new()
+                            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Email")))
+                            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Chat").bold())),
+                    ]),
+                    TableRow::new(vec![
+                        TableCell::new().vertical_merge(VMergeType::Continue),
+                        text_cell("30"),
+                        text_cell("Phone"),
+                    ]),
+                ])),
+        );
+
+        let markdown = docx_to_markdown(&docx);
+        assert_eq!(
+            markdown,
+            "Pricing\n\n\
+             | Plan | Limits |  |\n\
+             | --- | --- | --- |\n\
+             | Basic | 10 \\| 20 | Email<br>**Chat** |\n\
+             |  | 30 | Phone |"
+        );
+    }
+
+    #[test]
+    fn test_docx_to_markdown_nested_table_falls_back_to_html() {
+        let inner = Table::new(vec![TableRow::new(vec![text_cell("a < b"), text_cell("c")])]);
+        let docx = round_trip(
+            Docx::new().add_table(Table::new(vec![
+                TableRow::new(vec![text_cell("Item"), text_cell("Details")]),
+                TableRow::new(vec![
+                    text_cell("Widget").vertical_merge(VMergeType::Restart),
+                    TableCell::new()
+                        .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Parts")))
+                        .add_table(inner),
+                ]),
+                TableRow::new(vec![
+                    TableCell::new().vertical_merge(VMergeType::Continue),
+                    text_cell("Spare"),
+                ]),
+            ])),
+        );
+
+        let markdown = docx_to_markdown(&docx);
+        assert_eq!(
+            markdown,
+            "<table>\n\
+             <tr><th>Item</th><th>Details</th></tr>\n\
+             <tr><td rowspan=\"2\">Widget</td><td>Parts<br><table>\n\
+             <tr><th>a &lt; b</th><th>c</th></tr>\n\
+             </table></td></tr>\n\
+             <tr><td>Spare</td></tr>\n\
+             </table>"
+        );
+    }
+
+    /// Build a minimal package holding only the parts read by the page scan
+    fn raw_package(document_xml: &str, app_xml: Option<&str>) -> Vec<u8> {
+        use std::io::Write;
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("word/document.xml", options).unwrap();
+        writer.write_all(document_xml.as_bytes()).unwrap();
+        if let Some(app_xml) = app_xml {
+            writer.start_file("docProps/app.xml", options).unwrap();
+            writer.write_all(app_xml.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_scan_docx_page_layout_breaks() {
+        let document_xml = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+            <w:p><w:r><w:t>Page one</w:t><w:br w:type="page"/></w:r></w:p>
+            <w:p><w:r><w:lastRenderedPageBreak/><w:t>Page two</w:t></w:r></w:p>
+            <w:p><w:r><w:t>Still two, then wraps</w:t><w:lastRenderedPageBreak/><w:t>onto three</w:t></w:r></w:p>
+            <w:p><w:pPr><w:sectPr/></w:pPr><w:r><w:t>Three</w:t></w:r></w:p>
+            <w:p><w:pPr><w:sectPr><w:type w:val="continuous"/></w:sectPr></w:pPr><w:r><w:t>Continuous section on three</w:t></w:r></w:p>
+            <w:tbl><w:tr><w:tc><w:p><w:r><w:t>Table on four</w:t></w:r></w:p></w:tc></w:tr></w:tbl>
+            <w:p/>
+            <w:sectPr/>
+        </w:body></w:document>"#;
+
+        let layout = scan_docx_page_layout(&raw_package(document_xml, None)).unwrap();
+        assert_eq!(layout.block_pages, vec![1, 2, 2, 3, 3, 4, 4]);
+        assert_eq!(layout.total_pages, 4);
+    }
+
+    #[test]
+    fn test_read_docx_app_page_count() {
+        let document_xml = "<w:document><w:body/></w:document>";
+        let app_xml = "<Properties><Template>Normal.dotm</Template><Pages>12</Pages></Properties>";
+
+        assert_eq!(read_docx_app_page_count(&raw_package(document_xml, Some(app_xml))), Some(12));
+        assert_eq!(read_docx_app_page_count(&raw_package(document_xml, None)), None);
+    }
+
+    #[test]
+    fn test_docx_package_headers_footers_and_footnotes() {
+        let mut buffer = Cursor::new(Vec::new());
+        Docx::new()
+            .header(Header::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text("Confidential"))))
+            .first_header(Header::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text("Confidential"))))
+            .footer(Footer::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text("Contract 42"))))
+            .add_paragraph(
+                Paragraph::new()
+                    .add_run(Run::new().add_text("Liability is capped"))
+                    .add_run(Run::new().add_footnote_reference(
+                        Footnote::new().add_content(Paragraph::new().add_run(Run::new().add_text("See clause 9."))),
+                    ))
+                    .add_run(Run::new().add_text(" unless stated otherwise.")),
+            )
+            .build()
+            .pack(&mut buffer)
+            .unwrap();
+
+        let package = DocxPackage::read(&buffer.into_inner()).unwrap();
+        assert_eq!(docx_to_markdown(&package.docx), "Liability is capped[^1] unless stated otherwise.");
+        assert_eq!(package.headers, vec!["Confidential".to_string()]);
+        assert_eq!(package.footers, vec!["Contract 42".to_string()]);
+        assert_eq!(notes_to_markdown(&package.footnotes), "[^1]: See clause 9.");
+        assert!(package.endnotes.is_empty());
+    }
+
+    #[test]
+    fn test_docx_rs_private_fields() {
+        assert_eq!(comment_end_id(&CommentRangeEnd::new(7)), Some(7));
+        assert_eq!(deleted_text(&DeleteText::new("gone")).as_deref(), Some("gone"));
+
+        let level = Level::new(0, Start::new(3), NumberFormat::new("decimal"), LevelText::new("%1."), LevelJc::new("left"));
+        assert_eq!(level_start_value(&level), Some(3));
+        let style = Style::new("Heading1", StyleType::Paragraph).name("heading 1").based_on("Normal");
+        assert_eq!(style_name(&style).as_deref(), Some("heading 1"));
+        assert_eq!(style_based_on(&style).as_deref(), Some("Normal"));
+        assert_eq!(style_based_on(&Style::new("Normal", StyleType::Paragraph)), None);
+
+        assert_eq!(row_grid_before(&TableRowProperty::new().grid_before(2)), 2);
+        assert_eq!(row_grid_before(&TableRowProperty::new()), 0);
+        let cell = TableCellProperty::new().grid_span(3).vertical_merge(VMergeType::Restart);
+        assert_eq!(cell_grid(&cell), (3, VerticalMerge::Restart));
+        let cell = TableCellProperty::new().vertical_merge(VMergeType::Continue);
+        assert_eq!(cell_grid(&cell), (1, VerticalMerge::Continue));
+        assert_eq!(cell_grid(&TableCellProperty::new()), (1, VerticalMerge::None));
+    }
+
+    #[test]
+    fn test_docx_review_modes() {
+        let comment = |id: usize, author: &str, text: &str| {
+            Comment::new(id)
+                .author(author)
+                .date("2024-05-01T09:30:00Z")
+                .add_paragraph(Paragraph::new().add_run(Run::new().add_text(text)))
+        };
+        let docx = round_trip(
+            Docx::new().add_paragraph(
+                Paragraph::new()
+                    .add_comment_start(comment(1, "Carol", "Is this final?"))
+                    .add_run(Run::new().add_text("Payment due in "))
+                    .add_comment_end(1)
+                    .add_comment_start(comment(2, "Dan", "Yes.").parent_comment_id(1))
+                    .add_comment_end(2)
+                    .add_delete(
+                        Delete::new()
+                            .author("Bob")
+                            .date("2024-05-02T08:00:00Z")
+                            .add_run(Run::new().add_delete_text("30")),
+                    )
+                    .add_insert(
+                        Insert::new(Run::new().add_text("45"))
+                            .author("Alice")
+                            .date("2024-05-03T08:00:00Z"),
+                    )
+                    .add_run(Run::new().add_text(" days.")),
+            ),
+        );
+
+        let render = |mode: ReviewMode| DocxMarkdownRenderer::new(&docx).review_mode(mode).render();
+        assert_eq!(render(ReviewMode::Accept), "Payment due in 45 days.");
+        assert_eq!(render(ReviewMode::Reject), "Payment due in 30 days.");
+        assert_eq!(
+            render(ReviewMode::Annotate),
+            "{==Payment due in ==}\
+             {>>Comment by Carol (2024-05-01): Is this final?<<}\
+             {>>Reply by Dan (2024-05-01): Yes.<<}\
+             {--30--}{>>Deleted by Bob (2024-05-02)<<}\
+             {++45++}{>>Inserted by Alice (2024-05-03)<<} days."
+        );
+        assert_eq!("Annotate".parse::<ReviewMode>(), Ok(ReviewMode::Annotate));
+        assert!("redline".parse::<ReviewMode>().is_err());
+    }
+
+    #[test]
+    fn test_docx_hyperlinks_and_bookmarks() {
+        let mut buffer = Cursor::new(Vec::new());
+        Docx::new()
+            .add_paragraph(
+                Paragraph::new()
+                    .add_bookmark_start(1, "scope")
+                    .add_run(Run::new().add_text("Scope"))
+                    .add_bookmark_end(1)
+                    .add_bookmark_start(2, "_GoBack")
+                    .add_bookmark_end(2),
+            )
+            .add_paragraph(
+                Paragraph::new()
+                    .add_run(Run::new().add_text("Read "))
+                    .add_hyperlink(
+                        Hyperlink::new("https://example.com/spec?a=1&b=2", HyperlinkType::External)
+                            .add_run(Run::new().add_text("the spec").bold()),
+                    )
+                    .add_run(Run::new().add_text(" and "))
+                    .add_hyperlink(
+                        Hyperlink::new("scope", HyperlinkType::Anchor).add_run(Run::new().add_text("[1] Scope")),
+                    )
+                    .add_run(Run::new().add_text(".")),
+            )
+            .build()
+            .pack(&mut buffer)
+            .unwrap();
+
+        let package = DocxPackage::read(&buffer.into_inner()).unwrap();
+        assert_eq!(
+            package.hyperlinks.values().collect::<Vec<_>>(),
+            vec!["https://example.com/spec?a=1&b=2"]
+        );
+        assert_eq!(
+            DocxMarkdownRenderer::new(&package.docx).with_hyperlinks(&package.hyperlinks).render(),
+            "<a id=\"scope\"></a>Scope\n\n\
+             Read [**the spec**](https://example.com/spec?a=1&b=2) and [\\[1\\] Scope](#scope)."
+        );
+    }
+
+    #[test]
+    fn test_read_docx_hyperlinks() {
+        use std::io::Write;
+        let rels_xml = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+            <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/logo.png"/>
+            <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com/a b?x=1&amp;y=2" TargetMode="External"/>
+        </Relationships>"#;
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("word/_rels/header1.xml.rels", zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(rels_xml.as_bytes()).unwrap();
+        let buffer = writer.finish().unwrap().into_inner();
+
+        let hyperlinks = read_docx_hyperlinks(&buffer, "word/header1.xml");
+        assert_eq!(hyperlinks.len(), 1);
+        assert_eq!(hyperlinks["rId2"], "https://example.com/a b?x=1&y=2");
+        assert_eq!(link_destination(&hyperlinks["rId2"]), "<https://example.com/a b?x=1&y=2>");
+        assert!(read_docx_hyperlinks(&buffer, "word/document.xml").is_empty());
+    }
+}
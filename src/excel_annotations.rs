@@ -0,0 +1,324 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Seek};
+
+use anyhow::Result;
+use quick_xml::events::Event;
+use zip::ZipArchive;
+
+use crate::excel_parser::{column_name, element_attributes, read_relationships, read_zip_part, CellRange};
+
+/// What kind of context is attached to a cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationKind {
+    /// A legacy note (the yellow sticky note of older Excel versions)
+    Note,
+    /// A threaded comment, with its replies
+    Comment,
+    Link,
+}
+
+/// A note, comment or hyperlink attached to a cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellAnnotation {
+    pub kind: AnnotationKind,
+    pub author: Option<String>,
+    /// Date of a threaded comment, as `YYYY-MM-DD`
+    pub date: Option<String>,
+    /// The text of a note or comment, or the target of a link
+    pub text: String,
+    /// Display text or tooltip of a link
+    pub label: Option<String>,
+    /// Whether a threaded comment was marked as resolved
+    pub resolved: bool,
+    pub replies: Vec<CellAnnotation>,
+}
+
+impl CellAnnotation {
+    fn new(kind: AnnotationKind, text: String) -> Self {
+        Self { kind, author: None, date: None, text, label: None, resolved: false, replies: Vec::new() }
+    }
+
+    /// Author and date, as ` by Ann Lee (2024-03-01)`
+    fn byline(&self) -> String {
+        match (&self.author, &self.date) {
+            (Some(author), Some(date)) => format!(" by {} ({})", author, date),
+            (Some(author), None) => format!(" by {}", author),
+            (None, Some(date)) => format!(" ({})", date),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// One markdown list line, without the leading `- `
+    fn describe(&self) -> String {
+        let byline = self.byline();
+        match self.kind {
+            AnnotationKind::Link => match &self.label {
+                Some(label) => format!("Link to {} ({})", self.text, label),
+                None => format!("Link to {}", self.text),
+            },
+            AnnotationKind::Note => format!("Note{}: {}", byline, self.text),
+            AnnotationKind::Comment => format!(
+                "Comment{}{}: {}",
+                byline,
+                if self.resolved { " [resolved]" } else { "" },
+                self.text
+            ),
+        }
+    }
+}
+
+/// Annotations of a sheet by 0-based `(row, column)`, in row-major order
+pub type SheetAnnotations = BTreeMap<(u32, u32), Vec<CellAnnotation>>;
+
+/// An "Annotations" section listing the annotations of the given cells by
+/// address, or `None` when none of them has any
+pub fn annotations_section<'a>(
+    annotations: impl IntoIterator<Item = (&'a (u32, u32), &'a Vec<CellAnnotation>)>,
+) -> Option<String> {
+    let mut lines = Vec::new();
+    for (&(row, column), cell_annotations) in annotations {
+        let address = format!("{}{}", column_name(column), row + 1);
+        for annotation in cell_annotations {
+            lines.push(format!("- **{}** {}", address, annotation.describe()));
+            lines.extend(annotation.replies.iter().map(|reply| format!("  - Reply{}: {}", reply.byline(), reply.text)));
+        }
+    }
+    (!lines.is_empty()).then(|| format!("### Annotations\n\n{}", lines.join("\n")))
+}
+
+/// Display names of the people who wrote threaded comments, by person id
+pub(crate) fn read_persons<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<HashMap<String, String>> {
+    let mut persons = HashMap::new();
+    for (_, kind, target) in read_relationships(archive, "xl/workbook.xml")? {
+        if !kind.ends_with("/person") {
+            continue;
+        }
+        let xml = read_zip_part(archive, &target)?;
+        for element in read_elements(&xml)? {
+            if element.name == "person"
+                && let (Some(id), Some(name)) = (element.attributes.get("id"), element.attributes.get("displayName"))
+            {
+                persons.insert(id.clone(), name.clone());
+            }
+        }
+    }
+    Ok(persons)
+}
+
+/// Read the notes, threaded comments and hyperlinks of a worksheet part.
+/// `hyperlinks` are the attributes of the sheet's `hyperlink` elements.
+pub(crate) fn read_sheet_annotations<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    sheet_part: &str,
+    hyperlinks: &[HashMap<String, String>],
+    persons: &HashMap<String, String>,
+) -> Result<SheetAnnotations> {
+    let relationships = read_relationships(archive, sheet_part)?;
+    let mut annotations = SheetAnnotations::new();
+
+    // Threaded comments come first; Excel also writes a placeholder note for each
+    let mut commented = HashSet::new();
+    for (_, _, target) in relationships.iter().filter(|(_, kind, _)| kind.ends_with("/threadedComment")) {
+        let xml = read_zip_part(archive, target)?;
+        for (position, comment) in read_threaded_comments(&xml, persons)? {
+            commented.insert(position);
+            annotations.entry(position).or_default().push(comment);
+        }
+    }
+    for (_, _, target) in relationships.iter().filter(|(_, kind, _)| kind.ends_with("/comments")) {
+        let xml = read_zip_part(archive, target)?;
+        for (position, note) in read_notes(&xml)? {
+            if !commented.contains(&position) {
+                annotations.entry(position).or_default().push(note);
+            }
+        }
+    }
+
+    let targets: HashMap<&str, &str> = relationships.iter()
+        .map(|(id, _, target)| (id.as_str(), target.as_str()))
+        .collect();
+    for attributes in hyperlinks {
+        let Some(reference) = attributes.get("ref").and_then(|r| CellRange::parse(r).ok()) else {
+            continue;
+        };
+        // External links are relationships; links within the workbook are locations
+        let target = attributes.get("id").and_then(|id| targets.get(id.as_str())).map(|target| target.to_string())
+            .or_else(|| attributes.get("location").map(|location| format!("#{}", location)));
+        let Some(target) = target else {
+            continue;
+        };
+        let mut link = CellAnnotation::new(AnnotationKind::Link, target);
+        link.label = attributes.get("tooltip")
+            .or_else(|| attributes.get("display"))
+            .map(|label| collapse_whitespace(label))
+            .filter(|label| !label.is_empty() && *label != link.text);
+        annotations.entry(reference.start).or_default().push(link);
+    }
+    Ok(annotations)
+}
+
+/// Legacy notes of a comments part, by cell. The author name Excel puts at the
+/// start of the text is left out.
+fn read_notes(xml: &str) -> Result<Vec<((u32, u32), CellAnnotation)>> {
+    let mut authors = Vec::new();
+    let mut notes = Vec::new();
+    for element in read_elements(xml)? {
+        match element.name.as_str() {
+            "author" => authors.push(collapse_whitespace(&element.text)),
+            "comment" => {
+                let Some(reference) = element.attributes.get("ref").and_then(|r| CellRange::parse(r).ok()) else {
+                    continue;
+                };
+                let text = collapse_whitespace(&element.text);
+                if text.starts_with("[Threaded comment]") {
+                    continue;
+                }
+                let author = element.attributes.get("authorId")
+                    .and_then(|id| id.parse::<usize>().ok())
+                    .and_then(|id| authors.get(id))
+                    .filter(|author| !author.is_empty())
+                    .cloned();
+                let text = author.as_ref()
+                    .and_then(|author| text.strip_prefix(author.as_str()))
+                    .and_then(|rest| rest.strip_prefix(':'))
+                    .map_or(text.clone(), |rest| rest.trim_start().to_string());
+                let mut note = CellAnnotation::new(AnnotationKind::Note, text);
+                note.author = author;
+                notes.push((reference.start, note));
+            }
+            _ => {}
+        }
+    }
+    Ok(notes)
+}
+
+/// Threaded comments of a part, by cell, with replies nested under the comment
+/// that starts their thread
+fn read_threaded_comments(xml: &str, persons: &HashMap<String, String>) -> Result<Vec<((u32, u32), CellAnnotation)>> {
+    let mut comments: Vec<((u32, u32), CellAnnotation)> = Vec::new();
+    let mut thread_index: HashMap<String, usize> = HashMap::new();
+    for element in read_elements(xml)? {
+        if element.name != "threadedComment" {
+            continue;
+        }
+        let attributes = &element.attributes;
+        let Some(reference) = attributes.get("ref").and_then(|r| CellRange::parse(r).ok()) else {
+            continue;
+        };
+        let mut comment = CellAnnotation::new(AnnotationKind::Comment, collapse_whitespace(&element.text));
+        comment.author = attributes.get("personId").and_then(|id| persons.get(id)).cloned();
+        comment.date = attributes.get("dT").and_then(|date| date.get(..10)).map(str::to_string);
+        comment.resolved = attributes.get("done").is_some_and(|done| done == "1" || done == "true");
+
+        match attributes.get("parentId").and_then(|parent| thread_index.get(parent)) {
+            Some(&index) => comments[index].1.replies.push(comment),
+            None => {
+                if let Some(id) = attributes.get("id") {
+                    thread_index.insert(id.clone(), comments.len());
+                }
+                comments.push((reference.start, comment));
+            }
+        }
+    }
+    Ok(comments)
+}
+
+/// An element of interest with its attributes and the text it contains
+struct TextElement {
+    name: String,
+    attributes: HashMap<String, String>,
+    text: String,
+}
+
+/// The elements of a part that matter for annotations, with the text of
+/// their `t` descendants
+fn read_elements(xml: &str) -> Result<Vec<TextElement>> {
+    const NAMES: [&str; 4] = ["author", "comment", "threadedComment", "person"];
+    let mut elements: Vec<TextElement> = Vec::new();
+    // Index of the open element of interest, and whether text is being collected
+    let mut open: Option<usize> = None;
+    let mut in_text = false;
+    let mut reader = quick_xml::Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                if NAMES.contains(&name.as_str()) {
+                    open = Some(elements.len());
+                    // Authors hold their name directly; comments in `t` or `text` elements
+                    in_text = name == "author";
+                    elements.push(TextElement { name, attributes: element_attributes(&e), text: String::new() });
+                } else if open.is_some() && (name == "t" || name == "text") {
+                    in_text = true;
+                }
+            }
+            Event::Empty(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                if NAMES.contains(&name.as_str()) {
+                    elements.push(TextElement { name, attributes: element_attributes(&e), text: String::new() });
+                }
+            }
+            Event::End(e) => {
+                let name = e.local_name();
+                if name.as_ref() == b"t" || name.as_ref() == b"text" {
+                    in_text = false;
+                } else if open.is_some_and(|index| elements[index].name.as_bytes() == name.as_ref()) {
+                    open = None;
+                    in_text = false;
+                }
+            }
+            Event::Text(text) if in_text => {
+                if let Some(index) = open {
+                    elements[index].text.push_str(&text.decode()?);
+                }
+            }
+            Event::GeneralRef(reference) if in_text => {
+                if let Some(index) = open {
+                    if let Some(ch) = reference.resolve_char_ref()? {
+                        elements[index].text.push(ch);
+                    } else if let Some(text) = quick_xml::escape::resolve_predefined_entity(&reference.decode()?) {
+                        elements[index].text.push_str(text);
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(elements)
+}
+
+/// Text on one line, with runs of whitespace and line breaks as single spaces
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_notes_strips_author_prefix() {
+        let xml = r#"<comments xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><authors><author>Ann Lee</author><author>tc={1}</author></authors><commentList><comment ref="B3" authorId="0"><text><r><rPr><b/></rPr><t>Ann Lee:</t></r><r><t xml:space="preserve">
+Check this &amp; that</t></r></text></comment><comment ref="C4" authorId="1"><text><t>[Threaded comment]
+
+Your version of Excel allows you to read this threaded comment</t></text></comment></commentList></comments>"#;
+        let notes = read_notes(xml).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].0, (2, 1));
+        assert_eq!(notes[0].1.describe(), "Note by Ann Lee: Check this & that");
+    }
+
+    #[test]
+    fn test_threaded_comment_replies() {
+        let xml = r#"<ThreadedComments xmlns="http://schemas.microsoft.com/office/spreadsheetml/2018/threadedcomments"><threadedComment ref="A2" dT="2024-03-01T10:15:30.00" personId="{P1}" id="{C1}" done="1"><text>Is this final?</text></threadedComment><threadedComment ref="A2" dT="2024-03-02T08:00:00.00" personId="{P2}" id="{C2}" parentId="{C1}"><text>Yes</text></threadedComment></ThreadedComments>"#;
+        let persons = HashMap::from([("{P1}".to_string(), "Bo Chen".to_string())]);
+        let comments = read_threaded_comments(xml, &persons).unwrap();
+        assert_eq!(comments.len(), 1);
+        let annotations = SheetAnnotations::from([(comments[0].0, vec![comments[0].1.clone()])]);
+        assert_eq!(
+            annotations_section(&annotations).unwrap(),
+            "### Annotations\n\n- **A2** Comment by Bo Chen (2024-03-01) [resolved]: Is this final?\n  - Reply (2024-03-02): Yes"
+        );
+    }
+}
@@ -0,0 +1,2031 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anyhow::{Context, Result};
+use calamine::{Data, Range, Reader, SheetVisible, Sheets};
+use quick_xml::events::Event;
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::document_parser::{cells_to_markdown_table, open_excel_workbook};
+use crate::excel_annotations::{annotations_section, read_persons, read_sheet_annotations, SheetAnnotations};
+use crate::markdown_table::text_table;
+use crate::number_format::{builtin_format_code, format_general, format_number, is_date_format, iso_datetime, iso_duration};
+use crate::structured_output::{render_structured, OutputFormat};
+use crate::shared_utils::{validate_file_path, FileTextResult};
+
+/// Largest row and column indices of an Excel worksheet (0-based)
+const MAX_ROW: u32 = 1_048_575;
+const MAX_COLUMN: u32 = 16_383;
+
+/// A rectangle of cells in A1 notation, optionally qualified by a sheet name.
+/// Positions are 0-based `(row, column)` pairs and both corners are inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellRange {
+    pub sheet: Option<String>,
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+}
+
+impl CellRange {
+    /// Parse `B3:H200`, `Sheet2!B3:H200`, `'My Sheet'!$A$1`, `A:C` or `3:10`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let (sheet, reference) = match spec.rfind('!') {
+            Some(index) => (Some(unquote_sheet_name(&spec[..index])?), &spec[index + 1..]),
+            None => (None, spec),
+        };
+
+        let reference = reference.replace('$', "");
+        let (first, last) = reference.split_once(':').unwrap_or((&reference, &reference));
+        let first = parse_cell_reference(first)
+            .ok_or_else(|| anyhow::anyhow!("Invalid cell reference '{}' in range '{}'", first, spec))?;
+        let last = parse_cell_reference(last)
+            .ok_or_else(|| anyhow::anyhow!("Invalid cell reference '{}' in range '{}'", last, spec))?;
+
+        // Whole columns (`A:C`) and whole rows (`3:10`) leave the other axis open
+        let (start, end) = match (first, last) {
+            ((Some(r1), Some(c1)), (Some(r2), Some(c2))) => ((r1, c1), (r2, c2)),
+            ((None, Some(c1)), (None, Some(c2))) => ((0, c1), (MAX_ROW, c2)),
+            ((Some(r1), None), (Some(r2), None)) => ((r1, 0), (r2, MAX_COLUMN)),
+            _ => return Err(anyhow::anyhow!("Invalid range '{}'", spec)),
+        };
+
+        Ok(Self {
+            sheet,
+            start: (start.0.min(end.0), start.1.min(end.1)),
+            end: (start.0.max(end.0), start.1.max(end.1)),
+        })
+    }
+
+    /// The part of this range covered by a sheet's used cells, if any
+    pub fn clip_to(&self, range: &Range<Data>) -> Option<((u32, u32), (u32, u32))> {
+        let (used_start, used_end) = (range.start()?, range.end()?);
+        let start = (self.start.0.max(used_start.0), self.start.1.max(used_start.1));
+        let end = (self.end.0.min(used_end.0), self.end.1.min(used_end.1));
+        (start.0 <= end.0 && start.1 <= end.1).then_some((start, end))
+    }
+}
+
+impl fmt::Display for CellRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(sheet) = &self.sheet {
+            if sheet.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                write!(f, "{}!", sheet)?;
+            } else {
+                write!(f, "'{}'!", sheet.replace('\'', "''"))?;
+            }
+        }
+        write!(f, "{}{}", column_name(self.start.1), self.start.0 + 1)?;
+        if self.start != self.end {
+            write!(f, ":{}{}", column_name(self.end.1), self.end.0 + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The area of a sheet holding cells, such as `A1:L500`
+pub fn used_area(range: &Range<Data>) -> Option<CellRange> {
+    Some(CellRange { sheet: None, start: range.start()?, end: range.end()? })
+}
+
+/// A window of worksheet rows, numbered as in Excel (the first row is 1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowWindow {
+    /// First row of the window, 0-based
+    pub start: u32,
+    /// Last row of the window, 0-based and inclusive; `None` runs to the end of the sheet
+    pub end: Option<u32>,
+}
+
+impl RowWindow {
+    /// Parse `1-500`, `501-` (to the last row) or a single row such as `42`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let parse_row = |row: &str| -> Result<u32> {
+            let row: u32 = row.trim().parse()
+                .map_err(|_| anyhow::anyhow!("Invalid row number '{}' in '{}'", row.trim(), spec))?;
+            row.checked_sub(1).ok_or_else(|| anyhow::anyhow!("Row numbers start at 1: '{}'", spec))
+        };
+
+        let (start, end) = match spec.split_once('-') {
+            Some((start, end)) if end.trim().is_empty() => (parse_row(start)?, None),
+            Some((start, end)) => (parse_row(start)?, Some(parse_row(end)?)),
+            None => {
+                let row = parse_row(spec)?;
+                (row, Some(row))
+            }
+        };
+        if end.is_some_and(|end| end < start) {
+            return Err(anyhow::anyhow!("Row window '{}' ends before it starts", spec));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+/// Column letters for a 0-based column index (0 → `A`, 27 → `AB`)
+pub fn column_name(column: u32) -> String {
+    let mut name = Vec::new();
+    let mut remaining = column + 1;
+    while remaining > 0 {
+        let digit = (remaining - 1) % 26;
+        name.push(b'A' + digit as u8);
+        remaining = (remaining - 1) / 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Parse `B3`, `B` or `3` into optional 0-based row and column indices
+fn parse_cell_reference(reference: &str) -> Option<(Option<u32>, Option<u32>)> {
+    let reference = reference.trim();
+    let split = reference.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(reference.len());
+    let (letters, digits) = reference.split_at(split);
+    if letters.is_empty() && digits.is_empty() {
+        return None;
+    }
+
+    let column = if letters.is_empty() {
+        None
+    } else {
+        let column = letters.bytes().try_fold(0u32, |column, letter| {
+            column.checked_mul(26)?.checked_add((letter.to_ascii_uppercase() - b'A') as u32 + 1)
+        })?;
+        Some(column - 1).filter(|&column| column <= MAX_COLUMN)
+    };
+    let row = if digits.is_empty() {
+        None
+    } else {
+        let row: u32 = digits.parse().ok()?;
+        Some(row.checked_sub(1)?).filter(|&row| row <= MAX_ROW)
+    };
+
+    if (!letters.is_empty() && column.is_none()) || (!digits.is_empty() && row.is_none()) {
+        return None;
+    }
+    Some((row, column))
+}
+
+/// Strip the quotes Excel puts around sheet names with spaces or punctuation
+fn unquote_sheet_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    match name.strip_prefix('\'').and_then(|name| name.strip_suffix('\'')) {
+        Some(quoted) => Ok(quoted.replace("''", "'")),
+        None if name.is_empty() => Err(anyhow::anyhow!("Missing sheet name before '!'")),
+        None => Ok(name.to_string()),
+    }
+}
+
+/// Split a reference list such as a multi-area named range on commas outside quotes
+pub(crate) fn split_areas(spec: &str) -> Vec<&str> {
+    let mut areas = Vec::new();
+    let mut in_quotes = false;
+    let mut area_start = 0;
+    for (index, ch) in spec.char_indices() {
+        match ch {
+            '\'' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                areas.push(&spec[area_start..index]);
+                area_start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    areas.push(&spec[area_start..]);
+    areas.into_iter().map(str::trim).filter(|area| !area.is_empty()).collect()
+}
+
+/// Resolve a `range` parameter against a workbook: a defined name or table
+/// name (matched case-insensitively, like Excel does) or one or more A1 references
+pub fn resolve_range_parameter<RS>(workbook: &Sheets<RS>, tables: &[ExcelTable], spec: &str) -> Result<Vec<CellRange>>
+where
+    RS: std::io::Read + std::io::Seek,
+{
+    let spec = spec.trim();
+    if spec.is_empty() {
+        return Err(anyhow::anyhow!("Range parameter is empty"));
+    }
+    if let Some(table) = tables.iter().find(|table| table.name.eq_ignore_ascii_case(spec)) {
+        return Ok(vec![table.range.clone()]);
+    }
+
+    let reference = workbook.defined_names().iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(spec))
+        .map(|(_, formula)| formula.trim_start_matches('=').to_string());
+    let is_named = reference.is_some();
+    let reference = reference.unwrap_or_else(|| spec.to_string());
+
+    split_areas(&reference).into_iter()
+        .map(|area| {
+            CellRange::parse(area).map_err(|e| if is_named {
+                anyhow::anyhow!("Named range '{}' refers to '{}', which is not a cell range: {}", spec, reference, e)
+            } else {
+                anyhow::anyhow!("'{}' is neither a defined name, a table nor an A1 range: {}", spec, e)
+            })
+        })
+        .collect()
+}
+
+/// How formulas are shown when reading spreadsheets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FormulaMode {
+    /// Cached values only
+    #[default]
+    Values,
+    /// The formula instead of the value, for cells that have one
+    Formulas,
+    /// The value followed by its formula, as `42 (=SUM(A1:A3))`
+    Both,
+    /// A `Cell | Formula | Value` listing of the formula cells only
+    FormulasOnly,
+}
+
+impl FromStr for FormulaMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "values" => Ok(Self::Values),
+            "formulas" => Ok(Self::Formulas),
+            "both" => Ok(Self::Both),
+            "formulas_only" => Ok(Self::FormulasOnly),
+            other => Err(format!(
+                "Invalid formula_mode '{}': expected 'values', 'formulas', 'both' or 'formulas_only'",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FormulaMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Values => "values",
+            Self::Formulas => "formulas",
+            Self::Both => "both",
+            Self::FormulasOnly => "formulas_only",
+        })
+    }
+}
+
+/// Number formats of a workbook, used to show cells the way Excel does.
+/// Format codes are read from XLSX packages; other formats fall back to
+/// General for numbers and ISO 8601 for the dates calamine recognises.
+#[derive(Debug, Default)]
+pub struct WorkbookFormats {
+    file_path: String,
+    date_1904: bool,
+    /// Format code of each cell format (`cellXfs` entry); `None` for General
+    cell_formats: Vec<Option<String>>,
+    /// Worksheet part of each sheet name
+    sheet_parts: HashMap<String, String>,
+    /// Display names of the authors of threaded comments, by person id
+    persons: HashMap<String, String>,
+    /// Layout of each sheet read so far, so that each sheet is parsed once
+    sheet_layouts: Mutex<HashMap<String, Arc<SheetLayout>>>,
+}
+
+/// Values over inclusive column spans of a row, as `(first column, last column, value)`
+type ColumnRuns<T> = Vec<(u32, u32, T)>;
+
+/// The merged regions covering a band of rows (0-based, inclusive), as
+/// `(first column, last column, top-left cell)` sorted by column
+#[derive(Debug)]
+struct MergedBand {
+    first_row: u32,
+    last_row: u32,
+    regions: ColumnRuns<(u32, u32)>,
+}
+
+impl MergedBand {
+    /// Split merged regions, given by their corners, into bands of rows covered
+    /// by the same regions, so that each region is stored once per band rather
+    /// than once per row
+    fn from_regions(mut regions: Vec<((u32, u32), (u32, u32))>) -> Vec<Self> {
+        regions.sort_unstable_by_key(|&(start, _)| start.0);
+        let mut bounds: Vec<u32> = regions.iter()
+            .flat_map(|&(start, end)| [start.0, end.0.saturating_add(1)])
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        let mut bands = Vec::new();
+        let mut active: Vec<((u32, u32), (u32, u32))> = Vec::new();
+        let mut next = 0;
+        for pair in bounds.windows(2) {
+            let (first_row, last_row) = (pair[0], pair[1] - 1);
+            active.retain(|&(_, end)| end.0 >= first_row);
+            while let Some(&region) = regions.get(next).filter(|(start, _)| start.0 <= first_row) {
+                active.push(region);
+                next += 1;
+            }
+            if active.is_empty() {
+                continue;
+            }
+            let mut regions: ColumnRuns<(u32, u32)> = active.iter().map(|&(start, end)| (start.1, end.1, start)).collect();
+            regions.sort_unstable_by_key(|&(first, _, _)| first);
+            bands.push(Self { first_row, last_row, regions });
+        }
+        bands
+    }
+}
+
+/// What a worksheet part says about showing its cells
+#[derive(Debug, Default)]
+struct SheetLayout {
+    /// Runs of adjacent cells with the same non-General cell format, as
+    /// `(first column, last column, cell format)` sorted by column, by 0-based row
+    styles: HashMap<u32, ColumnRuns<usize>>,
+    /// Merged regions by the bands of rows they cover, sorted by row
+    merged_bands: Vec<MergedBand>,
+    hidden_rows: HashSet<u32>,
+    /// Inclusive spans of hidden columns
+    hidden_columns: Vec<(u32, u32)>,
+    /// Notes, threaded comments and hyperlinks by 0-based `(row, column)`
+    annotations: SheetAnnotations,
+}
+
+impl WorkbookFormats {
+    /// Read the number formats of a workbook. Files that are not XLSX packages,
+    /// or whose styles cannot be read, get the default formatting.
+    pub fn open(file_path: &str) -> Self {
+        match Self::read_xlsx(file_path) {
+            Ok(formats) => formats,
+            Err(e) => {
+                log::debug!("No XLSX number formats for {}: {}", file_path, e);
+                Self { file_path: file_path.to_string(), ..Self::default() }
+            }
+        }
+    }
+
+    fn read_xlsx(file_path: &str) -> Result<Self> {
+        let file = File::open(file_path)
+            .with_context(|| format!("Failed to open Excel file: {}", file_path))?;
+        let mut archive = ZipArchive::new(BufReader::new(file))?;
+
+        let (date_1904, sheet_parts) = read_workbook_sheets(&mut archive)?;
+        let sheet_parts = sheet_parts.into_iter().collect();
+        let persons = read_persons(&mut archive).unwrap_or_else(|e| {
+            log::warn!("Failed to read the comment authors of {}: {}", file_path, e);
+            HashMap::new()
+        });
+        // Workbooks without styles still have merged cells and hidden rows to read
+        let styles = read_zip_part(&mut archive, "xl/styles.xml").unwrap_or_default();
+
+        // Custom formats, then the number format of each cell format
+        let mut custom_formats = HashMap::new();
+        let mut cell_formats = Vec::new();
+        let mut in_cell_formats = false;
+        let mut reader = quick_xml::Reader::from_str(&styles);
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) if e.local_name().as_ref() == b"cellXfs" => in_cell_formats = true,
+                Event::End(e) if e.local_name().as_ref() == b"cellXfs" => in_cell_formats = false,
+                Event::Start(e) | Event::Empty(e) => {
+                    let attributes = element_attributes(&e);
+                    match e.local_name().as_ref() {
+                        b"numFmt" => {
+                            if let (Some(id), Some(code)) = (attributes.get("numFmtId"), attributes.get("formatCode")) {
+                                custom_formats.insert(id.parse::<u32>().unwrap_or(0), code.clone());
+                            }
+                        }
+                        b"xf" if in_cell_formats => {
+                            let id = attributes.get("numFmtId").and_then(|id| id.parse::<u32>().ok()).unwrap_or(0);
+                            cell_formats.push(id);
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        let cell_formats = cell_formats.into_iter()
+            .map(|id| {
+                custom_formats.get(&id).cloned()
+                    .or_else(|| builtin_format_code(id).map(str::to_string))
+                    .filter(|code| !code.eq_ignore_ascii_case("general"))
+            })
+            .collect();
+
+        Ok(Self { file_path: file_path.to_string(), date_1904, cell_formats, sheet_parts, persons, ..Self::default() })
+    }
+
+    /// The formatter for one sheet, with the number format of each styled cell,
+    /// its merged regions, its hidden rows and columns, and the notes, comments
+    /// and hyperlinks of its cells. Each sheet is read once and kept for later calls.
+    pub fn sheet(&self, sheet_name: &str) -> CellFormatter<'_> {
+        let layout = self.sheet_layouts.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(sheet_name.to_string())
+            .or_insert_with(|| Arc::new(self.read_layout(sheet_name)))
+            .clone();
+        CellFormatter { date_1904: self.date_1904, cell_formats: &self.cell_formats, layout, ..CellFormatter::default() }
+    }
+
+    fn read_layout(&self, sheet_name: &str) -> SheetLayout {
+        let mut layout = SheetLayout::default();
+        let Some(part) = self.sheet_parts.get(sheet_name) else {
+            return layout;
+        };
+
+        let result = (|| -> Result<()> {
+            let file = File::open(&self.file_path)?;
+            let mut archive = ZipArchive::new(BufReader::new(file))?;
+            let mut hyperlinks = Vec::new();
+            let mut merged_regions = Vec::new();
+            {
+                let xml = archive.by_name(part)
+                    .with_context(|| format!("Missing workbook part: {}", part))?;
+                let mut reader = quick_xml::Reader::from_reader(BufReader::new(xml));
+                let mut buffer = Vec::new();
+                // Rows and cells may omit their reference, continuing from the previous one
+                let (mut row, mut column) = (0u32, 0u32);
+                loop {
+                    match reader.read_event_into(&mut buffer)? {
+                        Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                            b"row" => {
+                                let attributes = element_attributes(&e);
+                                row = attributes.get("r").and_then(|r| r.parse::<u32>().ok()).map_or(row + 1, |r| r);
+                                column = 0;
+                                if attributes.get("hidden").is_some_and(|value| is_true(value)) {
+                                    layout.hidden_rows.insert(row.saturating_sub(1));
+                                }
+                            }
+                            b"col" => {
+                                let attributes = element_attributes(&e);
+                                let bound = |name: &str| attributes.get(name).and_then(|value| value.parse::<u32>().ok());
+                                if let (Some(min), Some(max)) = (bound("min"), bound("max"))
+                                    && attributes.get("hidden").is_some_and(|value| is_true(value))
+                                {
+                                    layout.hidden_columns.push((min.saturating_sub(1), max.saturating_sub(1)));
+                                }
+                            }
+                            b"hyperlink" => hyperlinks.push(element_attributes(&e)),
+                            b"mergeCell" => {
+                                let region = element_attributes(&e).get("ref").and_then(|r| CellRange::parse(r).ok());
+                                if let Some(region) = region {
+                                    merged_regions.push((region.start, region.end));
+                                }
+                            }
+                            b"c" => {
+                                let attributes = element_attributes(&e);
+                                let position = attributes.get("r")
+                                    .and_then(|r| CellRange::parse(r).ok())
+                                    .map_or((row.saturating_sub(1), column), |cell| cell.start);
+                                column = position.1 + 1;
+                                let style = attributes.get("s").and_then(|s| s.parse::<usize>().ok())
+                                    .filter(|&s| self.cell_formats.get(s).is_some_and(Option::is_some));
+                                if let Some(style) = style {
+                                    let runs = layout.styles.entry(position.0).or_default();
+                                    match runs.last_mut() {
+                                        Some(run) if run.1 + 1 == position.1 && run.2 == style => run.1 = position.1,
+                                        _ => runs.push((position.1, position.1, style)),
+                                    }
+                                }
+                            }
+                            _ => {}
+                        },
+                        Event::Eof => break,
+                        _ => {}
+                    }
+                    buffer.clear();
+                }
+            }
+            for runs in layout.styles.values_mut() {
+                runs.sort_unstable_by_key(|&(first, _, _)| first);
+            }
+            layout.merged_bands = MergedBand::from_regions(merged_regions);
+
+            match read_sheet_annotations(&mut archive, part, &hyperlinks, &self.persons) {
+                Ok(annotations) => layout.annotations = annotations,
+                Err(e) => log::warn!("Failed to read the comments and links of sheet '{}': {}", sheet_name, e),
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            log::warn!("Failed to read the layout of sheet '{}': {}", sheet_name, e);
+        }
+        layout
+    }
+}
+
+/// Formats the cells of one sheet as Excel displays them
+#[derive(Debug, Default)]
+pub struct CellFormatter<'a> {
+    date_1904: bool,
+    /// Format code of each cell format of the workbook; `None` for General
+    cell_formats: &'a [Option<String>],
+    /// Styles, merged regions, hidden rows and columns and annotations of the sheet
+    layout: Arc<SheetLayout>,
+    /// Formulas of the sheet, shown according to `formula_mode`
+    formulas: Option<Range<String>>,
+    formula_mode: FormulaMode,
+    /// Whether cells are rendered as a markdown table or as typed CSV or JSON
+    output_format: OutputFormat,
+}
+
+impl CellFormatter<'_> {
+    /// Show the sheet's formulas as well as, or instead of, the cached values
+    pub fn with_formulas(mut self, formulas: Range<String>, formula_mode: FormulaMode) -> Self {
+        self.formulas = Some(formulas);
+        self.formula_mode = formula_mode;
+        self
+    }
+
+    /// Render cells as typed CSV or JSON instead of a markdown table
+    pub fn with_output_format(mut self, output_format: OutputFormat) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
+    /// The formula of the cell at an absolute 0-based position, with its leading `=`
+    pub fn formula(&self, position: (u32, u32)) -> Option<String> {
+        self.formulas.as_ref()?
+            .get_value(position)
+            .filter(|formula| !formula.is_empty())
+            .map(|formula| format!("={}", formula.trim_start_matches('=')))
+    }
+
+    /// Display text of the cell at an absolute 0-based position
+    pub fn format(&self, position: (u32, u32), cell: &Data) -> String {
+        let value = self.format_value(position, cell);
+        match self.formula_mode {
+            FormulaMode::Formulas => self.formula(position).unwrap_or(value),
+            FormulaMode::Both => match self.formula(position) {
+                Some(formula) if value.is_empty() => formula,
+                Some(formula) => format!("{} ({})", value, formula),
+                None => value,
+            },
+            FormulaMode::Values | FormulaMode::FormulasOnly => value,
+        }
+    }
+
+    /// List the formula cells between two corners, with their cached values
+    pub fn formula_listing(&self, values: &Range<Data>, start: (u32, u32), end: (u32, u32)) -> String {
+        let Some(formulas) = &self.formulas else {
+            return "*No formulas*".to_string();
+        };
+        let (first_row, first_column) = formulas.start().unwrap_or((0, 0));
+        let mut rows = vec![vec!["Cell".to_string(), "Formula".to_string(), "Value".to_string()]];
+        for (row, column, formula) in formulas.used_cells() {
+            let position = (first_row + row as u32, first_column + column as u32);
+            let inside = (start.0..=end.0).contains(&position.0) && (start.1..=end.1).contains(&position.1);
+            if !inside || formula.is_empty() {
+                continue;
+            }
+            let value = values.get_value(position)
+                .map(|cell| self.format_value(position, cell))
+                .unwrap_or_default();
+            rows.push(vec![
+                format!("{}{}", column_name(position.1), position.0 + 1),
+                format!("={}", formula.trim_start_matches('=')),
+                value,
+            ]);
+        }
+
+        if rows.len() == 1 {
+            "*No formulas*".to_string()
+        } else {
+            text_table(rows)
+        }
+    }
+
+    /// Display text of the cached value at an absolute 0-based position
+    fn format_value(&self, position: (u32, u32), cell: &Data) -> String {
+        let code = self.format_code(position);
+        match cell {
+            Data::Empty => String::new(),
+            Data::String(text) => text.clone(),
+            Data::Bool(value) => if *value { "TRUE" } else { "FALSE" }.to_string(),
+            Data::Int(value) => match code {
+                Some(code) => format_number(*value as f64, code, self.date_1904),
+                None => value.to_string(),
+            },
+            Data::Float(value) => match code {
+                Some(code) => format_number(*value, code, self.date_1904),
+                None => format_general(*value),
+            },
+            Data::DateTime(value) => match code {
+                Some(code) => format_number(value.as_f64(), code, self.date_1904),
+                None if value.is_duration() => format_number(value.as_f64(), "[h]:mm:ss", false),
+                None => match value.as_datetime() {
+                    Some(datetime) if datetime.time() == chrono::NaiveTime::MIN => datetime.format("%Y-%m-%d").to_string(),
+                    Some(datetime) => datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    None => format_general(value.as_f64()),
+                },
+            },
+            Data::DateTimeIso(text) | Data::DurationIso(text) => text.clone(),
+            Data::Error(error) => error.to_string(),
+        }
+    }
+
+    /// Typed value of the cell at an absolute 0-based position: numbers, booleans
+    /// and text as they are, and dates, times and durations in ISO 8601
+    pub fn typed_value(&self, position: (u32, u32), cell: &Data) -> Value {
+        let code = self.format_code(position).filter(|code| is_date_format(code));
+        let serial = |value: f64, code: &str| {
+            if is_elapsed_time_format(code) {
+                Value::String(iso_duration(value))
+            } else {
+                iso_datetime(value, self.date_1904).map_or_else(|| number_value(value), Value::String)
+            }
+        };
+        match cell {
+            Data::Empty => Value::Null,
+            Data::String(text) => Value::String(text.clone()),
+            Data::Bool(value) => Value::Bool(*value),
+            Data::Int(value) => match code {
+                Some(code) => serial(*value as f64, code),
+                None => Value::from(*value),
+            },
+            Data::Float(value) => match code {
+                Some(code) => serial(*value, code),
+                None => number_value(*value),
+            },
+            Data::DateTime(value) if value.is_duration() => Value::String(iso_duration(value.as_f64())),
+            Data::DateTime(value) => serial(value.as_f64(), code.unwrap_or_default()),
+            Data::DateTimeIso(text) | Data::DurationIso(text) => Value::String(text.clone()),
+            Data::Error(error) => Value::String(error.to_string()),
+        }
+    }
+
+    /// Whether a cell holds a date or time, rather than a plain number or a duration
+    pub fn is_date(&self, position: (u32, u32), cell: &Data) -> bool {
+        let code = self.format_code(position);
+        match cell {
+            Data::DateTime(value) => !value.is_duration() && !code.is_some_and(is_elapsed_time_format),
+            Data::Int(_) | Data::Float(_) => code.is_some_and(|code| is_date_format(code) && !is_elapsed_time_format(code)),
+            _ => false,
+        }
+    }
+
+    /// The visible cells of a row between two columns (absolute, inclusive) with
+    /// the position their value comes from. Cells covered by a merged region
+    /// take its top-left value.
+    fn row_cells<'r>(
+        &'r self,
+        range: &'r Range<Data>,
+        row: u32,
+        (first_column, last_column): (u32, u32),
+    ) -> impl Iterator<Item = ((u32, u32), &'r Data)> + 'r {
+        (first_column..=last_column)
+            .filter(|&column| !self.is_column_hidden(column))
+            .map(move |column| {
+                let source = self.merged_origin((row, column)).unwrap_or((row, column));
+                (source, range.get_value(source).unwrap_or(&Data::Empty))
+            })
+    }
+
+    /// Display text of the visible cells of a row between two columns (absolute,
+    /// inclusive). Cells covered by a merged region repeat its top-left value.
+    pub fn format_row(&self, range: &Range<Data>, row: u32, columns: (u32, u32)) -> Vec<String> {
+        self.row_cells(range, row, columns).map(|(source, cell)| self.format(source, cell)).collect()
+    }
+
+    /// Typed values of the visible cells of a row, like `format_row`
+    pub fn typed_row(&self, range: &Range<Data>, row: u32, columns: (u32, u32)) -> Vec<Value> {
+        self.row_cells(range, row, columns).map(|(source, cell)| self.typed_value(source, cell)).collect()
+    }
+
+    /// Display text of the visible rows between two corners (absolute, inclusive)
+    pub fn format_rows<'r>(
+        &'r self,
+        range: &'r Range<Data>,
+        start: (u32, u32),
+        end: (u32, u32),
+    ) -> impl Iterator<Item = Vec<String>> + 'r {
+        (start.0..=end.0)
+            .filter(|row| !self.layout.hidden_rows.contains(row))
+            .map(move |row| self.format_row(range, row, (start.1, end.1)))
+    }
+
+    /// Typed values of the visible rows between two corners (absolute, inclusive)
+    pub fn typed_rows<'r>(
+        &'r self,
+        range: &'r Range<Data>,
+        start: (u32, u32),
+        end: (u32, u32),
+    ) -> impl Iterator<Item = Vec<Value>> + 'r {
+        (start.0..=end.0)
+            .filter(|row| !self.layout.hidden_rows.contains(row))
+            .map(move |row| self.typed_row(range, row, (start.1, end.1)))
+    }
+
+    /// Letters of the visible columns between two columns (absolute, inclusive)
+    pub fn visible_column_names(&self, (first_column, last_column): (u32, u32)) -> Vec<String> {
+        (first_column..=last_column)
+            .filter(|&column| !self.is_column_hidden(column))
+            .map(column_name)
+            .collect()
+    }
+
+    /// Number of visible columns between two columns (absolute, inclusive)
+    pub fn visible_width(&self, (first_column, last_column): (u32, u32)) -> usize {
+        (first_column..=last_column).filter(|&column| !self.is_column_hidden(column)).count()
+    }
+
+    fn is_column_hidden(&self, column: u32) -> bool {
+        self.layout.hidden_columns.iter().any(|&(min, max)| (min..=max).contains(&column))
+    }
+
+    /// The top-left cell of the merged region covering a cell, other than that cell itself
+    fn merged_origin(&self, position: (u32, u32)) -> Option<(u32, u32)> {
+        let bands = &self.layout.merged_bands;
+        let band = bands.get(bands.partition_point(|band| band.last_row < position.0))
+            .filter(|band| band.first_row <= position.0)?;
+        let spans = &band.regions;
+        let &(first, _, origin) = spans.get(spans.partition_point(|&(_, last, _)| last < position.1))?;
+        (first <= position.1 && origin != position).then_some(origin)
+    }
+
+    /// Format code of the cell at an absolute 0-based position; `None` for General
+    fn format_code(&self, position: (u32, u32)) -> Option<&str> {
+        let runs = self.layout.styles.get(&position.0)?;
+        let &(first, _, style) = runs.get(runs.partition_point(|&(_, last, _)| last < position.1))?;
+        if first > position.1 {
+            return None;
+        }
+        self.cell_formats.get(style)?.as_deref()
+    }
+
+    /// A note on the hidden rows and columns left out between two corners, if any
+    pub fn hidden_note(&self, start: (u32, u32), end: (u32, u32)) -> Option<String> {
+        let rows = self.layout.hidden_rows.iter().filter(|row| (start.0..=end.0).contains(row)).count();
+        let columns = (end.1 - start.1 + 1) as usize - self.visible_width((start.1, end.1));
+        let counts: Vec<String> = [(rows, "row"), (columns, "column")]
+            .into_iter()
+            .filter(|&(count, _)| count > 0)
+            .map(|(count, noun)| format!("{} hidden {}{}", count, noun, if count == 1 { "" } else { "s" }))
+            .collect();
+        (!counts.is_empty()).then(|| format!("*{} not shown*", counts.join(" and ")))
+    }
+
+    /// An "Annotations" section for the visible cells of the given inclusive
+    /// row spans and column span, if any of them has a note, comment or link
+    pub fn annotations_note(&self, rows: &[(u32, u32)], (first_column, last_column): (u32, u32)) -> Option<String> {
+        annotations_section(self.layout.annotations.iter().filter(|&(&(row, column), _)| {
+            rows.iter().any(|&(first, last)| (first..=last).contains(&row))
+                && (first_column..=last_column).contains(&column)
+                && !self.layout.hidden_rows.contains(&row)
+                && !self.is_column_hidden(column)
+        }))
+    }
+}
+
+/// A number as JSON, with whole numbers written without a fraction
+fn number_value(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < 9_007_199_254_740_992.0 {
+        Value::from(value as i64)
+    } else {
+        serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+    }
+}
+
+/// Whether a date format code shows elapsed time, such as `[h]:mm`
+fn is_elapsed_time_format(code: &str) -> bool {
+    let code = code.to_lowercase();
+    ["[h", "[m", "[s"].iter().any(|token| code.contains(token))
+}
+
+/// Whether an OOXML boolean attribute is set
+fn is_true(value: &str) -> bool {
+    value == "1" || value == "true"
+}
+
+/// Visibility of a sheet as shown next to its name, `None` for visible sheets
+pub fn visibility_label(visible: SheetVisible) -> Option<&'static str> {
+    match visible {
+        SheetVisible::Visible => None,
+        SheetVisible::Hidden => Some("hidden"),
+        SheetVisible::VeryHidden => Some("very hidden"),
+    }
+}
+
+/// A sheet name for headings, flagged when the sheet is hidden
+pub fn sheet_title(name: &str, visible: SheetVisible) -> String {
+    match visibility_label(visible) {
+        Some(label) => format!("{} ({})", name, label),
+        None => name.to_string(),
+    }
+}
+
+/// Visibility of each sheet of a workbook, in sheet order
+pub fn sheet_visibility(workbook: &Sheets<BufReader<File>>) -> Vec<SheetVisible> {
+    workbook.sheets_metadata().iter().map(|sheet| sheet.visible).collect()
+}
+
+/// An Excel table (ListObject) of a workbook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExcelTable {
+    pub name: String,
+    pub sheet: String,
+    /// The whole table, header and totals rows included
+    pub range: CellRange,
+    pub columns: Vec<String>,
+}
+
+/// Read the tables of an XLSX workbook, in sheet order. Other formats have none.
+pub fn read_excel_tables(file_path: &str) -> Result<Vec<ExcelTable>> {
+    let file = File::open(file_path)
+        .with_context(|| format!("Failed to open Excel file: {}", file_path))?;
+    let Ok(mut archive) = ZipArchive::new(BufReader::new(file)) else {
+        return Ok(Vec::new());
+    };
+    if archive.by_name("xl/workbook.xml").is_err() {
+        return Ok(Vec::new());
+    }
+
+    let (_, sheet_parts) = read_workbook_sheets(&mut archive)?;
+    let mut tables = Vec::new();
+    for (sheet, part) in sheet_parts {
+        for (_, kind, target) in read_relationships(&mut archive, &part)? {
+            if !kind.ends_with("/table") {
+                continue;
+            }
+            let xml = read_zip_part(&mut archive, &target)?;
+            let mut table: Option<ExcelTable> = None;
+            for_each_element(&xml, |name, attributes| match name {
+                "table" => {
+                    let range = attributes.get("ref").and_then(|r| CellRange::parse(r).ok());
+                    let name = attributes.get("displayName").or_else(|| attributes.get("name"));
+                    if let (Some(name), Some(range)) = (name, range) {
+                        table = Some(ExcelTable {
+                            name: name.clone(),
+                            sheet: sheet.clone(),
+                            range: CellRange { sheet: Some(sheet.clone()), ..range },
+                            columns: Vec::new(),
+                        });
+                    }
+                }
+                "tableColumn" => {
+                    if let (Some(table), Some(column)) = (table.as_mut(), attributes.get("name")) {
+                        table.columns.push(column.clone());
+                    }
+                }
+                _ => {}
+            })?;
+            tables.extend(table);
+        }
+    }
+    Ok(tables)
+}
+
+/// List the tables and defined names of a spreadsheet with their sheet, range
+/// and column headers. Expects a resolved file path.
+pub fn get_spreadsheet_tables(resolved_file_path: &str) -> FileTextResult {
+    let file_path = resolved_file_path.to_string();
+    match validate_file_path(resolved_file_path) {
+        Ok(ext) if matches!(ext.as_str(), "xlsx" | "xlsm" | "xlsb" | "xls" | "xla" | "ods") => {}
+        Ok(ext) => return FileTextResult::error(
+            file_path,
+            format!("Tables and defined names are only available for spreadsheets, not {} files", ext),
+        ),
+        Err(e) => return FileTextResult::error(file_path, e),
+    }
+
+    match list_tables_and_names(resolved_file_path) {
+        Ok(content) => FileTextResult::success(file_path, content),
+        Err(e) => FileTextResult::error(file_path, format!("Failed to list tables: {}", e)),
+    }
+}
+
+fn list_tables_and_names(file_path: &str) -> Result<String> {
+    let mut workbook = open_excel_workbook(file_path)?;
+    let tables = read_excel_tables(file_path)?;
+    let formats = WorkbookFormats::open(file_path);
+    let header = || ["Name", "Sheet", "Range", "Columns"].map(str::to_string).to_vec();
+
+    let mut markdown = format!("# {}\n\n## Tables\n\n", Path::new(file_path).file_name().unwrap().to_string_lossy());
+    if tables.is_empty() {
+        markdown.push_str("*No tables*");
+    } else {
+        let rows = tables.iter().map(|table| vec![
+            table.name.clone(),
+            table.sheet.clone(),
+            CellRange { sheet: None, ..table.range.clone() }.to_string(),
+            table.columns.join(", "),
+        ]);
+        markdown.push_str(&text_table(std::iter::once(header()).chain(rows)));
+    }
+
+    // Names starting with `_xlnm._` are kept by Excel for its own use, such as autofilters
+    let names: Vec<(String, String)> = workbook.defined_names().iter()
+        .filter(|(name, _)| !name.starts_with("_xlnm._"))
+        .cloned()
+        .collect();
+    markdown.push_str("\n\n## Defined names\n\n");
+    if names.is_empty() {
+        markdown.push_str("*No defined names*");
+    } else {
+        let mut rows = vec![header()];
+        // Several names often refer to the same sheet, which is read once
+        let mut sheets: HashMap<String, Option<Range<Data>>> = HashMap::new();
+        for (name, formula) in names {
+            let reference = formula.trim_start_matches('=');
+            let areas: Vec<CellRange> = split_areas(reference).into_iter()
+                .map(CellRange::parse)
+                .collect::<Result<_>>()
+                .unwrap_or_default();
+            let sheet = areas.first().and_then(|area| area.sheet.clone()).unwrap_or_default();
+            let (range, columns) = match areas.as_slice() {
+                [] => (format!("={}", reference), String::new()),
+                // A single area has its column headers in its first row
+                [area] => {
+                    let range = sheets.entry(sheet.clone())
+                        .or_insert_with(|| workbook.worksheet_range(&sheet).ok());
+                    let columns = range.as_ref()
+                        .and_then(|range| {
+                            let (start, end) = area.clip_to(range)?;
+                            let cells = formats.sheet(&sheet).format_row(range, start.0, (start.1, end.1));
+                            Some(cells.into_iter().filter(|cell| !cell.is_empty()).collect::<Vec<_>>().join(", "))
+                        })
+                        .unwrap_or_default();
+                    (CellRange { sheet: None, ..area.clone() }.to_string(), columns)
+                }
+                areas => (areas.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "), String::new()),
+            };
+            rows.push(vec![name, sheet, range, columns]);
+        }
+        markdown.push_str(&text_table(rows));
+    }
+    markdown.push_str("\n\nPass a table or defined name as the range of read_office_document to read it.\n");
+    Ok(markdown)
+}
+
+/// Whether the workbook uses the 1904 date system, and the worksheet part of
+/// each sheet in workbook order
+fn read_workbook_sheets<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>) -> Result<(bool, Vec<(String, String)>)> {
+    let workbook = read_zip_part(archive, "xl/workbook.xml")?;
+    let targets: HashMap<String, String> = read_relationships(archive, "xl/workbook.xml")?
+        .into_iter()
+        .map(|(id, _, target)| (id, target))
+        .collect();
+
+    let mut date_1904 = false;
+    let mut sheet_parts = Vec::new();
+    for_each_element(&workbook, |name, attributes| match name {
+        "workbookPr" => {
+            date_1904 = attributes.get("date1904").is_some_and(|value| is_true(value));
+        }
+        "sheet" => {
+            if let (Some(sheet), Some(part)) = (attributes.get("name"), attributes.get("id").and_then(|id| targets.get(id))) {
+                sheet_parts.push((sheet.clone(), part.clone()));
+            }
+        }
+        _ => {}
+    })?;
+    Ok((date_1904, sheet_parts))
+}
+
+/// Relationships of a package part as `(id, type, target part)`; parts without
+/// relationships have none. External targets, such as URLs, are kept as written.
+pub(crate) fn read_relationships<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>, part: &str) -> Result<Vec<(String, String, String)>> {
+    let (folder, file_name) = part.rsplit_once('/').unwrap_or(("", part));
+    let Ok(xml) = read_zip_part(archive, &format!("{}/_rels/{}.rels", folder, file_name)) else {
+        return Ok(Vec::new());
+    };
+
+    let mut relationships = Vec::new();
+    for_each_element(&xml, |name, attributes| {
+        if name == "Relationship"
+            && let (Some(id), Some(target)) = (attributes.get("Id"), attributes.get("Target"))
+        {
+            let kind = attributes.get("Type").cloned().unwrap_or_default();
+            let target = match attributes.get("TargetMode") {
+                Some(mode) if mode == "External" => target.clone(),
+                _ => resolve_part_path(folder, target),
+            };
+            relationships.push((id.clone(), kind, target));
+        }
+    })?;
+    Ok(relationships)
+}
+
+/// Resolve a relationship target against the folder of its source part
+fn resolve_part_path(folder: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut segments: Vec<&str> = folder.split('/').filter(|segment| !segment.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                segments.pop();
+            }
+            "." | "" => {}
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+pub(crate) fn read_zip_part<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<String> {
+    let mut part = archive.by_name(name)
+        .with_context(|| format!("Missing workbook part: {}", name))?;
+    let mut xml = String::new();
+    part.read_to_string(&mut xml)
+        .with_context(|| format!("Failed to read workbook part: {}", name))?;
+    Ok(xml)
+}
+
+/// Attributes of an element by local name, unescaped
+pub(crate) fn element_attributes(element: &quick_xml::events::BytesStart) -> HashMap<String, String> {
+    element.attributes().flatten()
+        .map(|attribute| {
+            let name = String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+            let value = attribute.unescape_value()
+                .map(|value| value.into_owned())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&attribute.value).into_owned());
+            (name, value)
+        })
+        .collect()
+}
+
+/// Call `visit` with the local name and attributes of every element of a part
+fn for_each_element(xml: &str, mut visit: impl FnMut(&str, &HashMap<String, String>)) -> Result<()> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                visit(&name, &element_attributes(&e));
+            }
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+/// Read a sheet's values and a formatter for them, with the sheet's formulas
+/// unless only values are shown
+fn read_sheet<'f>(
+    workbook: &mut Sheets<BufReader<File>>,
+    formats: &'f WorkbookFormats,
+    sheet_name: &str,
+    formula_mode: FormulaMode,
+    output_format: OutputFormat,
+) -> Result<(Range<Data>, CellFormatter<'f>)> {
+    let range = workbook.worksheet_range(sheet_name)
+        .map_err(|e| anyhow::anyhow!("Failed to read sheet '{}': {}", sheet_name, e))?;
+    let formatter = formats.sheet(sheet_name).with_output_format(output_format);
+    if formula_mode == FormulaMode::Values {
+        return Ok((range, formatter));
+    }
+
+    let formulas = workbook.worksheet_formula(sheet_name)
+        .map_err(|e| anyhow::anyhow!("Failed to read formulas of sheet '{}': {}", sheet_name, e))?;
+    Ok((range, formatter.with_formulas(formulas, formula_mode)))
+}
+
+/// Render the cells between two corners as a table in the formatter's output
+/// format, or list their formulas
+pub(crate) fn render_cells(range: &Range<Data>, formatter: &CellFormatter, start: (u32, u32), end: (u32, u32)) -> String {
+    if formatter.formula_mode == FormulaMode::FormulasOnly {
+        formatter.formula_listing(range, start, end)
+    } else if formatter.output_format != OutputFormat::Markdown {
+        let rows: Vec<Vec<Value>> = formatter.typed_rows(range, start, end).collect();
+        with_hidden_note(render_structured(formatter.output_format, &formatter.visible_column_names((start.1, end.1)), &rows), formatter, start, end)
+    } else {
+        cells_to_markdown_table(range, formatter, start, end)
+    }
+}
+
+/// Append the note on hidden rows and columns between two corners and the
+/// annotations of the cells shown, if any
+fn with_hidden_note(mut output: String, formatter: &CellFormatter, start: (u32, u32), end: (u32, u32)) -> String {
+    if let Some(note) = formatter.hidden_note(start, end) {
+        output.push_str(&format!("\n\n{}", note));
+    }
+    if let Some(annotations) = formatter.annotations_note(&[(start.0, end.0)], (start.1, end.1)) {
+        output.push_str(&format!("\n\n{}", annotations));
+    }
+    output
+}
+
+/// Read whole sheets (1-based) as tables in the given output format, showing
+/// formulas as selected
+pub fn read_excel_sheets(
+    file_path: &str,
+    sheet_numbers: &[usize],
+    formula_mode: FormulaMode,
+    output_format: OutputFormat,
+) -> Result<(String, Vec<usize>)> {
+    let mut workbook = open_excel_workbook(file_path)?;
+    let sheet_names = workbook.sheet_names().to_owned();
+    let visibility = sheet_visibility(&workbook);
+    let formats = WorkbookFormats::open(file_path);
+
+    let mut markdown = format!("# {}\n\n", Path::new(file_path).file_name().unwrap().to_string_lossy());
+    let mut returned_sheets = Vec::new();
+
+    for &sheet_number in sheet_numbers {
+        let Some(sheet_name) = sheet_number.checked_sub(1).and_then(|index| sheet_names.get(index)) else {
+            continue;
+        };
+        let title = sheet_title(sheet_name, visibility[sheet_number - 1]);
+        markdown.push_str(&format!("## Sheet {}: {}\n\n", sheet_number, title));
+        returned_sheets.push(sheet_number);
+
+        let (range, formatter) = read_sheet(&mut workbook, &formats, sheet_name, formula_mode, output_format)?;
+        match used_area(&range) {
+            Some(area) => markdown.push_str(&render_cells(&range, &formatter, area.start, area.end)),
+            None => markdown.push_str("Empty sheet"),
+        }
+        markdown.push_str("\n\n");
+    }
+
+    Ok((markdown, returned_sheets))
+}
+
+/// Read a range of cells from a workbook as tables. Unqualified ranges
+/// such as `B3:H200` are read from each of the requested sheets (1-based).
+pub fn read_excel_range(
+    file_path: &str,
+    range_spec: &str,
+    sheet_numbers: &[usize],
+    formula_mode: FormulaMode,
+    output_format: OutputFormat,
+) -> Result<(String, Vec<usize>)> {
+    let mut workbook: Sheets<BufReader<File>> = open_excel_workbook(file_path)?;
+    let sheet_names = workbook.sheet_names().to_owned();
+    let tables = read_excel_tables(file_path).unwrap_or_else(|e| {
+        log::warn!("Failed to read the tables of {}: {}", file_path, e);
+        Vec::new()
+    });
+    let ranges = resolve_range_parameter(&workbook, &tables, range_spec)?;
+    let formats = WorkbookFormats::open(file_path);
+
+    let mut markdown = format!("# {}\n\n", Path::new(file_path).file_name().unwrap().to_string_lossy());
+    let mut returned_sheets = Vec::new();
+
+    for cell_range in ranges {
+        let targets: Vec<usize> = match &cell_range.sheet {
+            Some(sheet) => {
+                let index = sheet_names.iter()
+                    .position(|name| name.eq_ignore_ascii_case(sheet))
+                    .ok_or_else(|| anyhow::anyhow!(
+                        "Sheet '{}' not found. Available sheets: {}",
+                        sheet,
+                        sheet_names.join(", ")
+                    ))?;
+                vec![index + 1]
+            }
+            None => sheet_numbers.to_vec(),
+        };
+
+        for sheet_number in targets {
+            let Some(sheet_name) = sheet_number.checked_sub(1).and_then(|index| sheet_names.get(index)) else {
+                continue;
+            };
+            let qualified = CellRange { sheet: Some(sheet_name.clone()), ..cell_range.clone() };
+            markdown.push_str(&format!("## Sheet {}: {}\n\n", sheet_number, qualified));
+
+            let (range, formatter) = read_sheet(&mut workbook, &formats, sheet_name, formula_mode, output_format)?;
+            match qualified.clip_to(&range) {
+                Some((start, end)) => markdown.push_str(&render_cells(&range, &formatter, start, end)),
+                None => markdown.push_str("*No data in this range*"),
+            }
+            markdown.push_str("\n\n");
+
+            if !returned_sheets.contains(&sheet_number) {
+                returned_sheets.push(sheet_number);
+            }
+        }
+    }
+
+    Ok((markdown, returned_sheets))
+}
+
+/// Read a window of rows from each requested sheet (1-based) as tables.
+/// The first used row of a sheet is taken as its header and repeated in every window.
+pub fn read_excel_rows(
+    file_path: &str,
+    rows_spec: &str,
+    sheet_numbers: &[usize],
+    formula_mode: FormulaMode,
+    output_format: OutputFormat,
+) -> Result<(String, Vec<usize>)> {
+    let window = RowWindow::parse(rows_spec)?;
+    let mut workbook = open_excel_workbook(file_path)?;
+    let sheet_names = workbook.sheet_names().to_owned();
+    let visibility = sheet_visibility(&workbook);
+    let formats = WorkbookFormats::open(file_path);
+
+    let mut markdown = format!("# {}\n\n", Path::new(file_path).file_name().unwrap().to_string_lossy());
+    let mut returned_sheets = Vec::new();
+
+    for &sheet_number in sheet_numbers {
+        let Some(sheet_name) = sheet_number.checked_sub(1).and_then(|index| sheet_names.get(index)) else {
+            continue;
+        };
+        let (range, formatter) = read_sheet(&mut workbook, &formats, sheet_name, formula_mode, output_format)?;
+        let sheet_name = sheet_title(sheet_name, visibility[sheet_number - 1]);
+        returned_sheets.push(sheet_number);
+
+        let title = format!("Sheet {}: {}", sheet_number, sheet_name);
+        push_row_window(&mut markdown, &title, &range, &formatter, window, rows_spec);
+    }
+
+    Ok((markdown, returned_sheets))
+}
+
+/// Write a window of rows of a sheet under a `## {title}` heading, with the
+/// first used row of the sheet repeated as the header
+pub(crate) fn push_row_window(
+    markdown: &mut String,
+    title: &str,
+    range: &Range<Data>,
+    formatter: &CellFormatter,
+    window: RowWindow,
+    rows_spec: &str,
+) {
+    let Some(area) = used_area(range) else {
+        markdown.push_str(&format!("## {}\n\nEmpty sheet\n\n", title));
+        return;
+    };
+    let (header_row, last_row) = (area.start.0, area.end.0);
+    let first = window.start.max(header_row + 1);
+    let last = window.end.unwrap_or(last_row).min(last_row);
+
+    if first > last {
+        markdown.push_str(&format!(
+            "## {} (rows {}, {} rows in sheet)\n\n*No rows in this window*\n\n",
+            title, rows_spec.trim(), last_row + 1
+        ));
+        return;
+    }
+
+    markdown.push_str(&format!("## {} (rows {}-{} of {})\n\n", title, first + 1, last + 1, last_row + 1));
+    if formatter.formula_mode == FormulaMode::FormulasOnly {
+        markdown.push_str(&formatter.formula_listing(range, (first, area.start.1), (last, area.end.1)));
+        markdown.push_str("\n\n");
+        return;
+    }
+    let columns = (area.start.1, area.end.1);
+    let table = if formatter.output_format == OutputFormat::Markdown {
+        let header = formatter.format_row(range, header_row, columns);
+        let window_rows = formatter.format_rows(range, (first, area.start.1), (last, area.end.1));
+        text_table(std::iter::once(header).chain(window_rows))
+    } else {
+        let header = formatter.typed_row(range, header_row, columns);
+        let rows: Vec<Vec<Value>> = std::iter::once(header)
+            .chain(formatter.typed_rows(range, (first, area.start.1), (last, area.end.1)))
+            .collect();
+        render_structured(formatter.output_format, &formatter.visible_column_names(columns), &rows)
+    };
+    markdown.push_str(&table);
+    // The header row is shown with every window, so its annotations are too
+    let notes = [
+        formatter.hidden_note((first, area.start.1), (last, area.end.1)),
+        formatter.annotations_note(&[(header_row, header_row), (first, last)], columns),
+    ];
+    for note in notes.into_iter().flatten() {
+        markdown.push_str(&format!("\n\n{}", note));
+    }
+    markdown.push_str("\n\n");
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{Cursor, Write};
+
+    /// Escape text for inclusion in test XML
+    fn xml_escape(text: &str) -> String {
+        text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    }
+
+    /// A worksheet of an `XlsxBuilder` workbook
+    #[derive(Default)]
+    pub(crate) struct TestSheet {
+        name: String,
+        rows: Vec<Vec<String>>,
+        /// Cell reference and index into the workbook's cell formats
+        styles: HashMap<String, usize>,
+        /// Cell reference and formula; the cell's grid value is its cached value
+        formulas: HashMap<String, String>,
+        /// `state` of the sheet in the workbook, such as `hidden`
+        state: Option<String>,
+        merged: Vec<String>,
+        /// 1-based rows and inclusive column spans that are hidden
+        hidden_rows: HashSet<usize>,
+        hidden_columns: Vec<(u32, u32)>,
+        /// Table name and reference; the first row of the reference holds the columns
+        tables: Vec<(String, String)>,
+        /// Cell reference, author and text of legacy notes and of threaded comments
+        notes: Vec<(String, String, String)>,
+        comments: Vec<(String, String, String)>,
+        /// Cell reference and URL, or `#` and a location within the workbook
+        links: Vec<(String, String)>,
+    }
+
+    /// Builds minimal XLSX workbooks for tests. Cell values that parse as
+    /// numbers are stored as numbers, the rest as inline strings, and empty
+    /// values are omitted.
+    #[derive(Default)]
+    pub(crate) struct XlsxBuilder {
+        sheets: Vec<TestSheet>,
+        defined_names: Vec<(String, String)>,
+        /// `numFmtId` of each cell format after the default one
+        cell_formats: Vec<u32>,
+        custom_formats: Vec<String>,
+        date_1904: bool,
+    }
+
+    impl XlsxBuilder {
+        pub(crate) fn new() -> Self {
+            Self::default()
+        }
+
+        pub(crate) fn sheet(mut self, name: &str, rows: Vec<Vec<&str>>) -> Self {
+            self.sheets.push(TestSheet {
+                name: name.to_string(),
+                rows: rows.into_iter().map(|row| row.into_iter().map(str::to_string).collect()).collect(),
+                ..TestSheet::default()
+            });
+            self
+        }
+
+        pub(crate) fn defined_name(mut self, name: &str, reference: &str) -> Self {
+            self.defined_names.push((name.to_string(), reference.to_string()));
+            self
+        }
+
+        pub(crate) fn date_1904(mut self) -> Self {
+            self.date_1904 = true;
+            self
+        }
+
+        /// Apply a custom number format to a cell of the last sheet
+        pub(crate) fn number_format(mut self, cell: &str, code: &str) -> Self {
+            let id = match self.custom_formats.iter().position(|existing| existing == code) {
+                Some(index) => 164 + index as u32,
+                None => {
+                    self.custom_formats.push(code.to_string());
+                    163 + self.custom_formats.len() as u32
+                }
+            };
+            self.builtin_format(cell, id)
+        }
+
+        /// Apply a number format by id (built-in ids are below 164) to a cell of the last sheet
+        pub(crate) fn builtin_format(mut self, cell: &str, id: u32) -> Self {
+            let index = match self.cell_formats.iter().position(|&existing| existing == id) {
+                Some(index) => index + 1,
+                None => {
+                    self.cell_formats.push(id);
+                    self.cell_formats.len()
+                }
+            };
+            self.sheets.last_mut().expect("add a sheet first").styles.insert(cell.to_string(), index);
+            self
+        }
+
+        /// Give a cell of the last sheet a formula
+        pub(crate) fn formula(mut self, cell: &str, formula: &str) -> Self {
+            self.sheets.last_mut().expect("add a sheet first").formulas.insert(cell.to_string(), formula.to_string());
+            self
+        }
+
+        /// Set the visibility of the last sheet (`hidden` or `veryHidden`)
+        pub(crate) fn sheet_state(mut self, state: &str) -> Self {
+            self.sheets.last_mut().expect("add a sheet first").state = Some(state.to_string());
+            self
+        }
+
+        /// Merge a range of cells of the last sheet, such as `A1:C1`
+        pub(crate) fn merge(mut self, reference: &str) -> Self {
+            self.sheets.last_mut().expect("add a sheet first").merged.push(reference.to_string());
+            self
+        }
+
+        /// Hide a row (1-based) of the last sheet
+        pub(crate) fn hide_row(mut self, row: usize) -> Self {
+            self.sheets.last_mut().expect("add a sheet first").hidden_rows.insert(row);
+            self
+        }
+
+        /// Hide a span of columns (1-based, inclusive) of the last sheet
+        pub(crate) fn hide_columns(mut self, min: u32, max: u32) -> Self {
+            self.sheets.last_mut().expect("add a sheet first").hidden_columns.push((min, max));
+            self
+        }
+
+        /// Add a table to the last sheet, with its header row in the first row of `reference`
+        pub(crate) fn table(mut self, name: &str, reference: &str) -> Self {
+            self.sheets.last_mut().expect("add a sheet first").tables.push((name.to_string(), reference.to_string()));
+            self
+        }
+
+        /// Add a legacy note to a cell of the last sheet
+        pub(crate) fn note(mut self, cell: &str, author: &str, text: &str) -> Self {
+            self.sheets.last_mut().expect("add a sheet first").notes.push((cell.to_string(), author.to_string(), text.to_string()));
+            self
+        }
+
+        /// Add a threaded comment to a cell of the last sheet; later comments on
+        /// the same cell are replies to the first
+        pub(crate) fn comment(mut self, cell: &str, author: &str, text: &str) -> Self {
+            self.sheets.last_mut().expect("add a sheet first").comments.push((cell.to_string(), author.to_string(), text.to_string()));
+            self
+        }
+
+        /// Link a cell of the last sheet to a URL, or to a location such as `#Sheet2!A1`
+        pub(crate) fn link(mut self, cell: &str, target: &str) -> Self {
+            self.sheets.last_mut().expect("add a sheet first").links.push((cell.to_string(), target.to_string()));
+            self
+        }
+
+        pub(crate) fn build(&self) -> Vec<u8> {
+            let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            let options = zip::write::SimpleFileOptions::default();
+
+            let mut content_types = String::from(
+                r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>"#,
+            );
+            let mut workbook = format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><workbookPr{}/><sheets>"#,
+                if self.date_1904 { r#" date1904="1""# } else { "" }
+            );
+            let mut relationships = String::from(
+                r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rIdStyles" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#,
+            );
+
+            let mut table_count = 0;
+            // Authors of threaded comments; a person's id is their name in braces
+            let mut persons: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
+            for (index, sheet) in self.sheets.iter().enumerate() {
+                let number = index + 1;
+                content_types.push_str(&format!(
+                    r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+                    number
+                ));
+                let state = sheet.state.as_ref().map(|state| format!(r#" state="{}""#, state)).unwrap_or_default();
+                workbook.push_str(&format!(
+                    r#"<sheet name="{}" sheetId="{}"{} r:id="rId{}"/>"#,
+                    xml_escape(&sheet.name), number, state, number
+                ));
+                relationships.push_str(&format!(
+                    r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#,
+                    number, number
+                ));
+
+                let mut xml = String::from(
+                    r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">"#,
+                );
+                if !sheet.hidden_columns.is_empty() {
+                    xml.push_str("<cols>");
+                    for (min, max) in &sheet.hidden_columns {
+                        xml.push_str(&format!(r#"<col min="{}" max="{}" hidden="1"/>"#, min, max));
+                    }
+                    xml.push_str("</cols>");
+                }
+                xml.push_str("<sheetData>");
+                for (row_index, row) in sheet.rows.iter().enumerate() {
+                    let hidden = if sheet.hidden_rows.contains(&(row_index + 1)) { r#" hidden="1""# } else { "" };
+                    xml.push_str(&format!(r#"<row r="{}"{}>"#, row_index + 1, hidden));
+                    for (column_index, value) in row.iter().enumerate() {
+                        let reference = format!("{}{}", column_name(column_index as u32), row_index + 1);
+                        let style = sheet.styles.get(&reference)
+                            .map(|index| format!(r#" s="{}""#, index))
+                            .unwrap_or_default();
+                        if let Some(formula) = sheet.formulas.get(&reference) {
+                            let (kind, cached) = match value.parse::<f64>() {
+                                Ok(_) => ("", format!("<v>{}</v>", value)),
+                                Err(_) if value.is_empty() => ("", String::new()),
+                                Err(_) => (r#" t="str""#, format!("<v>{}</v>", xml_escape(value))),
+                            };
+                            xml.push_str(&format!(
+                                r#"<c r="{}"{}{}><f>{}</f>{}</c>"#,
+                                reference, style, kind, xml_escape(formula), cached
+                            ));
+                        } else if value.is_empty() {
+                            continue;
+                        } else if value.parse::<f64>().is_ok() {
+                            xml.push_str(&format!(r#"<c r="{}"{}><v>{}</v></c>"#, reference, style, value));
+                        } else {
+                            xml.push_str(&format!(r#"<c r="{}"{} t="inlineStr"><is><t>{}</t></is></c>"#, reference, style, xml_escape(value)));
+                        }
+                    }
+                    xml.push_str("</row>");
+                }
+                xml.push_str("</sheetData>");
+                if !sheet.merged.is_empty() {
+                    xml.push_str(&format!(r#"<mergeCells count="{}">"#, sheet.merged.len()));
+                    for reference in &sheet.merged {
+                        xml.push_str(&format!(r#"<mergeCell ref="{}"/>"#, reference));
+                    }
+                    xml.push_str("</mergeCells>");
+                }
+                let mut sheet_relationships = String::new();
+                if !sheet.links.is_empty() {
+                    xml.push_str("<hyperlinks>");
+                    for (index, (cell, target)) in sheet.links.iter().enumerate() {
+                        match target.strip_prefix('#') {
+                            Some(location) => xml.push_str(&format!(r#"<hyperlink ref="{}" location="{}"/>"#, cell, xml_escape(location))),
+                            None => {
+                                xml.push_str(&format!(r#"<hyperlink ref="{}" r:id="rIdLink{}"/>"#, cell, index + 1));
+                                sheet_relationships.push_str(&format!(
+                                    r#"<Relationship Id="rIdLink{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="{}" TargetMode="External"/>"#,
+                                    index + 1, xml_escape(target)
+                                ));
+                            }
+                        }
+                    }
+                    xml.push_str("</hyperlinks>");
+                }
+                if !sheet.notes.is_empty() {
+                    let mut authors: Vec<&str> = Vec::new();
+                    let mut list = String::new();
+                    for (cell, author, text) in &sheet.notes {
+                        let id = authors.iter().position(|existing| existing == author).unwrap_or_else(|| {
+                            authors.push(author);
+                            authors.len() - 1
+                        });
+                        list.push_str(&format!(
+                            r#"<comment ref="{}" authorId="{}"><text><r><rPr><b/></rPr><t>{}:</t></r><r><t xml:space="preserve">&#10;{}</t></r></text></comment>"#,
+                            cell, id, xml_escape(author), xml_escape(text)
+                        ));
+                    }
+                    let authors: String = authors.iter().map(|author| format!("<author>{}</author>", xml_escape(author))).collect();
+                    writer.start_file(format!("xl/comments{}.xml", number), options).unwrap();
+                    writer.write_all(format!(
+                        r#"<?xml version="1.0" encoding="UTF-8"?><comments xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><authors>{}</authors><commentList>{}</commentList></comments>"#,
+                        authors, list
+                    ).as_bytes()).unwrap();
+                    sheet_relationships.push_str(&format!(
+                        r#"<Relationship Id="rIdNotes" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments" Target="../comments{}.xml"/>"#,
+                        number
+                    ));
+                }
+                if !sheet.comments.is_empty() {
+                    let mut threads: HashMap<&str, usize> = HashMap::new();
+                    let mut list = String::new();
+                    for (index, (cell, author, text)) in sheet.comments.iter().enumerate() {
+                        let parent = threads.get(cell.as_str())
+                            .map(|thread| format!(r#" parentId="{{C{}}}""#, thread))
+                            .unwrap_or_default();
+                        threads.entry(cell).or_insert(index);
+                        list.push_str(&format!(
+                            r#"<threadedComment ref="{}" dT="2024-03-0{}T09:30:00.00" personId="{{{}}}" id="{{C{}}}"{}><text>{}</text></threadedComment>"#,
+                            cell, index + 1, xml_escape(author), index, parent, xml_escape(text)
+                        ));
+                        persons.insert(author.clone());
+                    }
+                    writer.start_file(format!("xl/threadedComments/threadedComment{}.xml", number), options).unwrap();
+                    writer.write_all(format!(
+                        r#"<?xml version="1.0" encoding="UTF-8"?><ThreadedComments xmlns="http://schemas.microsoft.com/office/spreadsheetml/2018/threadedcomments">{}</ThreadedComments>"#,
+                        list
+                    ).as_bytes()).unwrap();
+                    sheet_relationships.push_str(&format!(
+                        r#"<Relationship Id="rIdThreads" Type="http://schemas.microsoft.com/office/2017/10/relationships/threadedComment" Target="../threadedComments/threadedComment{}.xml"/>"#,
+                        number
+                    ));
+                }
+                if !sheet.tables.is_empty() {
+                    xml.push_str(&format!(r#"<tableParts count="{}">"#, sheet.tables.len()));
+                    for (name, reference) in &sheet.tables {
+                        table_count += 1;
+                        xml.push_str(&format!(r#"<tablePart r:id="rIdTable{}"/>"#, table_count));
+                        sheet_relationships.push_str(&format!(
+                            r#"<Relationship Id="rIdTable{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/table" Target="../tables/table{}.xml"/>"#,
+                            table_count, table_count
+                        ));
+                        content_types.push_str(&format!(
+                            r#"<Override PartName="/xl/tables/table{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.table+xml"/>"#,
+                            table_count
+                        ));
+
+                        let area = CellRange::parse(reference).unwrap();
+                        let header = sheet.rows.get(area.start.0 as usize);
+                        let columns: Vec<String> = (area.start.1..=area.end.1)
+                            .map(|column| header.and_then(|row| row.get(column as usize)).cloned().unwrap_or_default())
+                            .collect();
+                        let mut table = format!(
+                            r#"<?xml version="1.0" encoding="UTF-8"?><table xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" id="{}" name="{}" displayName="{}" ref="{}"><tableColumns count="{}">"#,
+                            table_count, xml_escape(name), xml_escape(name), reference, columns.len()
+                        );
+                        for (index, column) in columns.iter().enumerate() {
+                            table.push_str(&format!(r#"<tableColumn id="{}" name="{}"/>"#, index + 1, xml_escape(column)));
+                        }
+                        table.push_str("</tableColumns></table>");
+                        writer.start_file(format!("xl/tables/table{}.xml", table_count), options).unwrap();
+                        writer.write_all(table.as_bytes()).unwrap();
+                    }
+                    xml.push_str("</tableParts>");
+                }
+                xml.push_str("</worksheet>");
+
+                writer.start_file(format!("xl/worksheets/sheet{}.xml", number), options).unwrap();
+                writer.write_all(xml.as_bytes()).unwrap();
+                if !sheet_relationships.is_empty() {
+                    writer.start_file(format!("xl/worksheets/_rels/sheet{}.xml.rels", number), options).unwrap();
+                    writer.write_all(format!(
+                        r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+                        sheet_relationships
+                    ).as_bytes()).unwrap();
+                }
+            }
+
+            workbook.push_str("</sheets>");
+            if !self.defined_names.is_empty() {
+                workbook.push_str("<definedNames>");
+                for (name, reference) in &self.defined_names {
+                    workbook.push_str(&format!(r#"<definedName name="{}">{}</definedName>"#, xml_escape(name), xml_escape(reference)));
+                }
+                workbook.push_str("</definedNames>");
+            }
+            workbook.push_str("</workbook>");
+            if !persons.is_empty() {
+                let list: String = persons.iter()
+                    .map(|person| format!(r#"<person displayName="{}" id="{{{}}}"/>"#, xml_escape(person), xml_escape(person)))
+                    .collect();
+                writer.start_file("xl/persons/person.xml", options).unwrap();
+                writer.write_all(format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?><personList xmlns="http://schemas.microsoft.com/office/spreadsheetml/2018/threadedcomments">{}</personList>"#,
+                    list
+                ).as_bytes()).unwrap();
+                relationships.push_str(r#"<Relationship Id="rIdPersons" Type="http://schemas.microsoft.com/office/2017/10/relationships/person" Target="persons/person.xml"/>"#);
+            }
+            content_types.push_str("</Types>");
+            relationships.push_str("</Relationships>");
+
+            let mut styles = String::from(
+                r#"<?xml version="1.0" encoding="UTF-8"?><styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+            );
+            if !self.custom_formats.is_empty() {
+                styles.push_str(&format!(r#"<numFmts count="{}">"#, self.custom_formats.len()));
+                for (index, code) in self.custom_formats.iter().enumerate() {
+                    styles.push_str(&format!(r#"<numFmt numFmtId="{}" formatCode="{}"/>"#, 164 + index, xml_escape(code)));
+                }
+                styles.push_str("</numFmts>");
+            }
+            styles.push_str(&format!(r#"<cellXfs count="{}"><xf numFmtId="0"/>"#, self.cell_formats.len() + 1));
+            for id in &self.cell_formats {
+                styles.push_str(&format!(r#"<xf numFmtId="{}" applyNumberFormat="1"/>"#, id));
+            }
+            styles.push_str("</cellXfs></styleSheet>");
+
+            for (name, contents) in [
+                ("[Content_Types].xml", content_types),
+                ("_rels/.rels", r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string()),
+                ("xl/workbook.xml", workbook),
+                ("xl/_rels/workbook.xml.rels", relationships),
+                ("xl/styles.xml", styles),
+            ] {
+                writer.start_file(name, options).unwrap();
+                writer.write_all(contents.as_bytes()).unwrap();
+            }
+            writer.finish().unwrap().into_inner()
+        }
+
+        /// Write the workbook to a temporary `.xlsx` file
+        pub(crate) fn file(&self) -> tempfile::NamedTempFile {
+            let mut file = tempfile::NamedTempFile::with_suffix(".xlsx").unwrap();
+            file.write_all(&self.build()).unwrap();
+            file
+        }
+    }
+
+    /// Write a workbook of plain values to a temporary `.xlsx` file
+    pub(crate) fn xlsx_file(sheets: &[(&str, Vec<Vec<&str>>)], defined_names: &[(&str, &str)]) -> tempfile::NamedTempFile {
+        let builder = sheets.iter().fold(XlsxBuilder::new(), |builder, (name, rows)| builder.sheet(name, rows.clone()));
+        defined_names.iter()
+            .fold(builder, |builder, (name, reference)| builder.defined_name(name, reference))
+            .file()
+    }
+
+    #[test]
+    fn test_structured_output_formats() {
+        let file = XlsxBuilder::new()
+            .sheet("Orders", vec![
+                vec!["Item", "Qty", "Price", "Date", ""],
+                vec!["Pen, blue", "3", "1234.5", "45122", "1.5"],
+                vec!["Ink", "", "0.25", "45122.75", "0.5"],
+            ])
+            .number_format("C2", "#,##0.00")
+            .number_format("D2", "yyyy-mm-dd")
+            .number_format("E2", "[h]:mm")
+            .number_format("D3", "yyyy-mm-dd hh:mm")
+            .number_format("E3", "h:mm")
+            .file();
+        let path = file.path().to_str().unwrap();
+
+        let (output, _) = read_excel_sheets(path, &[1], FormulaMode::Values, OutputFormat::JsonRecords).unwrap();
+        assert!(output.contains(concat!(
+            "## Sheet 1: Orders\n\n```json\n[\n",
+            "  {\"Item\": \"Pen, blue\", \"Qty\": 3, \"Price\": 1234.5, \"Date\": \"2023-07-15\", \"E\": \"PT36H0M0S\"},\n",
+            "  {\"Item\": \"Ink\", \"Qty\": null, \"Price\": 0.25, \"Date\": \"2023-07-15T18:00:00\", \"E\": \"12:00:00\"}\n",
+            "]\n```",
+        )), "{}", output);
+
+        let (output, _) = read_excel_range(path, "A1:C3", &[1], FormulaMode::Values, OutputFormat::Csv).unwrap();
+        assert!(output.contains("```csv\nItem,Qty,Price\n\"Pen, blue\",3,1234.5\nInk,,0.25\n```"), "{}", output);
+
+        let (output, _) = read_excel_rows(path, "3-", &[1], FormulaMode::Values, OutputFormat::JsonColumns).unwrap();
+        assert!(output.contains("## Sheet 1: Orders (rows 3-3 of 3)\n\n```json\n{\n  \"Item\": [\"Ink\"],\n  \"Qty\": [null],"), "{}", output);
+    }
+
+    #[test]
+    fn test_formula_modes() {
+        let file = XlsxBuilder::new()
+            .sheet("Totals", vec![
+                vec!["Item", "Amount", "Label"],
+                vec!["Apples", "3", ""],
+                vec!["Pears", "4", ""],
+                vec!["Total", "7", "done"],
+            ])
+            .formula("B4", "SUM(B2:B3)")
+            .formula("C4", "IF(B4>5,\"done\",\"open\")")
+            .file();
+        let path = file.path().to_str().unwrap();
+
+        let (markdown, _) = read_excel_sheets(path, &[1], FormulaMode::Formulas, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("| Total | =SUM(B2:B3) | =IF(B4>5,\"done\",\"open\") |"), "{}", markdown);
+        assert!(markdown.contains("| Apples | 3 |  |"));
+
+        let (markdown, _) = read_excel_range(path, "B3:B4", &[1], FormulaMode::Both, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("| 4 |\n"));
+        assert!(markdown.contains("| 7 (=SUM(B2:B3)) |\n"), "{}", markdown);
+
+        let (markdown, _) = read_excel_sheets(path, &[1], FormulaMode::FormulasOnly, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("| Cell | Formula | Value |\n| --- | --- | --- |\n| B4 | =SUM(B2:B3) | 7 |\n| C4 |"), "{}", markdown);
+        assert!(!markdown.contains("Apples"));
+
+        let (markdown, _) = read_excel_rows(path, "1-3", &[1], FormulaMode::FormulasOnly, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("*No formulas*"), "{}", markdown);
+
+        let (markdown, _) = read_excel_sheets(path, &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(!markdown.contains('='));
+
+        assert_eq!("formulas_only".parse::<FormulaMode>(), Ok(FormulaMode::FormulasOnly));
+        assert!("formula".parse::<FormulaMode>().is_err());
+    }
+
+    #[test]
+    fn test_merged_cells_and_hidden_rows_and_columns() {
+        let file = XlsxBuilder::new()
+            .sheet("Sales", vec![
+                vec!["Region", "2024", "", "Notes"],
+                vec!["", "H1", "H2", ""],
+                vec!["North", "5", "6", "scratch"],
+                vec!["South", "7", "8", "scratch"],
+                vec!["Check", "12", "14", "scratch"],
+            ])
+            .merge("B1:C1")
+            .merge("A1:A2")
+            .hide_row(5)
+            .hide_columns(4, 4)
+            .file();
+        let path = file.path().to_str().unwrap();
+
+        let (markdown, _) = read_excel_sheets(path, &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains(
+            "| Region | 2024 | 2024 |\n| --- | --- | --- |\n| Region | H1 | H2 |\n| North | 5 | 6 |\n| South | 7 | 8 |\n"
+        ), "{}", markdown);
+        assert!(!markdown.contains("Check") && !markdown.contains("scratch"));
+        assert!(markdown.contains("*1 hidden row and 1 hidden column not shown*"));
+
+        let (markdown, _) = read_excel_rows(path, "4-", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("(rows 4-5 of 5)\n\n| Region | 2024 | 2024 |\n| --- | --- | --- |\n| South | 7 | 8 |\n\n*1 hidden row"), "{}", markdown);
+
+        let (markdown, _) = read_excel_range(path, "C1:C3", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("| 2024 |\n| --- |\n| H2 |\n| 6 |\n"), "{}", markdown);
+
+        // A merged whole column is kept as one region, not one entry per row
+        let bands = MergedBand::from_regions(vec![((0, 0), (1_048_575, 0)), ((1, 1), (2, 2))]);
+        assert_eq!(bands.len(), 3);
+        let layout = SheetLayout { merged_bands: bands, ..SheetLayout::default() };
+        let formatter = CellFormatter { layout: Arc::new(layout), ..CellFormatter::default() };
+        assert_eq!(formatter.merged_origin((500_000, 0)), Some((0, 0)));
+        assert_eq!(formatter.merged_origin((0, 0)), None);
+        assert_eq!(formatter.merged_origin((2, 2)), Some((1, 1)));
+        assert_eq!(formatter.merged_origin((2, 3)), None);
+        assert_eq!(formatter.merged_origin((3, 1)), None);
+    }
+
+    #[test]
+    fn test_cell_notes_comments_and_links() {
+        let file = XlsxBuilder::new()
+            .sheet("Budget", vec![
+                vec!["Item", "Amount", "Source"],
+                vec!["Rent", "1200", "Lease"],
+                vec!["Travel", "300", "Policy"],
+                vec!["Old", "5", ""],
+            ])
+            .note("B2", "Ann Lee", "Includes\nparking")
+            .comment("B3", "Bo Chen", "Is this final?")
+            .comment("B3", "Ann Lee", "Yes")
+            .note("B3", "tc={C1}", "[Threaded comment]\n\nYour version of Excel allows you to read this threaded comment")
+            .link("C2", "https://example.com/lease.pdf")
+            .link("C3", "#Policies!A1")
+            .note("A4", "Ann Lee", "Hidden row")
+            .hide_row(4)
+            .file();
+        let path = file.path().to_str().unwrap();
+
+        let (markdown, _) = read_excel_sheets(path, &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains(
+            "| Travel | 300 | Policy |\n\n*1 hidden row not shown*\n\n### Annotations\n\n\
+             - **B2** Note by Ann Lee: Includes parking\n\
+             - **C2** Link to https://example.com/lease.pdf\n\
+             - **B3** Comment by Bo Chen (2024-03-01): Is this final?\n  - Reply by Ann Lee (2024-03-02): Yes\n\
+             - **C3** Link to #Policies!A1\n\n"
+        ), "{}", markdown);
+        assert!(!markdown.contains("Hidden row") && !markdown.contains("[Threaded comment]"));
+
+        let (markdown, _) = read_excel_range(path, "A1:A3", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(!markdown.contains("Annotations"), "{}", markdown);
+
+        let (markdown, _) = read_excel_rows(path, "3", &[1], FormulaMode::Values, OutputFormat::Csv).unwrap();
+        assert!(markdown.contains("```\n\n### Annotations\n\n- **B3** Comment by Bo Chen"), "{}", markdown);
+        assert!(!markdown.contains("**B2**"), "{}", markdown);
+    }
+
+    #[test]
+    fn test_tables_and_defined_names() {
+        let file = XlsxBuilder::new()
+            .sheet("Notes", vec![vec!["see data"]])
+            .sheet("Data", vec![
+                vec!["Title", "", ""],
+                vec!["Region", "Q1", "Q2"],
+                vec!["North", "5", "6"],
+                vec!["South", "7", "8"],
+                vec!["", "", ""],
+                vec!["Code", "Rate"],
+                vec!["A", "0.5"],
+            ])
+            .table("Sales", "A2:C4")
+            .table("Rates", "A6:B7")
+            .defined_name("Quarters", "Data!$B$2:$C$4")
+            .defined_name("TaxRate", "0.2")
+            .defined_name("_xlnm._FilterDatabase", "Data!$A$2:$C$4")
+            .file();
+        let path = file.path().to_str().unwrap();
+
+        let tables = read_excel_tables(path).unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].name, "Sales");
+        assert_eq!(tables[0].range.to_string(), "Data!A2:C4");
+        assert_eq!(tables[0].columns, vec!["Region", "Q1", "Q2"]);
+
+        let result = get_spreadsheet_tables(path);
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert!(result.content.contains(
+            "## Tables\n\n| Name | Sheet | Range | Columns |\n| --- | --- | --- | --- |\n| Sales | Data | A2:C4 | Region, Q1, Q2 |\n| Rates | Data | A6:B7 | Code, Rate |\n"
+        ), "{}", result.content);
+        assert!(result.content.contains("| Quarters | Data | B2:C4 | Q1, Q2 |\n| TaxRate |  | =0.2 |  |\n"), "{}", result.content);
+        assert!(!result.content.contains("_FilterDatabase"));
+
+        let (markdown, sheets) = read_excel_range(path, "sales", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert_eq!(sheets, vec![2]);
+        assert!(markdown.contains("## Sheet 2: Data!A2:C4\n\n| Region | Q1 | Q2 |\n| --- | --- | --- |\n| North | 5 | 6 |\n| South | 7 | 8 |\n"), "{}", markdown);
+
+        let empty = xlsx_file(&[("Sheet1", vec![vec!["a"]])], &[]);
+        let result = get_spreadsheet_tables(empty.path().to_str().unwrap());
+        assert!(result.content.contains("*No tables*") && result.content.contains("*No defined names*"));
+    }
+
+    #[test]
+    fn test_cell_text_is_escaped() {
+        let file = xlsx_file(&[("Notes", vec![
+            vec!["Label", "Comment", ""],
+            vec!["Net | gross", "see *note*\nand `code`", ""],
+            vec!["", "", ""],
+        ])], &[]);
+        let (markdown, _) = read_excel_range(file.path().to_str().unwrap(), "A1:C3", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.ends_with(
+            "| Label | Comment |\n| --- | --- |\n| Net \\| gross | see \\*note\\*<br>and \\`code\\` |\n\n"
+        ), "{}", markdown);
+    }
+
+    #[test]
+    fn test_parse_cell_range() {
+        let range = CellRange::parse("Sheet2!B3:H200").unwrap();
+        assert_eq!(range, CellRange { sheet: Some("Sheet2".to_string()), start: (2, 1), end: (199, 7) });
+        assert_eq!(range.to_string(), "Sheet2!B3:H200");
+
+        let range = CellRange::parse("'Q1 ''24'!$AA$10").unwrap();
+        assert_eq!(range, CellRange { sheet: Some("Q1 '24".to_string()), start: (9, 26), end: (9, 26) });
+        assert_eq!(range.to_string(), "'Q1 ''24'!AA10");
+
+        assert_eq!(CellRange::parse("C5:A1").unwrap().start, (0, 0));
+        assert_eq!(CellRange::parse("B:C").unwrap().end, (MAX_ROW, 2));
+        assert_eq!(CellRange::parse("3:4").unwrap().start, (2, 0));
+        assert!(CellRange::parse("A0").is_err());
+        assert!(CellRange::parse("A1:B").is_err());
+        assert!(CellRange::parse("XFE1").is_err());
+        assert!(CellRange::parse("Totals").is_err());
+    }
+
+    #[test]
+    fn test_parse_row_window() {
+        assert_eq!(RowWindow::parse("1-500").unwrap(), RowWindow { start: 0, end: Some(499) });
+        assert_eq!(RowWindow::parse(" 501- ").unwrap(), RowWindow { start: 500, end: None });
+        assert_eq!(RowWindow::parse("42").unwrap(), RowWindow { start: 41, end: Some(41) });
+        assert!(RowWindow::parse("0-10").is_err());
+        assert!(RowWindow::parse("10-2").is_err());
+        assert!(RowWindow::parse("first").is_err());
+    }
+
+    #[test]
+    fn test_read_excel_rows_repeats_header() {
+        let mut rows = vec![vec!["Id", "Value"]];
+        let values: Vec<String> = (1..=6).map(|i| (i * 10).to_string()).collect();
+        let ids: Vec<String> = (1..=6).map(|i| i.to_string()).collect();
+        for (id, value) in ids.iter().zip(&values) {
+            rows.push(vec![id.as_str(), value.as_str()]);
+        }
+        let file = xlsx_file(&[("Export", rows), ("Blank", vec![])], &[]);
+        let path = file.path().to_str().unwrap();
+
+        let (markdown, sheets) = read_excel_rows(path, "1-3", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert_eq!(sheets, vec![1]);
+        assert!(markdown.contains("## Sheet 1: Export (rows 2-3 of 7)\n\n| Id | Value |\n| --- | --- |\n| 1 | 10 |\n| 2 | 20 |\n\n"));
+
+        let (markdown, _) = read_excel_rows(path, "6-", &[1, 2], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("(rows 6-7 of 7)\n\n| Id | Value |\n| --- | --- |\n| 5 | 50 |\n| 6 | 60 |\n\n"));
+        assert!(markdown.contains("## Sheet 2: Blank\n\nEmpty sheet"));
+
+        let (markdown, _) = read_excel_rows(path, "100-200", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("*No rows in this window*"));
+    }
+
+    #[test]
+    fn test_cells_use_number_formats() {
+        let file = XlsxBuilder::new()
+            .sheet("Sales", vec![
+                vec!["Date", "Revenue", "Margin", "Units", "Time", "Plain"],
+                vec!["45122", "1234.5", "0.256", "1234567", "0.75", "0.30000000000000004"],
+            ])
+            .builtin_format("A2", 14)
+            .number_format("B2", "[$€-407]#,##0.00;-[$€-407]#,##0.00")
+            .builtin_format("C2", 10)
+            .builtin_format("D2", 3)
+            .number_format("E2", "h:mm AM/PM")
+            .file();
+        let path = file.path().to_str().unwrap();
+
+        let (markdown, _) = read_excel_range(path, "A1:F2", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("| 7/15/2023 | €1,234.50 | 25.60% | 1,234,567 | 6:00 PM | 0.3 |\n"), "{}", markdown);
+
+        let formats = WorkbookFormats::open(path);
+        let formatter = formats.sheet("Sales");
+        assert_eq!(formatter.format((1, 1), &Data::Float(-2.0)), "-€2.00");
+        assert_eq!(formatter.format((5, 5), &Data::Bool(true)), "TRUE");
+        assert_eq!(formatter.format((1, 5), &Data::Float(0.5)), "0.5");
+        // The sheet is read once and shared by later formatters
+        assert!(Arc::ptr_eq(&formatter.layout, &formats.sheet("Sales").layout));
+
+        // Mac workbooks count days from 1904
+        let file = XlsxBuilder::new().date_1904().sheet("Dates", vec![vec!["43660"]]).number_format("A1", "yyyy-mm-dd").file();
+        let (markdown, _) = read_excel_range(file.path().to_str().unwrap(), "A1", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("| 2023-07-15 |\n"), "{}", markdown);
+    }
+
+    #[test]
+    fn test_read_excel_range() {
+        let file = xlsx_file(
+            &[
+                ("Summary", vec![vec!["Total", "42"]]),
+                ("Data Export", vec![
+                    vec!["Region", "Q1", "Q2", "Q3"],
+                    vec!["North", "10", "11", "12"],
+                    vec!["South", "20", "21", "22"],
+                    vec!["West", "30", "31", "32"],
+                ]),
+            ],
+            &[("Quarters", "'Data Export'!$B$1:$C$3"), ("Split", "Summary!$A$1,'Data Export'!$A$4")],
+        );
+        let path = file.path().to_str().unwrap();
+
+        let (markdown, sheets) = read_excel_range(path, "'Data Export'!A2:B3", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert_eq!(sheets, vec![2]);
+        assert!(markdown.contains("## Sheet 2: 'Data Export'!A2:B3\n\n| North | 10 |\n| --- | --- |\n| South | 20 |\n"));
+        assert!(!markdown.contains("West"));
+
+        let (markdown, _) = read_excel_range(path, "quarters", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("| Q1 | Q2 |\n| --- | --- |\n| 10 | 11 |\n| 20 | 21 |\n"));
+
+        let (markdown, sheets) = read_excel_range(path, "Split", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert_eq!(sheets, vec![1, 2]);
+        assert!(markdown.contains("## Sheet 1: Summary!A1\n\n| Total |\n"));
+        assert!(markdown.contains("| West |\n"));
+
+        // Unqualified ranges apply to the requested sheets and are clipped to the data
+        let (markdown, _) = read_excel_range(path, "C:D", &[2], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("| Q2 | Q3 |\n"));
+        let (markdown, _) = read_excel_range(path, "Z100", &[2], FormulaMode::Values, OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("*No data in this range*"));
+
+        assert!(read_excel_range(path, "Missing!A1", &[1], FormulaMode::Values, OutputFormat::Markdown).unwrap_err().to_string().contains("Available sheets: Summary, Data Export"));
+        assert!(read_excel_range(path, "NoSuchName", &[1], FormulaMode::Values, OutputFormat::Markdown).is_err());
+    }
+}
@@ -18,6 +18,7 @@ pub enum PdfBackend {
     MuPDF,
     #[cfg(feature = "poppler")]
     Poppler,
+    Lopdf,
     PdfExtract, // Fallback
 }
 
@@ -344,6 +345,95 @@ impl PdfExtractor for PopplerExtractor {
     }
 }
 
+/// lopdf extractor (pure Rust, exact page count, text decoded per page from content streams)
+pub struct LopdfExtractor;
+
+impl PdfExtractor for LopdfExtractor {
+    fn extract_text(&self, file_path: &str) -> Result<String> {
+        let document = Self::load(file_path)?;
+        let pages = Self::decode_pages(&document, None)?;
+        Ok(pages.into_iter().map(|(_, text)| text).collect::<Vec<_>>().join("\n"))
+    }
+
+    fn extract_text_from_bytes(&self, pdf_bytes: &[u8]) -> Result<String> {
+        let document = match std::panic::catch_unwind(|| lopdf::Document::load_mem(pdf_bytes)) {
+            Ok(result) => result.map_err(|e| anyhow::anyhow!("Failed to load PDF from bytes with lopdf: {}", e))?,
+            Err(_) => anyhow::bail!("lopdf backend panicked while loading PDF from bytes"),
+        };
+        let pages = Self::decode_pages(&document, None)?;
+        Ok(pages.into_iter().map(|(_, text)| text).collect::<Vec<_>>().join("\n"))
+    }
+
+    fn get_page_count(&self, file_path: &str) -> Result<usize> {
+        Ok(Self::load(file_path)?.get_pages().len())
+    }
+
+    fn extract_pages_text(&self, file_path: &str, page_numbers: &[usize]) -> Result<String> {
+        let document = Self::load(file_path)?;
+        let page_count = document.get_pages().len();
+        if let Some(&page_num) = page_numbers.iter().find(|&&page_num| page_num == 0 || page_num > page_count) {
+            return Err(anyhow::anyhow!("Page {} is out of range (1-{})", page_num, page_count));
+        }
+
+        let pages = Self::decode_pages(&document, Some(page_numbers))?;
+        let mut result = String::new();
+        for (page_num, text) in pages {
+            result.push_str(&format!("=== Page {} ===\n", page_num));
+            result.push_str(&text);
+            result.push_str("\n\n");
+        }
+        Ok(result)
+    }
+
+    fn backend_type(&self) -> PdfBackend {
+        PdfBackend::Lopdf
+    }
+
+    fn description(&self) -> &'static str {
+        "lopdf (pure Rust, exact page count and per-page text)"
+    }
+}
+
+impl LopdfExtractor {
+    /// Load a document with lopdf, turning a parser panic into an error
+    pub(crate) fn load(file_path: &str) -> Result<lopdf::Document> {
+        match std::panic::catch_unwind(|| lopdf::Document::load(file_path)) {
+            Ok(result) => result.map_err(|e| anyhow::anyhow!("Failed to load PDF with lopdf: {}", e))
+                .with_context(|| format!("Failed to load PDF with lopdf: {}", file_path)),
+            Err(_) => anyhow::bail!("lopdf backend panicked while loading: {}", file_path),
+        }
+    }
+
+    /// Decode the requested pages (all when `None`), in the order given. Fails
+    /// when the document shows text but none of it could be decoded, so that
+    /// the next backend gets a chance.
+    fn decode_pages(document: &lopdf::Document, page_numbers: Option<&[usize]>) -> Result<Vec<(usize, String)>> {
+        let pages = document.get_pages();
+        let selected: Vec<usize> = match page_numbers {
+            Some(page_numbers) => page_numbers.to_vec(),
+            None => (1..=pages.len()).collect(),
+        };
+
+        let mut decoded = Vec::new();
+        let mut undecoded = 0;
+        for page_num in selected {
+            let Some(&page_id) = pages.get(&(page_num as u32)) else {
+                continue;
+            };
+            let page = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| crate::pdf_text::extract_page_text(document, page_id)))
+                .map_err(|_| anyhow::anyhow!("lopdf backend panicked while decoding page {}", page_num))?
+                .with_context(|| format!("Failed to decode page {} with lopdf", page_num))?;
+            undecoded += page.undecoded;
+            decoded.push((page_num, page.text));
+        }
+
+        if undecoded > 0 && decoded.iter().all(|(_, text)| text.trim().is_empty()) {
+            anyhow::bail!("lopdf could not decode the fonts of this PDF ({} text runs without a usable encoding)", undecoded);
+        }
+        Ok(decoded)
+    }
+}
+
 /// PDF Extract fallback extractor (slowest, limited encoding support)
 pub struct PdfExtractExtractor;
 
@@ -641,6 +731,9 @@ impl FastPdfExtractor {
         #[cfg(feature = "poppler")]
         extractors.push(Box::new(PopplerExtractor));
         
+        // lopdf reads the page tree exactly and decodes most fonts without native libraries
+        extractors.push(Box::new(LopdfExtractor));
+        
         // pdf-extract as fallback (slowest but most compatible)
         extractors.push(Box::new(PdfExtractExtractor));
         
@@ -760,3 +853,77 @@ impl FastPdfExtractor {
         PdfExtractExtractor::check_encoding_compatibility(file_path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{Document, Object, Stream, dictionary};
+
+    /// Two pages: WinAnsi Helvetica lines, then an Identity-H font with a ToUnicode map
+    fn two_page_pdf() -> tempfile::NamedTempFile {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let helvetica = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let cmap = b"/CIDInit /ProcSet findresource begin\nbegincmap\n1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n\
+            1 beginbfchar\n<0003> <0020>\nendbfchar\n1 beginbfrange\n<0024> <0030> <00C0>\nendbfrange\nendcmap\nend".to_vec();
+        let to_unicode = doc.add_object(Stream::new(dictionary! {}, cmap));
+        let composite = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "Subset+Sans",
+            "Encoding" => "Identity-H",
+            "ToUnicode" => to_unicode,
+        });
+        let resources = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => helvetica, "F2" => composite },
+        });
+
+        let contents = [
+            b"BT /F1 12 Tf 72 720 Td (Quarterly report) Tj 0 -14 Td [(Net) -400 (income \x80 5)] TJ 120 0 Td (rose) Tj ET".to_vec(),
+            b"BT /F2 12 Tf 72 720 Td <002400250003002C> Tj ET".to_vec(),
+        ];
+        let kids: Vec<Object> = contents.into_iter()
+            .map(|content| {
+                let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                    "Resources" => resources,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                }).into()
+            })
+            .collect();
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+        }));
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+
+        let mut file = tempfile::NamedTempFile::with_suffix(".pdf").unwrap();
+        doc.save_to(&mut file).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_lopdf_page_count_and_pages_text() {
+        let file = two_page_pdf();
+        let path = file.path().to_str().unwrap();
+
+        assert_eq!(LopdfExtractor.get_page_count(path).unwrap(), 2);
+        assert_eq!(
+            LopdfExtractor.extract_pages_text(path, &[2, 1]).unwrap(),
+            "=== Page 2 ===\nÀÁ È\n\n=== Page 1 ===\nQuarterly report\nNet income € 5 rose\n\n"
+        );
+        let error = LopdfExtractor.extract_pages_text(path, &[3]).unwrap_err();
+        assert_eq!(error.to_string(), "Page 3 is out of range (1-2)");
+        assert_eq!(FastPdfExtractor::get_page_count(path).unwrap(), 2);
+    }
+}
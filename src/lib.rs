@@ -2,9 +2,23 @@
 /// Supports Excel, PDF, DOCX, and PowerPoint files with streaming capabilities
 
 pub mod document_parser;
+pub mod docx_parser;
+pub mod doc_parser;
+pub mod odf_parser;
+pub mod excel_parser;
+pub mod excel_annotations;
+pub mod number_format;
+pub mod markdown_table;
+pub mod structured_output;
+pub mod csv_parser;
+pub mod spreadsheet_profile;
+pub mod spreadsheet_query;
 pub mod mcp_handler;
 pub mod streaming_parser;
 pub mod fast_pdf_extractor;
+pub mod pdf_text;
+pub mod pdf_outline;
+pub mod pdf_metadata;
 pub mod shared_utils;
 pub mod powerpoint_parser;
 pub mod cache_system;
@@ -18,12 +32,27 @@ pub use document_parser::{
     DocumentPageInfoResult,
     ExcelCache,
     DocxCache,
+    DocumentReadOptions,
     process_document_with_pages, 
+    process_document_with_options,
     get_document_page_info,
     read_excel_to_markdown,
     read_docx_to_markdown
 };
 
+/// Re-export DOCX functionality
+pub use docx_parser::{DocxMarkdownRenderer, ReviewMode, docx_to_markdown};
+
+/// Re-export structured spreadsheet output
+pub use structured_output::OutputFormat;
+
+/// Re-export spreadsheet table discovery
+pub use excel_parser::{ExcelTable, get_spreadsheet_tables, read_excel_tables};
+
+/// Re-export spreadsheet column profiling
+pub use spreadsheet_profile::profile_spreadsheet;
+pub use spreadsheet_query::query_spreadsheet;
+
 /// Re-export PowerPoint functionality
 pub use powerpoint_parser::{
     PowerPointProcessingResult,
@@ -42,11 +71,13 @@ pub use streaming_parser::{
     ProcessingProgress, 
     StreamingConfig, 
     stream_pdf_to_markdown, 
-    stream_excel_to_markdown
+    stream_excel_to_markdown,
+    stream_csv_to_markdown
 };
 
 /// Re-export shared utilities
 pub use shared_utils::{
+    FileTextResult,
     PdfCache,
     parse_pages_parameter,
     get_or_cache_pdf_content,
@@ -54,6 +85,7 @@ pub use shared_utils::{
     extract_char_range_from_cache,
     clear_pdf_cache,
     clear_excel_cache,
+    clear_csv_cache,
     clear_docx_cache,
     clear_powerpoint_cache,
     clear_all_caches,
@@ -67,6 +99,7 @@ pub use shared_utils::{
 
 /// Re-export fast PDF extraction
 pub use fast_pdf_extractor::{FastPdfExtractor, PdfBackend};
+pub use pdf_outline::get_pdf_outline;
 
 /// Re-export caching system
 pub use cache_system::{CacheableContent, CacheEntry}; 
\ No newline at end of file
@@ -0,0 +1,87 @@
+/// Escape plain text for a markdown table cell. Pipes, backslashes, asterisks
+/// and backticks are escaped so they stay literal, and line breaks become `<br>`.
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' | '|' | '*' | '`' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                escaped.push_str("<br>");
+            }
+            '\n' => escaped.push_str("<br>"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Fit already rendered markdown, such as a Word table cell, into a table cell:
+/// pipes are escaped and line breaks become `<br>`, but formatting is kept
+pub fn escape_markdown(markdown: &str) -> String {
+    markdown.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>")
+}
+
+/// Render rows of escaped cells as a markdown table, using the first row as the
+/// header. Short rows are padded, and trailing rows and columns without any
+/// content are left out. Returns an empty string when no cell has content.
+pub fn render_table(rows: impl IntoIterator<Item = Vec<String>>) -> String {
+    let mut rows: Vec<Vec<String>> = rows.into_iter().collect();
+    let width = rows.iter()
+        .map(|row| row.iter().rposition(|cell| !cell.is_empty()).map_or(0, |last| last + 1))
+        .max()
+        .unwrap_or(0);
+    if width == 0 {
+        return String::new();
+    }
+    // The header row is kept even when the rows below it are empty
+    let height = rows.iter().skip(1).rposition(|row| row.iter().any(|cell| !cell.is_empty())).map_or(1, |last| last + 2);
+    rows.truncate(height);
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    for (index, mut row) in rows.into_iter().enumerate() {
+        row.resize(width, String::new());
+        lines.push(format!("| {} |", row.join(" | ")));
+        if index == 0 {
+            lines.push(format!("| {} |", vec!["---"; width].join(" | ")));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Render rows of plain text cells as a markdown table, escaping every cell
+pub fn text_table(rows: impl IntoIterator<Item = Vec<String>>) -> String {
+    render_table(rows.into_iter().map(|row| row.iter().map(|cell| escape_text(cell)).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape_text("a|b"), "a\\|b");
+        assert_eq!(escape_text("*bold* `code` C:\\temp"), "\\*bold\\* \\`code\\` C:\\\\temp");
+        assert_eq!(escape_text("line 1\r\nline 2\nline 3"), "line 1<br>line 2<br>line 3");
+        assert_eq!(escape_markdown("**Chat** | Email\nPhone"), "**Chat** \\| Email<br>Phone");
+    }
+
+    #[test]
+    fn test_render_table_trims_empty_edges() {
+        let rows = vec![
+            vec!["Name".to_string(), "Qty".to_string(), String::new()],
+            vec!["Pen".to_string()],
+            vec![String::new(), String::new(), String::new()],
+        ];
+        assert_eq!(render_table(rows), "| Name | Qty |\n| --- | --- |\n| Pen |  |");
+
+        let header_only = vec![vec!["Name".to_string()], vec![String::new()]];
+        assert_eq!(render_table(header_only), "| Name |\n| --- |");
+        assert_eq!(render_table(vec![vec![String::new()]]), "");
+        assert_eq!(text_table(vec![vec!["a|b".to_string()], vec!["*".to_string()]]), "| a\\|b |\n| --- |\n| \\* |");
+    }
+}
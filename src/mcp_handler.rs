@@ -11,9 +11,15 @@ use anyhow::Result;
 use tokio_stream::StreamExt;
 use serde_json;
 
-use crate::document_parser::{process_document_with_pages, get_document_page_info, DocumentProcessingResult, DocumentPageInfoResult};
-use crate::shared_utils::resolve_file_path_string;
-use crate::streaming_parser::{stream_pdf_to_markdown, stream_excel_to_markdown, StreamingConfig, ProcessingProgress};
+use crate::document_parser::{process_document_with_options, get_document_page_info, DocumentProcessingResult, DocumentPageInfoResult, DocumentReadOptions};
+use crate::docx_parser::ReviewMode;
+use crate::excel_parser::{get_spreadsheet_tables, FormulaMode};
+use crate::structured_output::OutputFormat;
+use crate::spreadsheet_profile::profile_spreadsheet;
+use crate::spreadsheet_query::query_spreadsheet;
+use crate::pdf_outline::get_pdf_outline;
+use crate::shared_utils::{resolve_file_path_string, FileTextResult};
+use crate::streaming_parser::{stream_pdf_to_markdown, stream_excel_to_markdown, stream_csv_to_markdown, StreamingConfig, ProcessingProgress};
 use crate::powerpoint_parser::{
     process_powerpoint_with_slides, 
     get_powerpoint_slide_info, 
@@ -33,8 +39,38 @@ pub struct ReadOfficeDocumentInput {
 pub struct ReadOfficeDocumentByPageInput {
     #[schemars(description = "Path to the office document file")]
     pub file_path: String,
-    #[schemars(description = "Page/slide selection: integer for single page (e.g., 1), string for ranges/multiple pages (e.g., '1,3,5-7'), or 'all' for all pages/slides")]
+    #[schemars(description = "Page/slide selection: integer for single page (e.g., 1), string for ranges/multiple pages (e.g., '1,3,5-7'), or 'all' for all pages/slides. Pages are sheets for spreadsheets (hidden sheets are only read when selected), slides for presentations, and split on page and section breaks for Word files")]
     pub pages: Option<serde_json::Value>,
+    #[schemars(description = "DOCX only: how tracked changes and comments are shown. 'accept' (default) returns the final text, 'reject' the original text, and 'annotate' marks insertions, deletions and comment threads inline with author and date")]
+    pub review_mode: Option<String>,
+    #[schemars(description = "Spreadsheets and CSV/TSV files only: A1-style cell range (e.g., 'Sheet2!B3:H200', 'B3:H200', 'A:C'), a defined name or an Excel table name (see list_spreadsheet_tables); returns only that rectangle. Ranges without a sheet name are read from the sheets selected by pages")]
+    pub range: Option<String>,
+    #[schemars(description = "Spreadsheets and CSV/TSV files only: window of worksheet rows to return from each selected sheet (e.g., '1-500', '501-1000', or '1001-' for the rest). The header row is repeated in every window; get_document_page_info reports each sheet's dimensions")]
+    pub rows: Option<String>,
+    #[schemars(description = "Spreadsheets only: 'values' (default) shows cached cell values, 'formulas' shows each formula cell's formula instead, 'both' shows the value followed by its formula, and 'formulas_only' lists just the formula cells as Cell | Formula | Value")]
+    pub formula_mode: Option<String>,
+    #[schemars(description = "Spreadsheets and CSV/TSV files only: 'markdown' (default) returns tables of the cells as displayed; 'csv', 'json_records' (one object per row keyed by the header row) and 'json_columns' (one array per column) return typed values instead, with numbers as numbers, booleans as booleans and dates as ISO 8601, in a fenced code block per sheet or range")]
+    pub output_format: Option<String>,
+    #[schemars(description = "PDF only: outline (bookmark) title or path of titles separated by '>' (e.g., 'Chapter 7 > Error Codes') to read the pages of that section instead of selecting pages; titles match case-insensitively by prefix. See get_pdf_outline")]
+    pub section: Option<String>,
+}
+
+/// Input for the profile_spreadsheet tool
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema)]
+pub struct ProfileSpreadsheetInput {
+    #[schemars(description = "Path to the spreadsheet or CSV/TSV file")]
+    pub file_path: String,
+    #[schemars(description = "Sheet selection: integer for a single sheet (e.g., 1), string for ranges/multiple sheets (e.g., '1,3'), or 'all' (default) for all visible sheets")]
+    pub pages: Option<serde_json::Value>,
+}
+
+/// Input for the query_spreadsheet tool
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema)]
+pub struct QuerySpreadsheetInput {
+    #[schemars(description = "Path to the spreadsheet or CSV/TSV file")]
+    pub file_path: String,
+    #[schemars(description = "Query such as \"SELECT Region, SUM(Revenue) AS total FROM Sales WHERE YEAR(Date) = 2024 GROUP BY Region ORDER BY total DESC LIMIT 10\". FROM names a sheet, Excel table, defined name or A1 range (default: the first visible sheet); quote names with spaces in double quotes or brackets and text in single quotes")]
+    pub query: String,
 }
 
 /// Input for read by slide
@@ -158,6 +194,16 @@ impl From<DocumentPageInfoResult> for DocumentPageInfo {
     }
 }
 
+/// Tool output that is one block of markdown about a file
+impl IntoContents for FileTextResult {
+    fn into_contents(self) -> Vec<Content> {
+        match self.error {
+            Some(error) => vec![Content::text(format!("File: {}\nError: {}", self.file_path, error))],
+            None => vec![Content::text(format!("File: {}\n\n{}", self.file_path, self.content))],
+        }
+    }
+}
+
 /// Wrapper for PowerPoint slide snapshot
 pub struct SlideSnapshot {
     pub slide_number: usize,
@@ -210,7 +256,7 @@ impl OfficeReader {
     }
 
     /// Get the page information of an office document without reading the full content
-    #[tool(description = "Get the page information of an office document (Excel, PDF, DOCX, PowerPoint) without reading the full content")]
+    #[tool(description = "Get the page information of an office document (Excel, PDF, DOCX, PowerPoint) without reading the full content. For PDFs it also reports the document properties (title, author, subject, keywords, creator, producer, creation and modification dates), PDF version, encryption, tagged and PDF/A status and page sizes")]
     pub async fn get_document_page_info(
         &self,
         params: Parameters<ReadOfficeDocumentInput>,
@@ -224,8 +270,65 @@ impl OfficeReader {
         Ok(CallToolResult::success(doc_page_info.into_contents()))
     }
 
+    /// List the tables and defined names of a spreadsheet
+    #[tool(description = "List the Excel tables and defined names of a spreadsheet with their sheet, range and column headers; read one by passing its name as the range of read_office_document")]
+    pub async fn list_spreadsheet_tables(
+        &self,
+        params: Parameters<ReadOfficeDocumentInput>,
+    ) -> Result<CallToolResult, McpError> {
+        // Resolve file path at entry point
+        let resolved_path = resolve_file_path_string(&params.0.file_path)
+            .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))?;
+        
+        Ok(CallToolResult::success(get_spreadsheet_tables(&resolved_path).into_contents()))
+    }
+
+    /// Profile the columns of a spreadsheet without reading its rows
+    #[tool(description = "Profile each column of a spreadsheet or CSV/TSV file without returning its rows: inferred type, null count, distinct count, min/max/mean for numbers, date span for dates and sample values. Use it to understand the shape of a large sheet before reading it")]
+    pub async fn profile_spreadsheet(
+        &self,
+        params: Parameters<ProfileSpreadsheetInput>,
+    ) -> Result<CallToolResult, McpError> {
+        // Resolve file path at entry point
+        let resolved_path = resolve_file_path_string(&params.0.file_path)
+            .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))?;
+        let pages = match params.0.pages {
+            Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+            Some(serde_json::Value::String(s)) => Some(s),
+            _ => None,
+        };
+        
+        Ok(CallToolResult::success(profile_spreadsheet(&resolved_path, pages.as_deref()).into_contents()))
+    }
+
+    /// Run a SQL-like query over a sheet or table of a spreadsheet
+    #[tool(description = "Query a sheet, Excel table, defined name or A1 range of a spreadsheet or CSV/TSV file with a small SQL dialect and return the result as a markdown table. The header row gives the column names (case-insensitive). Supports SELECT [DISTINCT] with aliases and *, FROM, WHERE (=, <>, <, <=, >, >=, AND, OR, NOT, LIKE, IN, BETWEEN, IS NULL, arithmetic and ||), GROUP BY, HAVING, ORDER BY (ASC/DESC, by column, alias or position) and LIMIT/OFFSET; aggregates COUNT, SUM, AVG, MIN and MAX (with DISTINCT) and functions YEAR, MONTH, DAY, QUARTER, LOWER, UPPER, LENGTH, TRIM, ROUND, ABS and COALESCE. Dates compare as ISO 8601 text such as '2024-01-31'")]
+    pub async fn query_spreadsheet(
+        &self,
+        params: Parameters<QuerySpreadsheetInput>,
+    ) -> Result<CallToolResult, McpError> {
+        // Resolve file path at entry point
+        let resolved_path = resolve_file_path_string(&params.0.file_path)
+            .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))?;
+
+        Ok(CallToolResult::success(query_spreadsheet(&resolved_path, &params.0.query).into_contents()))
+    }
+
+    /// Get the outline (bookmarks) of a PDF as a table of contents
+    #[tool(description = "Get the outline (bookmarks) of a PDF as a nested table of contents with the target page of each entry. Pass an entry's title, or a path of titles such as 'Chapter 7 > Error Codes', as the section of read_office_document to read just that part of a long document")]
+    pub async fn get_pdf_outline(
+        &self,
+        params: Parameters<ReadOfficeDocumentInput>,
+    ) -> Result<CallToolResult, McpError> {
+        // Resolve file path at entry point
+        let resolved_path = resolve_file_path_string(&params.0.file_path)
+            .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))?;
+
+        Ok(CallToolResult::success(get_pdf_outline(&resolved_path).into_contents()))
+    }
+
     /// Read an office document and return its content as markdown with page selection
-    #[tool(description = "Read an office document (Excel, PDF, DOCX, PowerPoint) and return its content as markdown with page/slide selection")]
+    #[tool(description = "Read an office document (Excel, PDF, DOCX, PowerPoint) and return its content as markdown with page/slide selection. Spreadsheet tables are followed by the notes, comments and hyperlinks of their cells")]
     pub async fn read_office_document(
         &self,
         params: Parameters<ReadOfficeDocumentByPageInput>,
@@ -277,14 +380,38 @@ impl OfficeReader {
             }
         };
         
-        log::debug!("🔍 read_office_document: About to call process_document_with_pages with resolved_path='{}', pages_str={:?}", 
-                   resolved_path, pages_str);
+        let review_mode = match params.0.review_mode.as_deref() {
+            Some(mode) => mode.parse::<ReviewMode>()
+                .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))?,
+            None => ReviewMode::default(),
+        };
+        let formula_mode = match params.0.formula_mode.as_deref() {
+            Some(mode) => mode.parse::<FormulaMode>()
+                .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))?,
+            None => FormulaMode::default(),
+        };
+        let output_format = match params.0.output_format.as_deref() {
+            Some(format) => format.parse::<OutputFormat>()
+                .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))?,
+            None => OutputFormat::default(),
+        };
+        let options = DocumentReadOptions {
+            review_mode,
+            range: params.0.range,
+            rows: params.0.rows,
+            formula_mode,
+            output_format,
+            section: params.0.section,
+        };
+        
+        log::debug!("🔍 read_office_document: About to call process_document_with_options with resolved_path='{}', pages_str={:?}, options={:?}", 
+                   resolved_path, pages_str, options);
         
         let result = match std::panic::catch_unwind(|| {
-            process_document_with_pages(&resolved_path, pages_str)
+            process_document_with_options(&resolved_path, pages_str, &options)
         }) {
             Ok(result) => {
-                log::debug!("🔍 read_office_document: process_document_with_pages completed successfully");
+                log::debug!("🔍 read_office_document: process_document_with_options completed successfully");
                 result
             },
             Err(panic_info) => {
@@ -295,7 +422,7 @@ impl OfficeReader {
                 } else {
                     "Unknown panic occurred".to_string()
                 };
-                log::error!("❌ read_office_document: PANIC caught in process_document_with_pages: {}", panic_msg);
+                log::error!("❌ read_office_document: PANIC caught in process_document_with_options: {}", panic_msg);
                 return Err(ErrorData::new(ErrorCode::INTERNAL_ERROR, 
                           format!("Internal error during document processing: {}", panic_msg), None));
             }
@@ -441,7 +568,7 @@ impl OfficeReader {
                         };
                         return Ok(CallToolResult::success(content.into_contents()));
                     }
-                    "xlsx" | "xls" => {
+                    "xlsx" | "xlsm" | "xlsb" | "xls" | "xla" | "ods" => {
                         // Stream Excel content
                         let mut stream = Box::pin(stream_excel_to_markdown(&resolved_path, config));
                         let content = if let Some(progress) = stream.next().await {
@@ -459,6 +586,24 @@ impl OfficeReader {
                         };
                         return Ok(CallToolResult::success(content.into_contents()));
                     }
+                    "csv" | "tsv" => {
+                        // Stream CSV content in chunks of rows
+                        let mut stream = Box::pin(stream_csv_to_markdown(&resolved_path, config));
+                        let content = if let Some(progress) = stream.next().await {
+                            StreamingContent { progress }
+                        } else {
+                            StreamingContent {
+                                progress: ProcessingProgress {
+                                    current_page: 0,
+                                    total_pages: None,
+                                    current_chunk: "No content found".to_string(),
+                                    is_complete: true,
+                                    error: Some("No content found".to_string()),
+                                }
+                            }
+                        };
+                        return Ok(CallToolResult::success(content.into_contents()));
+                    }
                     _ => {
                         return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, format!("Unsupported file type for streaming: {}", ext), None));
                     }
@@ -481,18 +626,28 @@ impl ServerHandler for OfficeReader {
                 .build(),
             server_info: Implementation::from_build_env(),
             instructions: Some(
-                "This server provides functionality to read and parse office documents (Excel, PDF, DOCX, PowerPoint) and return their content as markdown. Available tools:\n\n\
+                "This server reads office documents (Excel, ODS, CSV/TSV, PDF, DOCX, DOC, ODT, PowerPoint, ODP) and returns their content as markdown. Available tools:\n\n\
                 1. get_document_page_info: Get page information of a document without reading the full content\n\
                 2. read_office_document: Read a document with page/slide selection (e.g., '1,3,5-7' or 'all')\n\
                 3. read_powerpoint_slides: Read PowerPoint slides with specific slide selection\n\
                 4. get_powerpoint_slide_info: Get PowerPoint slide information without reading content\n\
                 5. generate_powerpoint_slide_snapshot: Generate image snapshots of PowerPoint slides\n\
-                6. stream_office_document: Stream document content in chunks with progress tracking\n\n\
+                6. stream_office_document: Stream document content in chunks with progress tracking\n\
+                7. list_spreadsheet_tables: List the Excel tables and defined names of a workbook\n\
+                8. profile_spreadsheet: Summarize each column of a spreadsheet without reading its rows\n\
+                9. query_spreadsheet: Run a SQL-like query over a sheet or named table\n\
+                10. get_pdf_outline: Get the outline (bookmarks) of a PDF with target pages\n\n\
+                Options of read_office_document (see the parameter descriptions):\n\
+                - pages: sheets for spreadsheets, pages for PDF and Word files, slides for presentations\n\
+                - range, rows: read a block of cells or a window of rows of a spreadsheet\n\
+                - formula_mode: show spreadsheet formulas with or instead of values\n\
+                - output_format: return spreadsheet cells as typed CSV or JSON\n\
+                - review_mode: show or resolve DOCX tracked changes and comments\n\
+                - section: read one section of a PDF by its outline title\n\n\
                 File Path Support:\n\
                 - Supports both absolute and relative file paths\n\
                 - Relative paths are resolved using the PROJECT_ROOT environment variable if set\n\
                 - Falls back to current working directory if PROJECT_ROOT is not set\n\n\
-                For Excel files, pages refer to sheets. For PDF files, pages refer to actual pages. For DOCX files, there is only one page. For PowerPoint files, pages refer to slides.\n\
                 Use get_document_page_info or get_powerpoint_slide_info first to see available pages/slides, then use the appropriate read function with specific selection.".to_string()
             ),
         }
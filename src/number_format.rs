@@ -0,0 +1,777 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+/// Serial number of 29 February 1900, a day Excel counts although it never existed
+const LEAP_BUG_SERIAL: f64 = 60.0;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+const DAY_NAMES: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+
+/// Format codes of the built-in number formats (ids 0-49), as shown by Excel in the en-US locale
+pub fn builtin_format_code(id: u32) -> Option<&'static str> {
+    let code = match id {
+        0 => "General",
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        5 => "\"$\"#,##0_);(\"$\"#,##0)",
+        6 => "\"$\"#,##0_);[Red](\"$\"#,##0)",
+        7 => "\"$\"#,##0.00_);(\"$\"#,##0.00)",
+        8 => "\"$\"#,##0.00_);[Red](\"$\"#,##0.00)",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        12 => "# ?/?",
+        13 => "# ??/??",
+        14 => "m/d/yyyy",
+        15 => "d-mmm-yy",
+        16 => "d-mmm",
+        17 => "mmm-yy",
+        18 => "h:mm AM/PM",
+        19 => "h:mm:ss AM/PM",
+        20 => "h:mm",
+        21 => "h:mm:ss",
+        22 => "m/d/yyyy h:mm",
+        37 => "#,##0_);(#,##0)",
+        38 => "#,##0_);[Red](#,##0)",
+        39 => "#,##0.00_);(#,##0.00)",
+        40 => "#,##0.00_);[Red](#,##0.00)",
+        41 => "_(* #,##0_);_(* \\(#,##0\\);_(* \"-\"_);_(@_)",
+        42 => "_(\"$\"* #,##0_);_(\"$\"* \\(#,##0\\);_(\"$\"* \"-\"_);_(@_)",
+        43 => "_(* #,##0.00_);_(* \\(#,##0.00\\);_(* \"-\"??_);_(@_)",
+        44 => "_(\"$\"* #,##0.00_);_(\"$\"* \\(#,##0.00\\);_(\"$\"* \"-\"??_);_(@_)",
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mm:ss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Whether a format code displays numbers as dates or times
+pub fn is_date_format(code: &str) -> bool {
+    split_sections(code).first()
+        .is_some_and(|section| tokenize(section).iter().any(Token::is_date_time))
+}
+
+/// Display a number the way Excel shows it with the given format code.
+/// `date_1904` selects the 1904 date system used by some Mac workbooks.
+pub fn format_number(value: f64, code: &str, date_1904: bool) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+
+    let sections = split_sections(code);
+    let (section, value, show_sign) = choose_section(&sections, value);
+    let tokens = tokenize(section);
+
+    let formatted = if tokens.iter().any(Token::is_date_time) {
+        format_date_time(value, &tokens, date_1904)
+            .unwrap_or_else(|| format_general(value.abs()))
+    } else {
+        format_numeric(value.abs(), &tokens)
+    };
+    let formatted = formatted.trim();
+
+    if show_sign && value < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        format!("-{}", formatted)
+    } else {
+        formatted.to_string()
+    }
+}
+
+/// A date serial number in ISO 8601: a date, a date and time, or just the time
+/// for serials below 1 that carry no date
+pub fn iso_datetime(value: f64, date_1904: bool) -> Option<String> {
+    if !value.is_finite() || value < 0.0 {
+        return None;
+    }
+    let datetime = serial_to_datetime(value, date_1904)?;
+    Some(if value < 1.0 {
+        datetime.format("%H:%M:%S").to_string()
+    } else if datetime.time() == NaiveTime::MIN {
+        datetime.format("%Y-%m-%d").to_string()
+    } else {
+        datetime.format("%Y-%m-%dT%H:%M:%S").to_string()
+    })
+}
+
+/// A duration in days as an ISO 8601 duration, such as `PT36H30M0S`
+pub fn iso_duration(days: f64) -> String {
+    let seconds = (days.abs() * 86_400.0).round() as u64;
+    let sign = if days < 0.0 && seconds > 0 { "-" } else { "" };
+    format!("{}PT{}H{}M{}S", sign, seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// Excel's General format: integers as-is, other numbers to 10 significant digits
+pub fn format_general(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    let magnitude = value.abs();
+    if value.fract() == 0.0 && magnitude < 1e11 {
+        return format!("{:.0}", value);
+    }
+    if !(1e-9..1e11).contains(&magnitude) {
+        let formatted = format!("{:.5E}", value);
+        let (mantissa, exponent) = formatted.split_once('E').unwrap_or((&formatted, "0"));
+        let mantissa = trim_decimal_zeros(mantissa);
+        let exponent: i32 = exponent.parse().unwrap_or(0);
+        return format!("{}E{}{:02}", mantissa, if exponent < 0 { '-' } else { '+' }, exponent.abs());
+    }
+
+    let integer_digits = (magnitude.log10().floor() as i32 + 1).max(1);
+    let decimals = (10 - integer_digits).max(0) as usize;
+    trim_decimal_zeros(&round_to_decimals(value, decimals)).to_string()
+}
+
+/// Round to a number of decimals the way Excel does: half away from zero, after
+/// dropping the noise beyond 15 significant digits (so 1.005 rounds to 1.01)
+fn round_to_decimals(value: f64, decimals: usize) -> String {
+    let value: f64 = format!("{:.14e}", value).parse().unwrap_or(value);
+    let scale = 10f64.powi(decimals.min(15) as i32);
+    let rounded = (value.abs() * scale).round() / scale;
+    format!("{:.*}", decimals, rounded.copysign(value))
+}
+
+fn trim_decimal_zeros(number: &str) -> &str {
+    if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    }
+}
+
+/// Split a format code into its `;`-separated sections, ignoring quoted and escaped semicolons
+fn split_sections(code: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut section_start = 0;
+    let mut in_quotes = false;
+    let mut in_brackets = false;
+    let mut escaped = false;
+
+    for (index, ch) in code.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match ch {
+            '\\' if !in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            '[' if !in_quotes => in_brackets = true,
+            ']' if !in_quotes => in_brackets = false,
+            ';' if !in_quotes && !in_brackets => {
+                sections.push(&code[section_start..index]);
+                section_start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    sections.push(&code[section_start..]);
+    sections
+}
+
+/// A `[>=100]` style condition at the start of a section
+fn section_condition(section: &str) -> Option<(String, f64)> {
+    let inner = section.trim_start().strip_prefix('[')?;
+    let (condition, _) = inner.split_once(']')?;
+    let operator_len = condition.find(|c: char| !matches!(c, '<' | '>' | '=')).filter(|&len| len > 0)?;
+    let threshold = condition[operator_len..].trim().parse().ok()?;
+    Some((condition[..operator_len].to_string(), threshold))
+}
+
+fn condition_matches(operator: &str, threshold: f64, value: f64) -> bool {
+    match operator {
+        "<" => value < threshold,
+        "<=" => value <= threshold,
+        ">" => value > threshold,
+        ">=" => value >= threshold,
+        "=" => value == threshold,
+        "<>" => value != threshold,
+        _ => false,
+    }
+}
+
+/// Pick the section for a value; returns the section, the value to display and
+/// whether a minus sign has to be added for negative values
+fn choose_section<'a>(sections: &[&'a str], value: f64) -> (&'a str, f64, bool) {
+    // The fourth section formats text, and a trailing text-only section never formats numbers
+    let number_sections: Vec<&str> = sections.iter().take(3).copied()
+        .filter(|section| !section.contains('@') || section.contains(['0', '#', '?']))
+        .collect();
+    let number_sections = if number_sections.is_empty() { vec!["General"] } else { number_sections };
+
+    if let Some((operator, threshold)) = section_condition(number_sections[0]) {
+        if condition_matches(&operator, threshold, value) {
+            return (number_sections[0], value, true);
+        }
+        if let Some(second) = number_sections.get(1) {
+            let matches_second = section_condition(second)
+                .map(|(operator, threshold)| condition_matches(&operator, threshold, value))
+                .unwrap_or(true);
+            if matches_second {
+                return (second, value, section_condition(second).is_some());
+            }
+        }
+        return (number_sections.last().copied().unwrap_or("General"), value, true);
+    }
+
+    match number_sections.len() {
+        1 => (number_sections[0], value, true),
+        _ if value < 0.0 => (number_sections[1], value, false),
+        2 => (number_sections[0], value, true),
+        _ if value == 0.0 => (number_sections[2], value, true),
+        _ => (number_sections[0], value, true),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(String),
+    /// A digit placeholder: `0`, `#` or `?`
+    Digit(char),
+    Point,
+    Comma,
+    Percent,
+    /// Scientific notation; `true` for `E+`, which always shows the exponent's sign
+    Exponent(bool),
+    Slash,
+    General,
+    Text,
+    Year(usize),
+    Month(usize),
+    Day(usize),
+    Hour(usize),
+    Minute(usize),
+    Second(usize),
+    /// Fractions of a second, such as the `.0` of `mm:ss.0`
+    SubSecond(usize),
+    ElapsedHours,
+    ElapsedMinutes,
+    ElapsedSeconds,
+    /// `AM/PM`, or `A/P` when `false`
+    AmPm(bool),
+}
+
+impl Token {
+    fn is_date_time(&self) -> bool {
+        matches!(
+            self,
+            Token::Year(_) | Token::Month(_) | Token::Day(_) | Token::Hour(_) | Token::Minute(_)
+                | Token::Second(_) | Token::ElapsedHours | Token::ElapsedMinutes | Token::ElapsedSeconds
+                | Token::AmPm(_)
+        )
+    }
+}
+
+fn tokenize(section: &str) -> Vec<Token> {
+    let chars: Vec<char> = section.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    let run_length = |start: usize, target: char| {
+        chars[start..].iter().take_while(|c| c.eq_ignore_ascii_case(&target)).count()
+    };
+
+    while index < chars.len() {
+        let ch = chars[index];
+        let rest: String = chars[index..].iter().collect();
+        match ch {
+            '"' => {
+                let literal: String = chars[index + 1..].iter().take_while(|&&c| c != '"').collect();
+                index += literal.chars().count() + 2;
+                tokens.push(Token::Literal(literal));
+                continue;
+            }
+            '\\' => {
+                if let Some(&next) = chars.get(index + 1) {
+                    tokens.push(Token::Literal(next.to_string()));
+                }
+                index += 2;
+                continue;
+            }
+            // `_x` leaves room for the width of `x`; `*x` repeats `x` to fill the cell
+            '_' => {
+                tokens.push(Token::Literal(" ".to_string()));
+                index += 2;
+                continue;
+            }
+            '*' => {
+                index += 2;
+                continue;
+            }
+            '[' => {
+                let content: String = chars[index + 1..].iter().take_while(|&&c| c != ']').collect();
+                index += content.chars().count() + 2;
+                let lower = content.to_ascii_lowercase();
+                if let Some(currency) = content.strip_prefix('$') {
+                    let symbol = currency.split('-').next().unwrap_or_default();
+                    tokens.push(Token::Literal(symbol.to_string()));
+                } else if !lower.is_empty() && lower.chars().all(|c| c == 'h') {
+                    tokens.push(Token::ElapsedHours);
+                } else if !lower.is_empty() && lower.chars().all(|c| c == 'm') {
+                    tokens.push(Token::ElapsedMinutes);
+                } else if !lower.is_empty() && lower.chars().all(|c| c == 's') {
+                    tokens.push(Token::ElapsedSeconds);
+                }
+                // Colors and conditions do not change the text
+                continue;
+            }
+            '0' | '#' | '?' => tokens.push(Token::Digit(ch)),
+            '.' => {
+                let after_seconds = matches!(tokens.last(), Some(Token::Second(_)) | Some(Token::ElapsedSeconds));
+                let zeros = chars[index + 1..].iter().take_while(|&&c| c == '0').count();
+                if after_seconds && zeros > 0 {
+                    tokens.push(Token::SubSecond(zeros));
+                    index += zeros + 1;
+                    continue;
+                }
+                tokens.push(Token::Point);
+            }
+            ',' => tokens.push(Token::Comma),
+            '%' => tokens.push(Token::Percent),
+            '/' => tokens.push(Token::Slash),
+            '@' => tokens.push(Token::Text),
+            'E' | 'e' if matches!(chars.get(index + 1), Some('+') | Some('-')) => {
+                tokens.push(Token::Exponent(chars[index + 1] == '+'));
+                index += 2;
+                continue;
+            }
+            _ if rest.get(..7).is_some_and(|prefix| prefix.eq_ignore_ascii_case("general")) => {
+                tokens.push(Token::General);
+                index += 7;
+                continue;
+            }
+            _ if rest.get(..5).is_some_and(|prefix| prefix.eq_ignore_ascii_case("am/pm")) => {
+                tokens.push(Token::AmPm(true));
+                index += 5;
+                continue;
+            }
+            _ if rest.get(..3).is_some_and(|prefix| prefix.eq_ignore_ascii_case("a/p")) => {
+                tokens.push(Token::AmPm(false));
+                index += 3;
+                continue;
+            }
+            'y' | 'Y' | 'm' | 'M' | 'd' | 'D' | 'h' | 'H' | 's' | 'S' => {
+                let length = run_length(index, ch);
+                tokens.push(match ch.to_ascii_lowercase() {
+                    'y' => Token::Year(length),
+                    'm' => Token::Month(length),
+                    'd' => Token::Day(length),
+                    'h' => Token::Hour(length),
+                    _ => Token::Second(length),
+                });
+                index += length;
+                continue;
+            }
+            _ => tokens.push(Token::Literal(ch.to_string())),
+        }
+        index += 1;
+    }
+
+    resolve_minutes(&mut tokens);
+    tokens
+}
+
+/// `m` means minutes right after hours or right before seconds, and months otherwise
+fn resolve_minutes(tokens: &mut [Token]) {
+    let date_positions: Vec<usize> = (0..tokens.len()).filter(|&i| tokens[i].is_date_time()).collect();
+    for (position, &index) in date_positions.iter().enumerate() {
+        let Token::Month(length) = tokens[index] else { continue };
+        if length > 2 {
+            continue;
+        }
+        let previous = position.checked_sub(1).map(|p| &tokens[date_positions[p]]);
+        let next = date_positions.get(position + 1).map(|&i| &tokens[i]);
+        let after_hours = matches!(previous, Some(Token::Hour(_)) | Some(Token::ElapsedHours));
+        let before_seconds = matches!(next, Some(Token::Second(_)) | Some(Token::ElapsedSeconds));
+        if after_hours || before_seconds {
+            tokens[index] = Token::Minute(length);
+        }
+    }
+}
+
+/// Convert a date serial number to a date and time
+fn serial_to_datetime(value: f64, date_1904: bool) -> Option<NaiveDateTime> {
+    let (epoch, days) = if date_1904 {
+        (NaiveDate::from_ymd_opt(1904, 1, 1)?, value)
+    } else if value < LEAP_BUG_SERIAL {
+        (NaiveDate::from_ymd_opt(1899, 12, 31)?, value)
+    } else {
+        (NaiveDate::from_ymd_opt(1899, 12, 30)?, value)
+    };
+    let milliseconds = (days * 86_400_000.0).round() as i64;
+    epoch.and_hms_opt(0, 0, 0)?.checked_add_signed(Duration::milliseconds(milliseconds))
+}
+
+fn format_date_time(value: f64, tokens: &[Token], date_1904: bool) -> Option<String> {
+    if value < 0.0 {
+        return None;
+    }
+
+    // Round to the precision shown, so 11:59:59.6 displays as 12:00:00
+    let sub_second_digits = tokens.iter()
+        .find_map(|token| match token { Token::SubSecond(digits) => Some(*digits), _ => None })
+        .unwrap_or(0)
+        .min(3);
+    let unit = 10f64.powi(3 - sub_second_digits as i32);
+    let milliseconds = ((value * 86_400_000.0) / unit).round() * unit;
+    let value = milliseconds / 86_400_000.0;
+    let datetime = serial_to_datetime(value, date_1904)?;
+    let twelve_hour = tokens.iter().any(|token| matches!(token, Token::AmPm(_)));
+
+    let mut output = String::new();
+    for token in tokens {
+        match token {
+            Token::Literal(text) => output.push_str(text),
+            Token::Year(length) if *length <= 2 => output.push_str(&format!("{:02}", datetime.year() % 100)),
+            Token::Year(_) => output.push_str(&format!("{:04}", datetime.year())),
+            Token::Month(1) => output.push_str(&datetime.month().to_string()),
+            Token::Month(2) => output.push_str(&format!("{:02}", datetime.month())),
+            Token::Month(3) => output.push_str(&MONTH_NAMES[datetime.month0() as usize][..3]),
+            Token::Month(4) => output.push_str(MONTH_NAMES[datetime.month0() as usize]),
+            Token::Month(_) => output.push_str(&MONTH_NAMES[datetime.month0() as usize][..1]),
+            Token::Day(1) => output.push_str(&datetime.day().to_string()),
+            Token::Day(2) => output.push_str(&format!("{:02}", datetime.day())),
+            Token::Day(3) => output.push_str(&DAY_NAMES[datetime.weekday().num_days_from_monday() as usize][..3]),
+            Token::Day(_) => output.push_str(DAY_NAMES[datetime.weekday().num_days_from_monday() as usize]),
+            Token::Hour(length) => {
+                let hour = if twelve_hour {
+                    match datetime.hour() % 12 { 0 => 12, hour => hour }
+                } else {
+                    datetime.hour()
+                };
+                output.push_str(&if *length >= 2 { format!("{:02}", hour) } else { hour.to_string() });
+            }
+            Token::Minute(length) => output.push_str(&if *length >= 2 {
+                format!("{:02}", datetime.minute())
+            } else {
+                datetime.minute().to_string()
+            }),
+            Token::Second(length) => output.push_str(&if *length >= 2 {
+                format!("{:02}", datetime.second())
+            } else {
+                datetime.second().to_string()
+            }),
+            Token::SubSecond(digits) => {
+                let fraction = format!("{:03}", datetime.and_utc().timestamp_subsec_millis());
+                output.push('.');
+                output.push_str(&format!("{:0<width$}", &fraction[..(*digits).min(3)], width = *digits));
+            }
+            Token::ElapsedHours => output.push_str(&((value * 24.0).floor() as i64).to_string()),
+            Token::ElapsedMinutes => output.push_str(&((value * 1440.0).floor() as i64).to_string()),
+            Token::ElapsedSeconds => output.push_str(&((value * 86_400.0).round() as i64).to_string()),
+            Token::AmPm(full) => {
+                let morning = datetime.hour() < 12;
+                output.push_str(match (full, morning) {
+                    (true, true) => "AM",
+                    (true, false) => "PM",
+                    (false, true) => "A",
+                    (false, false) => "P",
+                });
+            }
+            Token::Digit(ch) => output.push(*ch),
+            Token::Point => output.push('.'),
+            Token::Comma => output.push(','),
+            Token::Percent => output.push('%'),
+            Token::Slash => output.push('/'),
+            Token::General | Token::Text | Token::Exponent(_) => {}
+        }
+    }
+    Some(output)
+}
+
+/// Format a non-negative number with a numeric (not date) section
+fn format_numeric(value: f64, tokens: &[Token]) -> String {
+    if tokens.iter().any(|token| matches!(token, Token::Slash))
+        && tokens.iter().any(|token| matches!(token, Token::Digit(_)))
+    {
+        return format_fraction(value, tokens);
+    }
+    if !tokens.iter().any(|token| matches!(token, Token::Digit(_))) {
+        // Sections without placeholders are literal text, or General
+        return tokens.iter()
+            .map(|token| match token {
+                Token::Literal(text) => text.clone(),
+                Token::General | Token::Text => format_general(value),
+                Token::Percent => "%".to_string(),
+                _ => String::new(),
+            })
+            .collect();
+    }
+
+    let exponent_at = tokens.iter().position(|token| matches!(token, Token::Exponent(_)));
+    let mantissa_tokens = &tokens[..exponent_at.unwrap_or(tokens.len())];
+    let point_at = mantissa_tokens.iter().position(|token| matches!(token, Token::Point));
+    let integer_end = point_at.unwrap_or(mantissa_tokens.len());
+
+    let integer_placeholders: Vec<usize> = (0..integer_end)
+        .filter(|&i| matches!(mantissa_tokens[i], Token::Digit(_)))
+        .collect();
+    let decimal_placeholders: Vec<usize> = (integer_end..mantissa_tokens.len())
+        .filter(|&i| matches!(mantissa_tokens[i], Token::Digit(_)))
+        .collect();
+
+    // A comma between placeholders groups thousands; commas after the last one scale by 1000
+    let last_integer_placeholder = integer_placeholders.last().copied();
+    let grouping = (0..integer_end).any(|i| {
+        matches!(mantissa_tokens[i], Token::Comma)
+            && last_integer_placeholder.is_some_and(|last| i < last)
+            && integer_placeholders.first().is_some_and(|&first| i > first)
+    });
+    let last_placeholder = decimal_placeholders.last().copied().or(last_integer_placeholder);
+    let scaling_commas = last_placeholder
+        .map(|last| mantissa_tokens[last + 1..].iter().take_while(|t| matches!(t, Token::Comma)).count())
+        .unwrap_or(0);
+    let is_scaling_comma = |index: usize| {
+        last_placeholder.is_some_and(|last| index > last && index <= last + scaling_commas)
+    };
+    let percents = tokens.iter().filter(|token| matches!(token, Token::Percent)).count();
+
+    let mut value = value * 100f64.powi(percents as i32) / 1000f64.powi(scaling_commas as i32);
+    let decimals = decimal_placeholders.len();
+
+    // Scientific notation: the mantissa keeps as many integer digits as placeholders
+    let mut exponent = 0i32;
+    if exponent_at.is_some() && value != 0.0 {
+        let step = if integer_placeholders.len() > 1
+            && integer_placeholders.iter().any(|&i| mantissa_tokens[i] == Token::Digit('#'))
+        {
+            integer_placeholders.len() as i32
+        } else {
+            1
+        };
+        exponent = value.log10().floor() as i32;
+        if step > 1 {
+            exponent -= exponent.rem_euclid(step);
+        } else {
+            exponent -= (integer_placeholders.len() as i32 - 1).max(0);
+        }
+        value /= 10f64.powi(exponent);
+        let rounded: f64 = round_to_decimals(value, decimals).parse().unwrap_or(value);
+        if rounded >= 10f64.powi(step.max(integer_placeholders.len() as i32)) {
+            value /= 10f64.powi(step);
+            exponent += step;
+        }
+    }
+
+    let rounded = round_to_decimals(value, decimals);
+    let (integer_digits, decimal_digits) = rounded.split_once('.').unwrap_or((&rounded, ""));
+    let integer_digits = integer_digits.trim_start_matches('0');
+
+    let mut integer_output: Vec<String> = vec![String::new(); integer_end];
+    if grouping {
+        let required = integer_placeholders.iter().filter(|&&i| mantissa_tokens[i] == Token::Digit('0')).count();
+        let padded = format!("{:0>width$}", integer_digits, width = required);
+        if let Some(&first) = integer_placeholders.first() {
+            integer_output[first] = group_thousands(&padded);
+        }
+    } else {
+        // Fill placeholders from the right; the leftmost one takes any extra digits
+        let digits: Vec<char> = integer_digits.chars().collect();
+        let mut remaining = digits.len();
+        for (position, &index) in integer_placeholders.iter().enumerate().rev() {
+            let Token::Digit(placeholder) = mantissa_tokens[index] else { continue };
+            integer_output[index] = if position == 0 {
+                let digits: String = digits[..remaining].iter().collect();
+                remaining = 0;
+                if digits.is_empty() { placeholder_fill(placeholder) } else { digits }
+            } else if remaining > 0 {
+                remaining -= 1;
+                digits[remaining].to_string()
+            } else {
+                placeholder_fill(placeholder)
+            };
+        }
+    }
+
+    // Trailing zeros are dropped for `#` and padded for `?`
+    let decimal_digits: Vec<char> = decimal_digits.chars().collect();
+    let mut decimal_output = vec![String::new(); decimals];
+    let mut trimming = true;
+    for position in (0..decimals).rev() {
+        let Token::Digit(placeholder) = mantissa_tokens[decimal_placeholders[position]] else { continue };
+        let digit = decimal_digits.get(position).copied().unwrap_or('0');
+        decimal_output[position] = if trimming && digit == '0' && placeholder != '0' {
+            placeholder_fill(placeholder)
+        } else {
+            trimming = false;
+            digit.to_string()
+        };
+    }
+
+    let mut output = String::new();
+    let mut decimal_position = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Digit(_) if index < integer_end => output.push_str(&integer_output[index]),
+            Token::Digit(_) if exponent_at.is_none_or(|at| index < at) => {
+                output.push_str(&decimal_output[decimal_position]);
+                decimal_position += 1;
+            }
+            Token::Digit(_) => {}
+            Token::Comma if index < integer_end || is_scaling_comma(index) => {}
+            Token::Comma => output.push(','),
+            Token::Point => output.push('.'),
+            Token::Percent => output.push('%'),
+            Token::Literal(text) => output.push_str(text),
+            Token::Exponent(always_sign) => {
+                let exponent_digits = tokens[index + 1..].iter().filter(|t| matches!(t, Token::Digit(_))).count();
+                let sign = if exponent < 0 { "-" } else if *always_sign { "+" } else { "" };
+                output.push_str(&format!("E{}{:0width$}", sign, exponent.abs(), width = exponent_digits.max(1)));
+            }
+            Token::General | Token::Text => output.push_str(&format_general(value)),
+            _ => {}
+        }
+    }
+    output
+}
+
+fn placeholder_fill(placeholder: char) -> String {
+    match placeholder {
+        '0' => "0".to_string(),
+        '?' => " ".to_string(),
+        _ => String::new(),
+    }
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Fractions such as `# ?/?` (whole part and fraction) or `?/8` (fixed denominator)
+fn format_fraction(value: f64, tokens: &[Token]) -> String {
+    let slash_at = tokens.iter().position(|token| matches!(token, Token::Slash)).unwrap_or(tokens.len());
+
+    // Placeholder groups before the slash: a whole part only if there are two
+    let mut groups = 0;
+    let mut in_group = false;
+    for token in &tokens[..slash_at] {
+        let is_digit = matches!(token, Token::Digit(_));
+        if is_digit && !in_group {
+            groups += 1;
+        }
+        in_group = is_digit;
+    }
+    let has_whole = groups >= 2;
+
+    // A literal number after the slash is a fixed denominator
+    let after_slash: String = tokens[slash_at + 1..].iter()
+        .take_while(|token| matches!(token, Token::Digit(_) | Token::Literal(_)))
+        .map(|token| match token {
+            Token::Digit(ch) => *ch,
+            Token::Literal(text) => text.chars().next().unwrap_or(' '),
+            _ => ' ',
+        })
+        .collect();
+    let fixed_denominator: Option<u64> = after_slash.trim().parse().ok()
+        .filter(|_| after_slash.trim().chars().all(|c| c.is_ascii_digit()) && !after_slash.contains(['#', '?']));
+    let denominator_digits = after_slash.chars().filter(|c| matches!(c, '0' | '#' | '?')).count().max(1);
+
+    let whole = if has_whole { value.trunc() } else { 0.0 };
+    let fraction = value - whole;
+    let (numerator, denominator) = match fixed_denominator {
+        Some(denominator) if denominator > 0 => ((fraction * denominator as f64).round() as u64, denominator),
+        _ => {
+            let max_denominator = 10u64.pow(denominator_digits as u32) - 1;
+            (1..=max_denominator)
+                .map(|denominator| ((fraction * denominator as f64).round() as u64, denominator))
+                .min_by(|a, b| {
+                    let error = |(n, d): (u64, u64)| (fraction - n as f64 / d as f64).abs();
+                    error(*a).partial_cmp(&error(*b)).unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or((0, 1))
+        }
+    };
+
+    let (whole, numerator) = if numerator == denominator && has_whole {
+        (whole + 1.0, 0)
+    } else {
+        (whole, numerator)
+    };
+    match (has_whole, numerator) {
+        (true, 0) => format!("{:.0}", whole),
+        (true, _) if whole == 0.0 => format!("{}/{}", numerator, denominator),
+        (true, _) => format!("{:.0} {}/{}", whole, numerator, denominator),
+        (false, _) => format!("{}/{}", numerator, denominator),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_numbers() {
+        assert_eq!(format_number(1234567.891, "#,##0.00", false), "1,234,567.89");
+        assert_eq!(format_number(-1234.5, "#,##0", false), "-1,235");
+        assert_eq!(format_number(0.1234, "0.0%", false), "12.3%");
+        assert_eq!(format_number(0.5, "#.##", false), ".5");
+        assert_eq!(format_number(7.0, "000", false), "007");
+        assert_eq!(format_number(123456789.0, "000-00-0000", false), "123-45-6789");
+        assert_eq!(format_number(12345.0, "0.00E+00", false), "1.23E+04");
+        assert_eq!(format_number(1500000.0, "#,##0.0,,\"M\"", false), "1.5M");
+        assert_eq!(format_number(1.25, "# ?/?", false), "1 1/4");
+        assert_eq!(format_number(0.3, "General", false), "0.3");
+        assert_eq!(format_number(3.0, "0.00;[Red]-0.00;\"zero\"", false), "3.00");
+        assert_eq!(format_number(0.0, "0.00;-0.00;\"zero\"", false), "zero");
+        assert_eq!(format_number(150.0, "[>=100]\"big\";\"small\"", false), "big");
+    }
+
+    #[test]
+    fn test_format_currency() {
+        let accounting = builtin_format_code(44).unwrap();
+        assert_eq!(format_number(1234.5, accounting, false), "$1,234.50");
+        assert_eq!(format_number(-1234.5, accounting, false), "$(1,234.50)");
+        assert_eq!(format_number(0.0, accounting, false), "$-");
+        assert_eq!(format_number(-99.0, builtin_format_code(8).unwrap(), false), "($99.00)");
+        assert_eq!(format_number(1234.5, "[$€-407]#,##0.00", false), "€1,234.50");
+        assert_eq!(format_number(-5.0, "\"$\"#,##0.00", false), "-$5.00");
+    }
+
+    #[test]
+    fn test_format_dates_and_times() {
+        assert_eq!(format_number(45122.0, "m/d/yyyy", false), "7/15/2023");
+        assert_eq!(format_number(45122.0, "yyyy-mm-dd", false), "2023-07-15");
+        assert_eq!(format_number(45122.0, "dddd, mmmm d, yyyy", false), "Saturday, July 15, 2023");
+        assert_eq!(format_number(45122.75, "d-mmm-yy h:mm AM/PM", false), "15-Jul-23 6:00 PM");
+        assert_eq!(format_number(0.5, "hh:mm:ss", false), "12:00:00");
+        assert_eq!(format_number(1.5, "[h]:mm", false), "36:00");
+        assert_eq!(format_number(0.000_011_6, "mm:ss.0", false), "00:01.0");
+        assert_eq!(format_number(43660.0, "m/d/yyyy", true), "7/15/2023");
+        assert_eq!(format_number(1.0, "yyyy-mm-dd", false), "1900-01-01");
+        assert!(is_date_format("[$-409]mmm d, yyyy"));
+        assert!(!is_date_format("#,##0.00 \"days\""));
+    }
+
+    #[test]
+    fn test_iso_dates_and_durations() {
+        assert_eq!(iso_datetime(45122.0, false).as_deref(), Some("2023-07-15"));
+        assert_eq!(iso_datetime(45122.75, false).as_deref(), Some("2023-07-15T18:00:00"));
+        assert_eq!(iso_datetime(0.5, false).as_deref(), Some("12:00:00"));
+        assert_eq!(iso_datetime(43660.0, true).as_deref(), Some("2023-07-15"));
+        assert_eq!(iso_datetime(-1.0, false), None);
+        assert_eq!(iso_duration(1.520_833_333), "PT36H30M0S");
+    }
+
+    #[test]
+    fn test_format_general() {
+        assert_eq!(format_general(42.0), "42");
+        assert_eq!(format_general(0.1 + 0.2), "0.3");
+        assert_eq!(format_general(-1234.5678), "-1234.5678");
+        assert_eq!(format_general(123456789012.0), "1.23457E+11");
+        assert_eq!(format_general(0.000_000_000_12), "1.2E-10");
+    }
+}
@@ -0,0 +1,749 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::docx_parser::{
+    escape_html, escape_link_text, extend_spans, link_destination, push_plain, read_docx_part,
+    spans_to_markdown, DocxNote, TextSpan,
+};
+use crate::markdown_table::{self, escape_markdown};
+
+/// Media types stored in the `mimetype` entry of OpenDocument packages
+const ODT_MIMETYPE: &str = "application/vnd.oasis.opendocument.text";
+const ODP_MIMETYPE: &str = "application/vnd.oasis.opendocument.presentation";
+
+/// Maximum depth followed through `style:parent-style-name` inheritance
+const MAX_STYLE_DEPTH: usize = 16;
+
+/// Upper bound for repeated rows and columns; tables may repeat empty cells many times
+const MAX_REPEAT: usize = 256;
+
+/// Text of an OpenDocument text (`.odt`) file, rendered as markdown in the same
+/// shape as DOCX output: headings, lists, tables, links and note markers
+#[derive(Debug, Clone, Default)]
+pub struct OdtDocument {
+    /// Body split on page breaks; entry `i` holds page `i + 1`
+    pub pages: Vec<String>,
+    pub headers: Vec<String>,
+    pub footers: Vec<String>,
+    pub footnotes: Vec<DocxNote>,
+    pub endnotes: Vec<DocxNote>,
+    /// Page count saved in `meta.xml` by the authoring application
+    pub stored_page_count: Option<usize>,
+}
+
+impl OdtDocument {
+    /// Parse an OpenDocument text package
+    pub fn read(buffer: &[u8]) -> Result<Self> {
+        let content = parse_xml(&read_docx_part(buffer, "content.xml")?)?;
+        let styles_xml = read_docx_part(buffer, "styles.xml").ok();
+        let styles_root = styles_xml.as_deref().map(parse_xml).transpose()?;
+
+        let mut styles = OdfStyles::default();
+        styles.collect(&content);
+        if let Some(root) = &styles_root {
+            styles.collect(root);
+        }
+
+        let body = content.find(&["body", "text"])
+            .ok_or_else(|| anyhow::anyhow!("OpenDocument file has no text body"))?;
+        let mut link_anchors = HashSet::new();
+        collect_link_anchors(body, &mut link_anchors);
+
+        let mut renderer = OdfRenderer::new(&styles, &link_anchors);
+        renderer.render_blocks(&body.children);
+        let pages = renderer.pages();
+
+        // Headers and footers live in the master pages of styles.xml
+        let mut headers: Vec<String> = Vec::new();
+        let mut footers: Vec<String> = Vec::new();
+        if let Some(master_styles) = styles_root.as_ref().and_then(|root| root.find(&["master-styles"])) {
+            for master_page in master_styles.elements().filter(|e| e.name == "master-page") {
+                for part in master_page.elements() {
+                    let target = match part.name.as_str() {
+                        "header" | "header-left" | "header-first" => &mut headers,
+                        "footer" | "footer-left" | "footer-first" => &mut footers,
+                        _ => continue,
+                    };
+                    let text = renderer.render_nested(&part.children).join("\n\n");
+                    if !text.is_empty() && !target.contains(&text) {
+                        target.push(text);
+                    }
+                }
+            }
+        }
+
+        let stored_page_count = read_docx_part(buffer, "meta.xml").ok()
+            .and_then(|xml| parse_xml(&xml).ok())
+            .and_then(|meta| {
+                meta.find(&["meta", "document-statistic"])?
+                    .attribute("page-count")?
+                    .parse().ok()
+            })
+            .filter(|&pages| pages > 0);
+
+        Ok(Self {
+            pages,
+            headers,
+            footers,
+            footnotes: renderer.footnotes,
+            endnotes: renderer.endnotes,
+            stored_page_count,
+        })
+    }
+
+    /// Number of pages split on the page breaks found; `stored_page_count`
+    /// holds the application's own count
+    pub fn page_count(&self) -> usize {
+        self.pages.len().max(1)
+    }
+}
+
+/// Render each slide (`draw:page`) of an OpenDocument presentation as markdown.
+/// Speaker notes are not included.
+pub fn read_odp_slides(buffer: &[u8]) -> Result<Vec<String>> {
+    let content = parse_xml(&read_docx_part(buffer, "content.xml")?)?;
+    let mut styles = OdfStyles::default();
+    styles.collect(&content);
+    if let Ok(xml) = read_docx_part(buffer, "styles.xml") {
+        styles.collect(&parse_xml(&xml)?);
+    }
+
+    let presentation = content.find(&["body", "presentation"])
+        .ok_or_else(|| anyhow::anyhow!("OpenDocument file has no presentation body"))?;
+    let link_anchors = HashSet::new();
+    let mut renderer = OdfRenderer::new(&styles, &link_anchors);
+
+    Ok(presentation.elements()
+        .filter(|e| e.name == "page")
+        .map(|page| renderer.render_nested(&page.children).join("\n\n"))
+        .collect())
+}
+
+/// The media type of an OpenDocument package, from its `mimetype` entry
+pub fn odf_mimetype(buffer: &[u8]) -> Option<String> {
+    read_docx_part(buffer, "mimetype").ok().map(|mimetype| mimetype.trim().to_string())
+}
+
+/// Whether a buffer holds an OpenDocument text package
+pub fn is_odf_text(buffer: &[u8]) -> bool {
+    odf_mimetype(buffer).as_deref() == Some(ODT_MIMETYPE)
+}
+
+/// Whether a buffer holds an OpenDocument presentation package
+pub fn is_odf_presentation(buffer: &[u8]) -> bool {
+    odf_mimetype(buffer).as_deref() == Some(ODP_MIMETYPE)
+}
+
+/// An XML element, keyed by local names; ODF parts are walked as a tree
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<Node>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    fn from_start(start: &BytesStart) -> Self {
+        let attributes = start.attributes().flatten()
+            .map(|attribute| {
+                let name = String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+                let value = attribute.unescape_value()
+                    .map(|value| value.into_owned())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&attribute.value).into_owned());
+                (name, value)
+            })
+            .collect();
+        Self {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+            attributes,
+            children: Vec::new(),
+        }
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// Follow a path of child element names
+    fn find(&self, path: &[&str]) -> Option<&Element> {
+        path.iter().try_fold(self, |element, name| element.elements().find(|e| e.name == *name))
+    }
+}
+
+/// Parse an XML part into its root element
+fn parse_xml(xml: &str) -> Result<Element> {
+    let mut reader = Reader::from_str(xml);
+    let mut stack = vec![Element::default()];
+
+    fn push_text(stack: &mut [Element], text: &str) {
+        let parent = stack.last_mut().expect("document node");
+        match parent.children.last_mut() {
+            Some(Node::Text(previous)) => previous.push_str(text),
+            _ => parent.children.push(Node::Text(text.to_string())),
+        }
+    }
+
+    loop {
+        let event = reader.read_event()
+            .map_err(|e| anyhow::anyhow!("Failed to parse OpenDocument XML: {}", e))?;
+        match event {
+            Event::Start(start) => stack.push(Element::from_start(&start)),
+            Event::Empty(start) => {
+                let element = Element::from_start(&start);
+                stack.last_mut().expect("document node").children.push(Node::Element(element));
+            }
+            Event::End(_) if stack.len() > 1 => {
+                let element = stack.pop().expect("open element");
+                stack.last_mut().expect("document node").children.push(Node::Element(element));
+            }
+            Event::Text(text) => push_text(&mut stack, &text.decode()?),
+            Event::CData(text) => push_text(&mut stack, &text.decode()?),
+            Event::GeneralRef(reference) => {
+                if let Some(ch) = reference.resolve_char_ref()? {
+                    push_text(&mut stack, ch.encode_utf8(&mut [0; 4]));
+                } else if let Some(text) = quick_xml::escape::resolve_predefined_entity(&reference.decode()?) {
+                    push_text(&mut stack, text);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    stack.swap_remove(0).children.into_iter()
+        .find_map(|child| match child {
+            Node::Element(root) => Some(root),
+            Node::Text(_) => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("OpenDocument XML part is empty"))
+}
+
+/// Properties of a named style that affect the markdown output
+#[derive(Debug, Clone, Default)]
+struct OdfStyle {
+    parent: Option<String>,
+    break_before: Option<bool>,
+    break_after: Option<bool>,
+    bold: Option<bool>,
+    italic: Option<bool>,
+}
+
+/// Styles from `content.xml` and `styles.xml`
+#[derive(Debug, Default)]
+struct OdfStyles {
+    styles: HashMap<String, OdfStyle>,
+    /// For each list style, whether each level (from 0) is numbered
+    list_styles: HashMap<String, Vec<bool>>,
+}
+
+impl OdfStyles {
+    fn collect(&mut self, element: &Element) {
+        for child in element.elements() {
+            match (child.name.as_str(), child.attribute("name")) {
+                ("style", Some(name)) => {
+                    let mut style = OdfStyle {
+                        parent: child.attribute("parent-style-name").map(str::to_string),
+                        ..OdfStyle::default()
+                    };
+                    for properties in child.elements() {
+                        if let Some(value) = properties.attribute("break-before") {
+                            style.break_before = Some(value == "page");
+                        }
+                        if let Some(value) = properties.attribute("break-after") {
+                            style.break_after = Some(value == "page");
+                        }
+                        if let Some(weight) = properties.attribute("font-weight") {
+                            style.bold = Some(weight == "bold" || weight.parse::<u32>().is_ok_and(|w| w >= 600));
+                        }
+                        if let Some(font_style) = properties.attribute("font-style") {
+                            style.italic = Some(font_style == "italic" || font_style == "oblique");
+                        }
+                    }
+                    self.styles.entry(name.to_string()).or_insert(style);
+                }
+                ("list-style", Some(name)) => {
+                    let mut levels = Vec::new();
+                    for level_style in child.elements() {
+                        let level: usize = level_style.attribute("level").and_then(|l| l.parse().ok()).unwrap_or(1);
+                        if levels.len() < level {
+                            levels.resize(level, false);
+                        }
+                        levels[level.max(1) - 1] = level_style.name == "list-level-style-number";
+                    }
+                    self.list_styles.entry(name.to_string()).or_insert(levels);
+                }
+                _ => self.collect(child),
+            }
+        }
+    }
+
+    /// Look up a property through the style's parents
+    fn resolve<T>(&self, name: Option<&str>, property: impl Fn(&OdfStyle) -> Option<T>) -> Option<T> {
+        let mut current = name;
+        for _ in 0..MAX_STYLE_DEPTH {
+            let style = self.styles.get(current?)?;
+            if let Some(value) = property(style) {
+                return Some(value);
+            }
+            current = style.parent.as_deref();
+        }
+        None
+    }
+
+    fn is_ordered_list(&self, name: Option<&str>, level: usize) -> bool {
+        name.and_then(|name| self.list_styles.get(name))
+            .and_then(|levels| levels.get(level))
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+/// Bookmark names targeted by internal links (`xlink:href="#name"`)
+fn collect_link_anchors(element: &Element, anchors: &mut HashSet<String>) {
+    for child in element.elements() {
+        if child.name == "a"
+            && let Some(anchor) = child.attribute("href").and_then(|href| href.strip_prefix('#'))
+        {
+            anchors.insert(anchor.to_string());
+        }
+        collect_link_anchors(child, anchors);
+    }
+}
+
+/// Walks ODF text content and renders it as markdown blocks
+struct OdfRenderer<'a> {
+    styles: &'a OdfStyles,
+    link_anchors: &'a HashSet<String>,
+    blocks: Vec<String>,
+    /// Page number of each entry in `blocks`
+    block_pages: Vec<usize>,
+    current_page: usize,
+    /// Page breaks found inside the current paragraph, applied once it ends
+    pending_breaks: usize,
+    /// Level of the list item rendered last, if the previous block was a list item
+    last_list_level: Option<usize>,
+    /// Blocks anchored in the current paragraph, such as text boxes
+    anchored_blocks: Vec<String>,
+    footnotes: Vec<DocxNote>,
+    endnotes: Vec<DocxNote>,
+}
+
+impl<'a> OdfRenderer<'a> {
+    fn new(styles: &'a OdfStyles, link_anchors: &'a HashSet<String>) -> Self {
+        Self {
+            styles,
+            link_anchors,
+            blocks: Vec::new(),
+            block_pages: Vec::new(),
+            current_page: 1,
+            pending_breaks: 0,
+            last_list_level: None,
+            anchored_blocks: Vec::new(),
+            footnotes: Vec::new(),
+            endnotes: Vec::new(),
+        }
+    }
+
+    /// Blocks grouped by page; entry `i` holds page `i + 1`
+    fn pages(&self) -> Vec<String> {
+        let page_count = self.block_pages.iter().copied().max().unwrap_or(1).max(self.current_page);
+        let mut pages = vec![Vec::new(); page_count];
+        for (block, page) in self.blocks.iter().zip(&self.block_pages) {
+            pages[page - 1].push(block.as_str());
+        }
+        pages.into_iter().map(|blocks| blocks.join("\n\n")).collect()
+    }
+
+    fn render_blocks(&mut self, children: &[Node]) {
+        for child in children {
+            let Node::Element(element) = child else { continue };
+            match element.name.as_str() {
+                "p" => self.render_paragraph(element, None),
+                "h" => {
+                    let level = element.attribute("outline-level").and_then(|l| l.parse().ok()).unwrap_or(1);
+                    self.render_paragraph(element, Some(level));
+                }
+                "list" => self.render_list(element, 0, None),
+                "table" => self.render_table(element),
+                "soft-page-break" => self.current_page += 1,
+                // Speaker notes, comments, change tracking and index templates
+                "notes" | "annotation" | "tracked-changes" | "forms" => {}
+                name if name.ends_with("-source") || name.ends_with("-decls") => {}
+                _ => self.render_blocks(&element.children),
+            }
+        }
+    }
+
+    /// Render content into a separate list of blocks, as for table cells and notes
+    fn render_nested(&mut self, children: &[Node]) -> Vec<String> {
+        let outer_blocks = std::mem::take(&mut self.blocks);
+        let outer_pages = std::mem::take(&mut self.block_pages);
+        let outer_list_level = self.last_list_level.take();
+
+        self.render_blocks(children);
+
+        let blocks = std::mem::replace(&mut self.blocks, outer_blocks);
+        self.block_pages = outer_pages;
+        self.last_list_level = outer_list_level;
+        blocks
+    }
+
+    fn render_paragraph(&mut self, paragraph: &Element, heading_level: Option<usize>) {
+        let style = paragraph.attribute("style-name");
+        let break_before = self.styles.resolve(style, |s| s.break_before).unwrap_or(false);
+        if break_before && !self.block_pages.is_empty() {
+            self.current_page += 1;
+        }
+
+        let bold = self.styles.resolve(style, |s| s.bold).unwrap_or(false);
+        let italic = self.styles.resolve(style, |s| s.italic).unwrap_or(false);
+        let mut spans = Vec::new();
+        self.collect_inline(&paragraph.children, bold, italic, &mut spans);
+        let text = spans_to_markdown(&spans);
+
+        match heading_level {
+            Some(level) if !text.is_empty() => {
+                let title = text.replace("  \n", " ").replace('\n', " ");
+                self.push_block(format!("{} {}", "#".repeat(level.clamp(1, 6)), title));
+            }
+            _ => self.push_block(text),
+        }
+        for block in std::mem::take(&mut self.anchored_blocks) {
+            self.push_block(block);
+        }
+
+        self.current_page += std::mem::take(&mut self.pending_breaks);
+        if self.styles.resolve(style, |s| s.break_after).unwrap_or(false) {
+            self.current_page += 1;
+        }
+    }
+
+    fn collect_inline(&mut self, children: &[Node], bold: bool, italic: bool, spans: &mut Vec<TextSpan>) {
+        for child in children {
+            let element = match child {
+                Node::Text(text) => {
+                    // Runs of white space collapse to a single space
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    let padded = match (text.is_empty(), child_starts_with_space(child), child_ends_with_space(child)) {
+                        (true, true, _) | (true, _, true) => " ".to_string(),
+                        (true, false, false) => continue,
+                        (false, leading, trailing) => format!(
+                            "{}{}{}",
+                            if leading { " " } else { "" },
+                            text,
+                            if trailing { " " } else { "" }
+                        ),
+                    };
+                    extend_spans(spans, vec![TextSpan { text: padded, bold, italic }]);
+                    continue;
+                }
+                Node::Element(element) => element,
+            };
+
+            match element.name.as_str() {
+                "span" => {
+                    let style = element.attribute("style-name");
+                    let bold = self.styles.resolve(style, |s| s.bold).unwrap_or(bold);
+                    let italic = self.styles.resolve(style, |s| s.italic).unwrap_or(italic);
+                    self.collect_inline(&element.children, bold, italic, spans);
+                }
+                "a" => {
+                    let mut text_spans = Vec::new();
+                    self.collect_inline(&element.children, bold, italic, &mut text_spans);
+                    let text = spans_to_markdown(&text_spans).replace("  \n", " ").replace('\n', " ");
+                    match element.attribute("href") {
+                        Some(href) if !text.is_empty() => {
+                            push_plain(spans, &format!("[{}]({})", escape_link_text(&text), link_destination(href)));
+                        }
+                        _ => extend_spans(spans, text_spans),
+                    }
+                }
+                "s" => {
+                    let count = element.attribute("c").and_then(|c| c.parse().ok()).unwrap_or(1usize);
+                    extend_spans(spans, vec![TextSpan { text: " ".repeat(count.min(MAX_REPEAT)), bold, italic }]);
+                }
+                "tab" => extend_spans(spans, vec![TextSpan { text: "\t".to_string(), bold, italic }]),
+                "line-break" => extend_spans(spans, vec![TextSpan { text: "\n".to_string(), bold, italic }]),
+                "note" => self.collect_note(element, spans),
+                "bookmark" | "bookmark-start" => {
+                    if let Some(name) = element.attribute("name").filter(|name| self.link_anchors.contains(*name)) {
+                        push_plain(spans, &format!("<a id=\"{}\"></a>", escape_html(name)));
+                    }
+                }
+                "soft-page-break" => self.pending_breaks += 1,
+                "frame" => {
+                    let blocks = self.render_nested(&element.children);
+                    self.anchored_blocks.extend(blocks);
+                }
+                "annotation" | "note-citation" => {}
+                _ => self.collect_inline(&element.children, bold, italic, spans),
+            }
+        }
+    }
+
+    /// Footnotes become `[^1]`, `[^2]`… and endnotes `[^e1]`…, as in DOCX output
+    fn collect_note(&mut self, note: &Element, spans: &mut Vec<TextSpan>) {
+        let is_endnote = note.attribute("note-class") == Some("endnote");
+        let label = if is_endnote {
+            format!("e{}", self.endnotes.len() + 1)
+        } else {
+            (self.footnotes.len() + 1).to_string()
+        };
+        push_plain(spans, &format!("[^{}]", label));
+
+        // Reserve the label before rendering, in case the note holds further notes
+        let notes = if is_endnote { &mut self.endnotes } else { &mut self.footnotes };
+        let index = notes.len();
+        notes.push(DocxNote { label, text: String::new() });
+
+        let text = note.find(&["note-body"])
+            .map(|body| self.render_nested(&body.children).join("\n\n"))
+            .unwrap_or_default();
+        let notes = if is_endnote { &mut self.endnotes } else { &mut self.footnotes };
+        notes[index].text = text;
+    }
+
+    fn render_list(&mut self, list: &Element, level: usize, inherited_style: Option<&str>) {
+        let style = list.attribute("style-name").or(inherited_style);
+        let ordered = self.styles.is_ordered_list(style, level);
+        let mut number = 1;
+
+        for item in list.elements().filter(|e| e.name == "list-item" || e.name == "list-header") {
+            let mut paragraphs = Vec::new();
+            let mut nested = Vec::new();
+            for child in item.elements() {
+                match child.name.as_str() {
+                    "p" | "h" => {
+                        let bold = self.styles.resolve(child.attribute("style-name"), |s| s.bold).unwrap_or(false);
+                        let italic = self.styles.resolve(child.attribute("style-name"), |s| s.italic).unwrap_or(false);
+                        let mut spans = Vec::new();
+                        self.collect_inline(&child.children, bold, italic, &mut spans);
+                        let text = spans_to_markdown(&spans);
+                        if !text.is_empty() {
+                            paragraphs.push(text);
+                        }
+                    }
+                    "list" => nested.push(child),
+                    _ => {}
+                }
+            }
+
+            if !paragraphs.is_empty() {
+                self.push_list_item(level, ordered, number, &paragraphs.join("  \n"));
+            }
+            for block in std::mem::take(&mut self.anchored_blocks) {
+                self.push_block(block);
+            }
+            self.current_page += std::mem::take(&mut self.pending_breaks);
+            for nested_list in nested {
+                self.render_list(nested_list, level + 1, style);
+            }
+            if item.name == "list-item" {
+                number += 1;
+            }
+        }
+    }
+
+    fn render_table(&mut self, table: &Element) {
+        let mut rows = Vec::new();
+        self.collect_table_rows(table, &mut rows);
+
+        // Repeated cells leave trailing empty rows and columns, which the table renderer drops
+        let markdown = markdown_table::render_table(rows);
+        if !markdown.is_empty() {
+            self.push_block(markdown);
+        }
+    }
+
+    fn collect_table_rows(&mut self, element: &Element, rows: &mut Vec<Vec<String>>) {
+        for child in element.elements() {
+            match child.name.as_str() {
+                "table-row" => {
+                    let mut cells = Vec::new();
+                    for cell in child.elements() {
+                        let text = match cell.name.as_str() {
+                            "table-cell" => escape_markdown(&self.render_nested(&cell.children).join("\n")),
+                            // Cells hidden by a merge are left empty
+                            "covered-table-cell" => String::new(),
+                            _ => continue,
+                        };
+                        let repeat = repeat_count(cell, "number-columns-repeated");
+                        cells.extend(std::iter::repeat_n(text, repeat));
+                    }
+                    let repeat = repeat_count(child, "number-rows-repeated");
+                    rows.extend(std::iter::repeat_n(cells, repeat));
+                }
+                "table-header-rows" | "table-rows" | "table-row-group" => self.collect_table_rows(child, rows),
+                _ => {}
+            }
+        }
+    }
+
+    fn push_block(&mut self, block: String) {
+        if !block.trim().is_empty() {
+            self.blocks.push(block);
+            self.block_pages.push(self.current_page);
+            self.last_list_level = None;
+        }
+    }
+
+    fn push_list_item(&mut self, level: usize, ordered: bool, number: usize, text: &str) {
+        // Markdown cannot skip nesting levels, so clamp to one below the previous item
+        let level = match self.last_list_level {
+            Some(previous) => level.min(previous + 1),
+            None => 0,
+        };
+
+        let indent = "    ".repeat(level);
+        let marker = if ordered { format!("{}.", number) } else { "-".to_string() };
+        let continuation = format!("\n{}{}", indent, " ".repeat(marker.len() + 1));
+        let item = format!("{}{} {}", indent, marker, text.replace('\n', &continuation));
+
+        // Lists continuing on a new page start a new block so pages can be split cleanly
+        let same_page = self.block_pages.last() == Some(&self.current_page);
+        match (self.last_list_level, self.blocks.last_mut()) {
+            (Some(_), Some(list)) if same_page => {
+                list.push('\n');
+                list.push_str(&item);
+            }
+            _ => {
+                self.blocks.push(item);
+                self.block_pages.push(self.current_page);
+            }
+        }
+        self.last_list_level = Some(level);
+    }
+}
+
+fn repeat_count(element: &Element, attribute: &str) -> usize {
+    element.attribute(attribute)
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(1usize)
+        .clamp(1, MAX_REPEAT)
+}
+
+fn child_starts_with_space(node: &Node) -> bool {
+    matches!(node, Node::Text(text) if text.starts_with(char::is_whitespace))
+}
+
+fn child_ends_with_space(node: &Node) -> bool {
+    matches!(node, Node::Text(text) if text.ends_with(char::is_whitespace))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    /// Build an OpenDocument package from its parts, `mimetype` first and uncompressed
+    pub(crate) fn odf_package(mimetype: &str, parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let stored = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        writer.start_file("mimetype", stored).unwrap();
+        writer.write_all(mimetype.as_bytes()).unwrap();
+        for (name, contents) in parts {
+            writer.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    const NAMESPACES: &str = r#"xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:draw="urn:oasis:names:tc:opendocument:xmlns:drawing:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:meta="urn:oasis:names:tc:opendocument:xmlns:meta:1.0" xmlns:presentation="urn:oasis:names:tc:opendocument:xmlns:presentation:1.0""#;
+
+    #[test]
+    fn test_odt_document_structure_and_pages() {
+        let content = format!(
+            r##"<office:document-content {NAMESPACES}>
+            <office:automatic-styles>
+                <style:style style:name="P1" style:family="paragraph"><style:paragraph-properties fo:break-before="page"/></style:style>
+                <style:style style:name="T1" style:family="text"><style:text-properties fo:font-weight="bold"/></style:style>
+                <text:list-style style:name="L1"><text:list-level-style-number text:level="1"/><text:list-level-style-bullet text:level="2"/></text:list-style>
+            </office:automatic-styles>
+            <office:body><office:text>
+                <text:sequence-decls><text:sequence-decl text:name="Table"/></text:sequence-decls>
+                <text:h text:outline-level="2"><text:bookmark text:name="scope"/>Scope</text:h>
+                <text:p>Read the <text:span text:style-name="T1">terms</text:span>  at <text:a xlink:href="https://example.com/?a=1&amp;b=2">example</text:a>,<text:s text:c="2"/>see <text:a xlink:href="#scope">Scope</text:a>.<text:note text:note-class="footnote"><text:note-citation>1</text:note-citation><text:note-body><text:p>Binding.</text:p></text:note-body></text:note></text:p>
+                <text:list text:style-name="L1">
+                    <text:list-item><text:p>First</text:p>
+                        <text:list><text:list-item><text:p>Detail</text:p></text:list-item></text:list>
+                    </text:list-item>
+                    <text:list-item><text:p>Second</text:p></text:list-item>
+                </text:list>
+                <table:table>
+                    <table:table-header-rows><table:table-row><table:table-cell table:number-columns-spanned="2"><text:p>Item | Cost</text:p></table:table-cell><table:covered-table-cell/><table:table-cell table:number-columns-repeated="3"/></table:table-row></table:table-header-rows>
+                    <table:table-row><table:table-cell><text:p>Pen</text:p></table:table-cell><table:table-cell><text:p>2</text:p><text:p>each</text:p></table:table-cell></table:table-row>
+                    <table:table-row table:number-rows-repeated="5"><table:table-cell/></table:table-row>
+                </table:table>
+                <text:p text:style-name="P1">Annex<text:soft-page-break/> continues</text:p>
+                <text:p>Last page</text:p>
+            </office:text></office:body></office:document-content>"##
+        );
+        let styles = format!(
+            r#"<office:document-styles {NAMESPACES}><office:master-styles><style:master-page style:name="Standard">
+                <style:header><text:p>Confidential</text:p></style:header>
+                <style:footer><text:p>Page footer</text:p></style:footer>
+            </style:master-page></office:master-styles></office:document-styles>"#
+        );
+        let meta = format!(
+            r#"<office:document-meta {NAMESPACES}><office:meta><meta:document-statistic meta:page-count="4"/></office:meta></office:document-meta>"#
+        );
+        let buffer = odf_package(ODT_MIMETYPE, &[("content.xml", &content), ("styles.xml", &styles), ("meta.xml", &meta)]);
+        assert!(is_odf_text(&buffer));
+        assert!(!is_odf_presentation(&buffer));
+
+        let document = OdtDocument::read(&buffer).unwrap();
+        assert_eq!(
+            document.pages,
+            vec![
+                "## <a id=\"scope\"></a>Scope\n\n\
+                 Read the **terms** at [example](https://example.com/?a=1&b=2),  see [Scope](#scope).[^1]\n\n\
+                 1. First\n    - Detail\n2. Second\n\n\
+                 | Item \\| Cost |  |\n| --- | --- |\n| Pen | 2<br>each |"
+                    .to_string(),
+                "Annex continues".to_string(),
+                "Last page".to_string(),
+            ]
+        );
+        assert_eq!(document.page_count(), 3);
+        assert_eq!(document.stored_page_count, Some(4));
+        assert_eq!(document.headers, vec!["Confidential".to_string()]);
+        assert_eq!(document.footers, vec!["Page footer".to_string()]);
+        assert_eq!(document.footnotes, vec![DocxNote { label: "1".to_string(), text: "Binding.".to_string() }]);
+    }
+
+    #[test]
+    fn test_read_odp_slides() {
+        let content = format!(
+            r#"<office:document-content {NAMESPACES}><office:body><office:presentation>
+                <draw:page draw:name="page1">
+                    <draw:frame presentation:class="title"><draw:text-box><text:p>Roadmap</text:p></draw:text-box></draw:frame>
+                    <draw:frame><draw:text-box><text:list><text:list-item><text:p>Ship 2.0</text:p></text:list-item></text:list></draw:text-box></draw:frame>
+                    <presentation:notes><draw:frame><draw:text-box><text:p>Speaker notes</text:p></draw:text-box></draw:frame></presentation:notes>
+                </draw:page>
+                <draw:page draw:name="page2"/>
+                <draw:page draw:name="page3"><draw:custom-shape><text:p>Questions?</text:p></draw:custom-shape></draw:page>
+            </office:presentation></office:body></office:document-content>"#
+        );
+        let buffer = odf_package(ODP_MIMETYPE, &[("content.xml", &content)]);
+        assert!(is_odf_presentation(&buffer));
+
+        let slides = read_odp_slides(&buffer).unwrap();
+        assert_eq!(slides, vec!["Roadmap\n\n- Ship 2.0".to_string(), String::new(), "Questions?".to_string()]);
+    }
+}
@@ -0,0 +1,524 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use lopdf::{Dictionary, Document, Object, ObjectId, Permissions};
+use quick_xml::events::{BytesStart, Event};
+
+use crate::fast_pdf_extractor::LopdfExtractor;
+
+/// Most distinct page sizes listed before the rest are summarized
+const MAX_PAGE_SIZES: usize = 10;
+
+/// Named paper sizes in points, portrait, matched within `PAPER_TOLERANCE`
+const PAPER_SIZES: [(&str, f32, f32); 8] = [
+    ("Letter", 612.0, 792.0),
+    ("Legal", 612.0, 1008.0),
+    ("Tabloid", 792.0, 1224.0),
+    ("A3", 841.9, 1190.6),
+    ("A4", 595.3, 841.9),
+    ("A5", 419.5, 595.3),
+    ("B5", 498.9, 708.7),
+    ("Executive", 522.0, 756.0),
+];
+const PAPER_TOLERANCE: f32 = 2.0;
+
+/// Document properties of a PDF, in the order they are listed
+const PROPERTIES: [(&str, &str); 8] = [
+    ("Title", "Title"),
+    ("Author", "Author"),
+    ("Subject", "Subject"),
+    ("Keywords", "Keywords"),
+    ("Creator", "Creator"),
+    ("Producer", "Producer"),
+    ("CreationDate", "Created"),
+    ("ModDate", "Modified"),
+];
+
+/// Metadata of a PDF: its document properties from the Info dictionary, with
+/// XMP metadata filling the gaps, and facts about the file and its pages
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PdfMetadata {
+    /// Properties by label (Title, Author, ..., Created, Modified)
+    pub properties: Vec<(&'static str, String)>,
+    pub version: String,
+    /// Encryption method and restrictions, `None` when the file is not encrypted
+    pub encryption: Option<String>,
+    pub tagged: bool,
+    /// Conformance claimed in the XMP metadata, such as `PDF/A-2b`
+    pub pdfa: Option<String>,
+    /// Distinct page sizes as displayed (crop box, rotation applied), with their pages
+    pub page_sizes: Vec<((f32, f32), Vec<usize>)>,
+}
+
+impl PdfMetadata {
+    /// Read the metadata of a PDF file
+    pub fn read(file_path: &str) -> Result<Self> {
+        let document = LopdfExtractor::load(file_path)?;
+        Ok(Self::from_document(&document))
+    }
+
+    pub fn from_document(document: &Document) -> Self {
+        let catalog = document.catalog().ok();
+        let version = catalog
+            .and_then(|catalog| catalog.get(b"Version").and_then(Object::as_name).ok())
+            .map(|version| String::from_utf8_lossy(version).into_owned())
+            // The catalog may raise the version of an incrementally updated file
+            .filter(|version| version_number(version) > version_number(&document.version))
+            .unwrap_or_else(|| document.version.clone());
+        let tagged = catalog
+            .and_then(|catalog| resolve(document, catalog, b"MarkInfo"))
+            .and_then(|mark_info| mark_info.as_dict().ok())
+            .and_then(|mark_info| mark_info.get(b"Marked").and_then(Object::as_bool).ok())
+            .unwrap_or(false);
+
+        let mut metadata = Self {
+            version,
+            encryption: encryption(document),
+            tagged,
+            page_sizes: page_sizes(document),
+            ..Default::default()
+        };
+        // The strings and streams of a file that could not be decrypted are unreadable
+        if document.is_encrypted() {
+            return metadata;
+        }
+
+        let xmp = catalog
+            .and_then(|catalog| resolve(document, catalog, b"Metadata"))
+            .and_then(|metadata| metadata.as_stream().ok())
+            .and_then(|stream| stream.get_plain_content().ok().or_else(|| Some(stream.content.clone())))
+            .and_then(|content| read_xmp(&String::from_utf8_lossy(&content)).ok())
+            .unwrap_or_default();
+        let info = document.trailer.get(b"Info").ok()
+            .and_then(|info| document.dereference(info).ok())
+            .and_then(|(_, info)| info.as_dict().ok());
+        for (key, label) in PROPERTIES {
+            let value = info
+                .and_then(|info| resolve(document, info, key.as_bytes()))
+                .and_then(|value| lopdf::decode_text_string(value).ok())
+                .map(|value| value.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|value| !value.is_empty())
+                .map(|value| if key.ends_with("Date") { pdf_date(&value) } else { value })
+                .or_else(|| xmp_property(&xmp, key));
+            if let Some(value) = value {
+                metadata.properties.push((label, value));
+            }
+        }
+        metadata.pdfa = xmp.get("part").and_then(|part| part.first()).map(|part| {
+            let conformance = xmp.get("conformance").and_then(|value| value.first()).map(|value| value.to_lowercase());
+            format!("PDF/A-{}{}", part, conformance.unwrap_or_default())
+        });
+        metadata
+    }
+
+    /// Plain-text report, one fact per line, as shown by `get_document_page_info`
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if self.properties.is_empty() {
+            text.push_str("Document properties: none\n");
+        } else {
+            text.push_str("Document properties:\n");
+            for (label, value) in &self.properties {
+                text.push_str(&format!("  {}: {}\n", label, value));
+            }
+        }
+        text.push_str(&format!("PDF version: {}\n", self.version));
+        text.push_str(&format!("Encrypted: {}\n", self.encryption.as_deref().unwrap_or("no")));
+        text.push_str(&format!("Tagged: {}\n", if self.tagged { "yes" } else { "no" }));
+        text.push_str(&format!("PDF/A: {}\n", self.pdfa.as_deref().unwrap_or("no")));
+
+        text.push_str("Page sizes:\n");
+        for ((width, height), pages) in self.page_sizes.iter().take(MAX_PAGE_SIZES) {
+            text.push_str(&format!("  {}: {}\n", page_list(pages), describe_size(*width, *height)));
+        }
+        if self.page_sizes.len() > MAX_PAGE_SIZES {
+            let other_pages: usize = self.page_sizes[MAX_PAGE_SIZES..].iter().map(|(_, pages)| pages.len()).sum();
+            text.push_str(&format!(
+                "  ... {} more sizes on {} pages\n",
+                self.page_sizes.len() - MAX_PAGE_SIZES,
+                other_pages,
+            ));
+        }
+        text.trim_end().to_string()
+    }
+}
+
+/// Look up a dictionary entry, following a reference
+fn resolve<'a>(document: &'a Document, dictionary: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+    let object = dictionary.get(key).ok()?;
+    document.dereference(object).ok().map(|(_, object)| object)
+}
+
+fn version_number(version: &str) -> f32 {
+    version.trim().parse().unwrap_or(0.0)
+}
+
+/// Encryption method, and whether the file opened without a password or
+/// which restrictions apply when it did
+fn encryption(document: &Document) -> Option<String> {
+    if let Ok(dictionary) = document.get_encrypted() {
+        let integer = |key: &[u8]| dictionary.get(key).and_then(Object::as_i64).ok();
+        let stream_filter = dictionary.get(b"StmF").and_then(Object::as_name).ok().and_then(|name| {
+            dictionary.get(b"CF").and_then(Object::as_dict).ok()?
+                .get(name).and_then(Object::as_dict).ok()?
+                .get(b"CFM").and_then(Object::as_name).ok()
+        });
+        let method = encryption_method(integer(b"V").unwrap_or(0), integer(b"Length"), stream_filter);
+        return Some(format!("yes ({}, password required to read the contents)", method));
+    }
+
+    let state = document.encryption_state.as_ref()?;
+    let stream_filter = state.crypt_filters().get(state.default_stream_filter()).map(|filter| filter.method());
+    let method = encryption_method(state.version(), state.key_length().map(|length| length as i64), stream_filter);
+    let permissions = state.permissions();
+    let restrictions: Vec<&str> = [
+        (Permissions::PRINTABLE, "printing"),
+        (Permissions::MODIFIABLE, "editing"),
+        (Permissions::COPYABLE, "copying"),
+        (Permissions::ANNOTABLE, "annotating"),
+    ]
+    .into_iter()
+    .filter(|(permission, _)| !permissions.contains(*permission))
+    .map(|(_, action)| action)
+    .collect();
+    Some(match restrictions.is_empty() {
+        true => format!("yes ({}, opened without a password)", method),
+        false => format!("yes ({}, opened without a password; {} not allowed)", method, restrictions.join(", ")),
+    })
+}
+
+/// Algorithm of a standard security handler from its version (`V`), key
+/// length (`Length`, in bits or bytes) and crypt filter method
+fn encryption_method(version: i64, length: Option<i64>, stream_filter: Option<&[u8]>) -> String {
+    match (version, stream_filter) {
+        (5, _) | (_, Some(b"AESV3")) => "AES-256".to_string(),
+        (4, Some(b"AESV2")) => "AES-128".to_string(),
+        (1, _) => "RC4 40-bit".to_string(),
+        (2..=4, _) => {
+            let bits = length.map(|length| if length <= 16 { length * 8 } else { length }).unwrap_or(40);
+            format!("RC4 {}-bit", bits)
+        }
+        _ => format!("security handler version {}", version),
+    }
+}
+
+/// An inheritable page attribute, from the page or the nearest ancestor that has it
+fn inherited<'a>(document: &'a Document, page_id: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut node = document.get_dictionary(page_id).ok()?;
+    for _ in 0..32 {
+        if let Some(value) = resolve(document, node, key) {
+            return Some(value);
+        }
+        node = resolve(document, node, b"Parent")?.as_dict().ok()?;
+    }
+    None
+}
+
+/// Distinct displayed page sizes in points, in order of first use
+fn page_sizes(document: &Document) -> Vec<((f32, f32), Vec<usize>)> {
+    let mut sizes: Vec<((f32, f32), Vec<usize>)> = Vec::new();
+    for (number, page_id) in document.get_pages() {
+        let rectangle = |key: &[u8]| -> Option<(f32, f32)> {
+            let values: Vec<f32> = inherited(document, page_id, key)?.as_array().ok()?
+                .iter()
+                .filter_map(|value| document.dereference(value).ok()?.1.as_float().ok())
+                .collect();
+            match values.as_slice() {
+                [left, bottom, right, top] => Some(((right - left).abs(), (top - bottom).abs())),
+                _ => None,
+            }
+        };
+        // US Letter is the default media box of viewers for pages without one
+        let (mut width, mut height) = rectangle(b"CropBox").or_else(|| rectangle(b"MediaBox")).unwrap_or((612.0, 792.0));
+        let rotation = inherited(document, page_id, b"Rotate").and_then(|rotate| rotate.as_i64().ok()).unwrap_or(0);
+        if rotation.rem_euclid(180) == 90 {
+            std::mem::swap(&mut width, &mut height);
+        }
+
+        let size = ((width * 10.0).round() / 10.0, (height * 10.0).round() / 10.0);
+        match sizes.iter_mut().find(|(known, _)| *known == size) {
+            Some((_, pages)) => pages.push(number as usize),
+            None => sizes.push((size, vec![number as usize])),
+        }
+    }
+    sizes
+}
+
+fn describe_size(width: f32, height: f32) -> String {
+    let (short, long) = (width.min(height), width.max(height));
+    let paper = PAPER_SIZES.iter()
+        .find(|(_, paper_width, paper_height)| {
+            (short - paper_width).abs() <= PAPER_TOLERANCE && (long - paper_height).abs() <= PAPER_TOLERANCE
+        })
+        .map(|(name, _, _)| format!("{}, ", name))
+        .unwrap_or_default();
+    let orientation = match width.partial_cmp(&height) {
+        Some(std::cmp::Ordering::Greater) => "landscape",
+        Some(std::cmp::Ordering::Less) => "portrait",
+        _ => "square",
+    };
+    format!("{} x {} pt ({}{})", width, height, paper, orientation)
+}
+
+/// Page numbers with consecutive runs collapsed, such as `1-3, 5`
+fn page_list(pages: &[usize]) -> String {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &page in pages {
+        match runs.last_mut() {
+            Some((_, last)) if *last + 1 == page => *last = page,
+            _ => runs.push((page, page)),
+        }
+    }
+    runs.iter()
+        .map(|&(first, last)| if first == last { first.to_string() } else { format!("{}-{}", first, last) })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A PDF date (`D:YYYYMMDDHHmmSSOHH'mm'`, all but the year optional) as ISO 8601;
+/// text that is not a PDF date is returned as it is
+fn pdf_date(value: &str) -> String {
+    let text = value.trim().trim_start_matches("D:");
+    let digits: String = text.chars().take_while(char::is_ascii_digit).collect();
+    if digits.len() < 4 {
+        return value.to_string();
+    }
+    let field = |start: usize| digits.get(start..start + 2);
+    let mut date = digits[..4].to_string();
+    for (start, separator) in [(4, '-'), (6, '-')] {
+        match field(start) {
+            Some(part) => date.push_str(&format!("{}{}", separator, part)),
+            None => return date,
+        }
+    }
+    let Some(hour) = field(8) else {
+        return date;
+    };
+    date.push_str(&format!("T{}:{}:{}", hour, field(10).unwrap_or("00"), field(12).unwrap_or("00")));
+
+    let zone = &text[digits.len()..];
+    match zone.chars().next() {
+        Some('Z') => date.push('Z'),
+        Some(sign @ ('+' | '-')) => {
+            let zone_digits: String = zone.chars().filter(char::is_ascii_digit).collect();
+            if let Some(hours) = zone_digits.get(..2) {
+                date.push_str(&format!("{}{}:{}", sign, hours, zone_digits.get(2..4).unwrap_or("00")));
+            }
+        }
+        _ => {}
+    }
+    date
+}
+
+/// The XMP property standing in for an Info dictionary key
+fn xmp_property(xmp: &HashMap<String, Vec<String>>, info_key: &str) -> Option<String> {
+    let values = |name: &str| xmp.get(name).filter(|values| !values.is_empty());
+    let value = match info_key {
+        "Title" => values("title")?.first().cloned(),
+        "Author" => values("creator").map(|authors| authors.join("; ")),
+        "Subject" => values("description")?.first().cloned(),
+        "Keywords" => values("Keywords").and_then(|keywords| keywords.first().cloned())
+            .or_else(|| values("subject").map(|subjects| subjects.join(", "))),
+        "Creator" => values("CreatorTool")?.first().cloned(),
+        "Producer" => values("Producer")?.first().cloned(),
+        "CreationDate" => values("CreateDate")?.first().cloned(),
+        "ModDate" => values("ModifyDate")?.first().cloned(),
+        _ => None,
+    };
+    value.filter(|value| !value.is_empty())
+}
+
+/// The XMP properties of interest by local name, with the items of `rdf:Alt`,
+/// `rdf:Bag` and `rdf:Seq` values as separate entries. Simple properties may
+/// also be written as attributes of `rdf:Description`.
+fn read_xmp(xml: &str) -> Result<HashMap<String, Vec<String>>> {
+    const NAMES: [&str; 12] = [
+        "title", "creator", "description", "subject", "Keywords", "Producer",
+        "CreatorTool", "CreateDate", "ModifyDate", "MetadataDate", "part", "conformance",
+    ];
+    let mut properties: HashMap<String, Vec<String>> = HashMap::new();
+    let description_attributes = |element: &BytesStart, properties: &mut HashMap<String, Vec<String>>| {
+        if element.local_name().as_ref() != b"Description" {
+            return;
+        }
+        for attribute in element.attributes().flatten() {
+            let name = String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+            if NAMES.contains(&name.as_str())
+                && let Ok(value) = attribute.unescape_value()
+            {
+                properties.entry(name).or_default().push(value.trim().to_string());
+            }
+        }
+    };
+
+    // The property being read, and whether a list item of it is open
+    let mut open: Option<String> = None;
+    let mut depth = 0usize;
+    let mut reader = quick_xml::Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                description_attributes(&e, &mut properties);
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                match &open {
+                    Some(property) if name == "li" => properties.entry(property.clone()).or_default().push(String::new()),
+                    Some(_) => {}
+                    None if NAMES.contains(&name.as_str()) => {
+                        properties.entry(name.clone()).or_default();
+                        open = Some(name);
+                        depth = 0;
+                    }
+                    None => {}
+                }
+                if open.is_some() {
+                    depth += 1;
+                }
+            }
+            Event::Empty(e) => description_attributes(&e, &mut properties),
+            Event::End(_) if open.is_some() => {
+                depth -= 1;
+                if depth == 0 {
+                    open = None;
+                }
+            }
+            Event::Text(text) => {
+                if let Some(property) = &open {
+                    let text = text.decode()?;
+                    push_text(properties.entry(property.clone()).or_default(), &text);
+                }
+            }
+            Event::GeneralRef(reference) => {
+                if let Some(property) = &open {
+                    let values = properties.entry(property.clone()).or_default();
+                    if let Some(ch) = reference.resolve_char_ref()? {
+                        push_text(values, &ch.to_string());
+                    } else if let Some(text) = quick_xml::escape::resolve_predefined_entity(&reference.decode()?) {
+                        push_text(values, text);
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    for values in properties.values_mut() {
+        for value in values.iter_mut() {
+            *value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        values.retain(|value| !value.is_empty());
+    }
+    Ok(properties)
+}
+
+/// Append text to the last value of a property, starting one if there is none
+fn push_text(values: &mut Vec<String>, text: &str) {
+    match values.last_mut() {
+        Some(last) => last.push_str(text),
+        None => values.push(text.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{Stream, dictionary};
+
+    const XMP: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:pdfaid="http://www.aiim.org/pdfa/ns/id/" pdfaid:part="2" pdfaid:conformance="B"/>
+  <rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:xmp="http://ns.adobe.com/xap/1.0/">
+   <dc:title><rdf:Alt><rdf:li xml:lang="x-default">XMP title</rdf:li></rdf:Alt></dc:title>
+   <dc:creator><rdf:Seq><rdf:li>Ann Lee</rdf:li><rdf:li>Bo &amp; Co</rdf:li></rdf:Seq></dc:creator>
+   <dc:subject><rdf:Bag><rdf:li>budget</rdf:li><rdf:li>2024</rdf:li></rdf:Bag></dc:subject>
+   <xmp:ModifyDate>2024-03-05T09:15:00+01:00</xmp:ModifyDate>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    /// Three pages: two Letter, then an A4 page rotated to landscape
+    fn report_pdf() -> tempfile::NamedTempFile {
+        let mut doc = Document::with_version("1.4");
+        let pages_id = doc.new_object_id();
+        let mut kids = Vec::new();
+        for (media_box, rotate) in [([612, 792], 0), ([612, 792], 0), ([595, 842], 90)] {
+            let content_id = doc.add_object(Stream::new(dictionary! {}, b"BT ET".to_vec()));
+            kids.push(doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+                "MediaBox" => vec![0.into(), 0.into(), media_box[0].into(), media_box[1].into()],
+                "Rotate" => rotate,
+            }).into());
+        }
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => 3,
+            "Kids" => kids,
+        }));
+        let metadata_id = doc.add_object(Stream::new(
+            dictionary! { "Type" => "Metadata", "Subtype" => "XML" },
+            XMP.as_bytes().to_vec(),
+        ));
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+            "Version" => "1.7",
+            "Metadata" => metadata_id,
+            "MarkInfo" => dictionary! { "Marked" => true },
+        });
+        let info_id = doc.add_object(dictionary! {
+            "Title" => Object::string_literal("Quarterly Report"),
+            "Author" => lopdf::text_string("Zoë Park"),
+            "Producer" => Object::string_literal("Report Writer 2.1"),
+            "CreationDate" => Object::string_literal("D:20240301103000+01'00'"),
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.trailer.set("Info", info_id);
+
+        let mut file = tempfile::NamedTempFile::with_suffix(".pdf").unwrap();
+        doc.save_to(&mut file).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_pdf_metadata_report() {
+        let file = report_pdf();
+        let metadata = PdfMetadata::read(file.path().to_str().unwrap()).unwrap();
+        assert_eq!(
+            metadata.to_text(),
+            "Document properties:\n\
+            \x20 Title: Quarterly Report\n\
+            \x20 Author: Zoë Park\n\
+            \x20 Keywords: budget, 2024\n\
+            \x20 Producer: Report Writer 2.1\n\
+            \x20 Created: 2024-03-01T10:30:00+01:00\n\
+            \x20 Modified: 2024-03-05T09:15:00+01:00\n\
+            PDF version: 1.7\n\
+            Encrypted: no\n\
+            Tagged: yes\n\
+            PDF/A: PDF/A-2b\n\
+            Page sizes:\n\
+            \x20 1-2: 612 x 792 pt (Letter, portrait)\n\
+            \x20 3: 842 x 595 pt (A4, landscape)"
+        );
+
+        let info = crate::document_parser::get_document_page_info(file.path().to_str().unwrap());
+        assert_eq!(info.total_pages, Some(3));
+        assert!(info.page_info.starts_with("PDF file with 3 pages\n\nDocument properties:\n  Title: Quarterly Report\n"));
+    }
+
+    #[test]
+    fn test_pdf_dates_and_encryption_methods() {
+        assert_eq!(pdf_date("D:20231105"), "2023-11-05");
+        assert_eq!(pdf_date("D:20231105142233Z"), "2023-11-05T14:22:33Z");
+        assert_eq!(pdf_date("D:202311051422-05'30'"), "2023-11-05T14:22:00-05:30");
+        assert_eq!(pdf_date("last Tuesday"), "last Tuesday");
+        assert_eq!(encryption_method(2, Some(128), None), "RC4 128-bit");
+        assert_eq!(encryption_method(4, Some(16), Some(b"AESV2")), "AES-128");
+        assert_eq!(encryption_method(5, Some(256), Some(b"AESV3")), "AES-256");
+        assert_eq!(page_list(&[1, 2, 3, 5, 7, 8]), "1-3, 5, 7-8");
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::Result;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use crate::fast_pdf_extractor::LopdfExtractor;
+use crate::shared_utils::{validate_file_path, FileTextResult};
+
+/// Most outline entries read from one document, against malformed sibling chains
+const MAX_OUTLINE_ENTRIES: usize = 10_000;
+
+/// Deepest name tree followed when resolving named destinations
+const MAX_NAME_TREE_DEPTH: usize = 32;
+
+/// One outline entry, in document order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineEntry {
+    pub title: String,
+    /// Nesting level, 0 for top-level entries
+    pub depth: usize,
+    /// 1-based target page, when the entry points at a page of this document
+    pub page: Option<usize>,
+}
+
+/// The outline of a PDF with its page count
+#[derive(Debug, Clone, Default)]
+pub struct PdfOutline {
+    pub entries: Vec<OutlineEntry>,
+    pub total_pages: usize,
+}
+
+/// Pages spanned by an outline entry found by `PdfOutline::find_section`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineSection {
+    /// Titles from the top-level entry down to the one found, joined with " > "
+    pub path: String,
+    pub first_page: usize,
+    pub last_page: usize,
+}
+
+impl PdfOutline {
+    /// Read the outline of a PDF file
+    pub fn read(file_path: &str) -> Result<Self> {
+        let document = LopdfExtractor::load(file_path)?;
+        Ok(Self::from_document(&document))
+    }
+
+    pub fn from_document(document: &Document) -> Self {
+        let pages: HashMap<ObjectId, usize> = document.get_pages().into_iter()
+            .map(|(number, id)| (id, number as usize))
+            .collect();
+        let mut entries = Vec::new();
+        let first = document.catalog().ok()
+            .and_then(|catalog| resolve(document, catalog, b"Outlines"))
+            .and_then(|outlines| outlines.as_dict().ok())
+            .and_then(|outlines| outlines.get(b"First").and_then(Object::as_reference).ok());
+        if let Some(first) = first {
+            let mut visited = HashSet::new();
+            read_entries(document, &pages, first, 0, &mut visited, &mut entries);
+        }
+        Self { entries, total_pages: pages.len() }
+    }
+
+    /// Markdown list of the outline, one nested bullet per entry with its page
+    pub fn to_markdown(&self, file_name: &str) -> String {
+        let mut markdown = format!("# {}\n\n", file_name);
+        if self.entries.is_empty() {
+            markdown.push_str(&format!("This PDF has no outline (bookmarks); it has {} pages.\n", self.total_pages));
+            return markdown;
+        }
+
+        markdown.push_str(&format!("## Outline ({} entries, {} pages)\n\n", self.entries.len(), self.total_pages));
+        for entry in &self.entries {
+            let target = match entry.page {
+                Some(page) => format!("page {}", page),
+                None => "no page".to_string(),
+            };
+            markdown.push_str(&format!("{}- {} ({})\n", "  ".repeat(entry.depth), entry.title, target));
+        }
+        markdown.push_str("\nRead a section by passing its title, or a path of titles such as \"Chapter 7 > Error Codes\", as the section of read_office_document.\n");
+        markdown
+    }
+
+    /// Find the entry named by a title or a path of titles separated by `>`,
+    /// and the pages it spans: from its target page through the page where the
+    /// next entry at the same or a higher level begins, since sections often
+    /// start mid-page. Titles match case-insensitively, preferring exact
+    /// matches over prefixes over substrings.
+    pub fn find_section(&self, section: &str) -> Result<OutlineSection> {
+        if self.entries.is_empty() {
+            anyhow::bail!("This PDF has no outline (bookmarks) to find sections in");
+        }
+
+        // A title containing '>' is matched whole before the text is split into a path
+        let whole = [section];
+        let split: Vec<&str> = section.split('>').map(str::trim).filter(|part| !part.is_empty()).collect();
+        let index = self.find_path(&whole)
+            .or_else(|| (split.len() > 1).then(|| self.find_path(&split)).flatten())
+            .ok_or_else(|| {
+                let top_level: Vec<&str> = self.entries.iter()
+                    .filter(|entry| entry.depth == 0)
+                    .map(|entry| entry.title.as_str())
+                    .collect();
+                anyhow::anyhow!("No outline entry matches '{}'. Top-level entries: {}", section, top_level.join(", "))
+            })?;
+
+        let entry = &self.entries[index];
+        let end = self.entries[index + 1..].iter()
+            .position(|next| next.depth <= entry.depth)
+            .map_or(self.entries.len(), |offset| index + 1 + offset);
+        // An entry without a page of its own starts at its first child that has one
+        let first_page = self.entries[index..end].iter().find_map(|entry| entry.page)
+            .ok_or_else(|| anyhow::anyhow!("Outline entry '{}' does not point at a page of this document", entry.title))?;
+        let last_page = self.entries[end..].iter()
+            .filter(|next| next.depth <= entry.depth)
+            .find_map(|next| next.page)
+            .map_or(self.total_pages, |next_page| next_page.max(first_page))
+            .min(self.total_pages.max(first_page));
+
+        Ok(OutlineSection { path: self.path_of(index), first_page, last_page })
+    }
+
+    /// Index of the entry reached by matching each title below the previous one
+    fn find_path(&self, titles: &[&str]) -> Option<usize> {
+        let mut scope = 0..self.entries.len();
+        let mut found = None;
+        for (level, title) in titles.iter().enumerate() {
+            let wanted = normalize(title);
+            let parent_depth = found.map(|index: usize| self.entries[index].depth);
+            let candidates = scope.clone()
+                .filter(|&index| level == 0 || Some(self.entries[index].depth) > parent_depth);
+            let index = candidates
+                .filter_map(|index| match_rank(&normalize(&self.entries[index].title), &wanted).map(|rank| (rank, index)))
+                .min()?
+                .1;
+
+            let depth = self.entries[index].depth;
+            let end = self.entries[index + 1..].iter()
+                .position(|entry| entry.depth <= depth)
+                .map_or(self.entries.len(), |offset| index + 1 + offset);
+            scope = index + 1..end;
+            found = Some(index);
+        }
+        found
+    }
+
+    /// Titles of an entry and its ancestors, joined with " > "
+    fn path_of(&self, index: usize) -> String {
+        let mut titles = vec![self.entries[index].title.as_str()];
+        let mut depth = self.entries[index].depth;
+        for entry in self.entries[..index].iter().rev() {
+            if entry.depth < depth {
+                titles.push(&entry.title);
+                depth = entry.depth;
+            }
+        }
+        titles.reverse();
+        titles.join(" > ")
+    }
+}
+
+/// Read the outline of a PDF and describe it as a nested markdown list
+pub fn get_pdf_outline(resolved_file_path: &str) -> FileTextResult {
+    let file_path = resolved_file_path.to_string();
+    match validate_file_path(resolved_file_path) {
+        Ok(ext) if ext == "pdf" => {}
+        Ok(ext) => return FileTextResult::error(
+            file_path,
+            format!("Outlines are only available for PDF files, not {} files", ext),
+        ),
+        Err(e) => return FileTextResult::error(file_path, e),
+    }
+
+    match PdfOutline::read(resolved_file_path) {
+        Ok(outline) => {
+            let file_name = Path::new(resolved_file_path).file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            FileTextResult::success(file_path, outline.to_markdown(&file_name))
+        }
+        Err(e) => FileTextResult::error(file_path, format!("Failed to read PDF outline: {}", e)),
+    }
+}
+
+/// Lower-cased title with runs of whitespace collapsed
+fn normalize(title: &str) -> String {
+    title.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// How well a title matches: 0 exactly, 1 as a prefix, 2 anywhere
+fn match_rank(title: &str, wanted: &str) -> Option<u8> {
+    if title == wanted {
+        Some(0)
+    } else if title.starts_with(wanted) {
+        Some(1)
+    } else if title.contains(wanted) {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Look up a dictionary entry, following a reference
+fn resolve<'a>(document: &'a Document, dictionary: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+    let object = dictionary.get(key).ok()?;
+    document.dereference(object).ok().map(|(_, object)| object)
+}
+
+/// Append an outline item, its children and its later siblings
+fn read_entries(
+    document: &Document,
+    pages: &HashMap<ObjectId, usize>,
+    first: ObjectId,
+    depth: usize,
+    visited: &mut HashSet<ObjectId>,
+    entries: &mut Vec<OutlineEntry>,
+) {
+    let mut next = Some(first);
+    while let Some(id) = next {
+        if entries.len() >= MAX_OUTLINE_ENTRIES || !visited.insert(id) {
+            return;
+        }
+        let Ok(item) = document.get_dictionary(id) else {
+            return;
+        };
+
+        let title = resolve(document, item, b"Title")
+            .map(|title| lopdf::decode_text_string(title)
+                .unwrap_or_else(|_| String::from_utf8_lossy(title.as_str().unwrap_or_default()).into_owned()))
+            .unwrap_or_default();
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        let destination = resolve(document, item, b"Dest").or_else(|| {
+            let action = resolve(document, item, b"A")?.as_dict().ok()?;
+            (action.get(b"S").and_then(Object::as_name).ok() == Some(b"GoTo".as_slice()))
+                .then(|| resolve(document, action, b"D"))
+                .flatten()
+        });
+        let page = destination.and_then(|destination| destination_page(document, pages, destination, 0));
+        entries.push(OutlineEntry { title, depth, page });
+
+        if let Ok(child) = item.get(b"First").and_then(Object::as_reference) {
+            read_entries(document, pages, child, depth + 1, visited, entries);
+        }
+        next = item.get(b"Next").and_then(Object::as_reference).ok();
+    }
+}
+
+/// Page number of an explicit destination array, a named destination or a
+/// dictionary holding one under `D`
+fn destination_page(document: &Document, pages: &HashMap<ObjectId, usize>, destination: &Object, depth: usize) -> Option<usize> {
+    if depth > 4 {
+        return None;
+    }
+    match destination {
+        Object::Array(items) => match items.first()? {
+            Object::Reference(id) => pages.get(id).copied(),
+            // Some producers give the 0-based page index instead of a page reference
+            Object::Integer(index) => usize::try_from(*index).ok().map(|index| index + 1).filter(|page| *page <= pages.len()),
+            _ => None,
+        },
+        Object::Dictionary(dictionary) => {
+            let inner = resolve(document, dictionary, b"D")?;
+            destination_page(document, pages, inner, depth + 1)
+        }
+        Object::Name(name) | Object::String(name, _) => {
+            let target = named_destination(document, name)?;
+            destination_page(document, pages, target, depth + 1)
+        }
+        Object::Reference(_) => {
+            let (_, target) = document.dereference(destination).ok()?;
+            destination_page(document, pages, target, depth + 1)
+        }
+        _ => None,
+    }
+}
+
+/// Look a named destination up in the catalog's `Dests` dictionary (PDF 1.1)
+/// or the `Dests` name tree of its `Names` dictionary
+fn named_destination<'a>(document: &'a Document, name: &[u8]) -> Option<&'a Object> {
+    let catalog = document.catalog().ok()?;
+    if let Some(target) = resolve(document, catalog, b"Dests")
+        .and_then(|dests| dests.as_dict().ok())
+        .and_then(|dests| resolve(document, dests, name))
+    {
+        return Some(target);
+    }
+    let tree = resolve(document, catalog, b"Names")?.as_dict().ok()
+        .and_then(|names| resolve(document, names, b"Dests"))?
+        .as_dict().ok()?;
+    name_tree_lookup(document, tree, name, 0)
+}
+
+fn name_tree_lookup<'a>(document: &'a Document, node: &'a Dictionary, name: &[u8], depth: usize) -> Option<&'a Object> {
+    if depth > MAX_NAME_TREE_DEPTH {
+        return None;
+    }
+    if let Some(names) = resolve(document, node, b"Names").and_then(|names| names.as_array().ok()) {
+        let found = names.chunks(2).find_map(|pair| match pair {
+            [key, value] if key.as_str().ok() == Some(name) => document.dereference(value).ok().map(|(_, value)| value),
+            _ => None,
+        });
+        if found.is_some() {
+            return found;
+        }
+    }
+    let kids = resolve(document, node, b"Kids")?.as_array().ok()?;
+    kids.iter().find_map(|kid| {
+        let kid = document.dereference(kid).ok()?.1.as_dict().ok()?;
+        // Skip subtrees whose Limits exclude the name
+        if let Some(limits) = resolve(document, kid, b"Limits").and_then(|limits| limits.as_array().ok())
+            && let [low, high] = limits.as_slice()
+            && let (Ok(low), Ok(high)) = (low.as_str(), high.as_str())
+            && (name < low || name > high)
+        {
+            return None;
+        }
+        name_tree_lookup(document, kid, name, depth + 1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{Stream, StringFormat, dictionary};
+
+    /// Six pages reading "Page N", with an outline using page references, a
+    /// named destination and a GoTo action
+    fn manual_pdf() -> tempfile::NamedTempFile {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let resources = doc.add_object(dictionary! { "Font" => dictionary! { "F1" => font } });
+        let page_ids: Vec<ObjectId> = (1..=6)
+            .map(|number| {
+                let content = format!("BT /F1 12 Tf 72 720 Td (Page {}) Tj ET", number).into_bytes();
+                let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                    "Resources" => resources,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                })
+            })
+            .collect();
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => 6,
+            "Kids" => page_ids.iter().map(|&id| id.into()).collect::<Vec<Object>>(),
+        }));
+
+        // Chapter 1 (1) > Scope (2); Chapter 7 (3) > Overview (3), Error Codes (4, named); Appendix (6, action)
+        let outlines_id = doc.new_object_id();
+        let ids: Vec<ObjectId> = (0..6).map(|_| doc.new_object_id()).collect();
+        let explicit = |page: usize| Object::Array(vec![page_ids[page - 1].into(), "Fit".into()]);
+        let items = [
+            (ids[0], "Chapter 1 Introduction", outlines_id, Some(ids[2]), None, Some(ids[1]), explicit(1)),
+            (ids[1], "Scope", ids[0], None, None, None, explicit(2)),
+            (ids[2], "Chapter 7 > Error Handling", outlines_id, Some(ids[5]), Some(ids[0]), Some(ids[3]), explicit(3)),
+            (ids[3], "Overview", ids[2], Some(ids[4]), None, None, explicit(3)),
+            (ids[4], "Error Codes", ids[2], None, Some(ids[3]), None, Object::String(b"codes".to_vec(), StringFormat::Literal)),
+            (ids[5], "Appendix \u{C9}", outlines_id, None, Some(ids[2]), None, Object::Null),
+        ];
+        for (id, title, parent, next, prev, first, destination) in items {
+            let mut item = dictionary! { "Title" => lopdf::text_string(title), "Parent" => parent };
+            if let Some(next) = next {
+                item.set("Next", next);
+            }
+            if let Some(prev) = prev {
+                item.set("Prev", prev);
+            }
+            if let Some(first) = first {
+                item.set("First", first);
+            }
+            if destination == Object::Null {
+                item.set("A", dictionary! { "S" => "GoTo", "D" => explicit(6) });
+            } else {
+                item.set("Dest", destination);
+            }
+            doc.objects.insert(id, Object::Dictionary(item));
+        }
+        doc.objects.insert(outlines_id, Object::Dictionary(dictionary! {
+            "Type" => "Outlines",
+            "First" => ids[0],
+            "Last" => ids[5],
+        }));
+        let names = dictionary! {
+            "Dests" => dictionary! {
+                "Kids" => vec![Object::Dictionary(dictionary! {
+                    "Limits" => vec![Object::string_literal("a"), Object::string_literal("z")],
+                    "Names" => vec![Object::string_literal("codes"), explicit(4)],
+                })],
+            },
+        };
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+            "Outlines" => outlines_id,
+            "Names" => names,
+        });
+        doc.trailer.set("Root", catalog_id);
+
+        let mut file = tempfile::NamedTempFile::with_suffix(".pdf").unwrap();
+        doc.save_to(&mut file).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_outline_markdown() {
+        let file = manual_pdf();
+        let result = get_pdf_outline(file.path().to_str().unwrap());
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert!(result.content.contains(
+            "## Outline (6 entries, 6 pages)\n\n\
+            - Chapter 1 Introduction (page 1)\n  - Scope (page 2)\n\
+            - Chapter 7 > Error Handling (page 3)\n  - Overview (page 3)\n  - Error Codes (page 4)\n\
+            - Appendix \u{C9} (page 6)\n"
+        ));
+    }
+
+    #[test]
+    fn test_find_section() {
+        let file = manual_pdf();
+        let outline = PdfOutline::read(file.path().to_str().unwrap()).unwrap();
+        let section = |query: &str| outline.find_section(query).map(|section| (section.path, section.first_page, section.last_page));
+
+        assert_eq!(section("chapter 7 > error codes").unwrap(), ("Chapter 7 > Error Handling > Error Codes".to_string(), 4, 6));
+        assert_eq!(section("Chapter 7 > Error Handling").unwrap(), ("Chapter 7 > Error Handling".to_string(), 3, 6));
+        assert_eq!(section("Chapter 1").unwrap(), ("Chapter 1 Introduction".to_string(), 1, 3));
+        assert_eq!(section("scope").unwrap(), ("Chapter 1 Introduction > Scope".to_string(), 2, 3));
+        assert_eq!(section("Appendix").unwrap().1..=section("Appendix").unwrap().2, 6..=6);
+        let error = section("Chapter 1 > Error Codes").unwrap_err().to_string();
+        assert!(error.starts_with("No outline entry matches 'Chapter 1 > Error Codes'. Top-level entries: Chapter 1 Introduction"));
+    }
+
+    #[test]
+    fn test_read_section_of_pdf() {
+        use crate::document_parser::{DocumentReadOptions, process_document_with_options};
+
+        let file = manual_pdf();
+        let path = file.path().to_str().unwrap();
+        let options = DocumentReadOptions { section: Some("Chapter 7 > Error Codes".to_string()), ..Default::default() };
+        let result = process_document_with_options(path, None, &options);
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert_eq!(result.returned_pages, vec![4, 5, 6]);
+        assert_eq!(result.requested_pages, "4-6 (section Chapter 7 > Error Handling > Error Codes)");
+        assert!(result.content.contains("=== Page 4 ===\nPage 4\n"));
+        assert!(!result.content.contains("Page 3"));
+
+        let result = process_document_with_options(path, Some("2".to_string()), &options);
+        assert_eq!(result.error.unwrap(), "Use either the pages or the section parameter, not both");
+    }
+}
@@ -0,0 +1,719 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context, Result};
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+/// Deepest nesting of form XObjects followed when collecting page text
+const MAX_XOBJECT_DEPTH: usize = 8;
+
+/// Text of one page, decoded from its content stream
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageText {
+    pub text: String,
+    /// Number of text-showing operators whose font could not be decoded
+    pub undecoded: usize,
+}
+
+/// Decode the text of a page (as returned by `Document::get_pages`), following
+/// the form XObjects it draws. Lines are broken where the text moves to a new
+/// baseline, and wide gaps within a line become spaces.
+pub fn extract_page_text(document: &Document, page_id: ObjectId) -> Result<PageText> {
+    let content = document.get_page_content(page_id)
+        .with_context(|| format!("Failed to read the content of page object {:?}", page_id))?;
+    let fonts: HashMap<Vec<u8>, Font> = document.get_page_fonts(page_id)
+        .map(|fonts| fonts.into_iter().map(|(name, font)| (name, Font::new(document, font))).collect())
+        .unwrap_or_default();
+
+    let mut writer = TextWriter::default();
+    let resources = page_resources(document, page_id);
+    writer.run(document, &content, &fonts, &resources, 0)?;
+    Ok(writer.finish())
+}
+
+/// Resource dictionaries of a page, its own first and then those it inherits
+fn page_resources(document: &Document, page_id: ObjectId) -> Vec<&Dictionary> {
+    let Ok((own, inherited)) = document.get_page_resources(page_id) else {
+        return Vec::new();
+    };
+    own.into_iter()
+        .chain(inherited.into_iter().filter_map(|id| document.get_dictionary(id).ok()))
+        .collect()
+}
+
+/// Look up a dictionary entry, following a reference
+fn resolve<'a>(document: &'a Document, dictionary: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+    let object = dictionary.get(key).ok()?;
+    document.dereference(object).ok().map(|(_, object)| object)
+}
+
+fn number(object: &Object) -> Option<f32> {
+    match object {
+        Object::Integer(value) => Some(*value as f32),
+        Object::Real(value) => Some(*value),
+        _ => None,
+    }
+}
+
+/// Gap between two runs of text on a line, as a fraction of the font size,
+/// from which they are taken to be separate words
+const WORD_GAP: f32 = 0.15;
+
+/// Collects the text of a content stream into lines
+struct TextWriter {
+    lines: Vec<String>,
+    line: String,
+    /// Baseline of the text last shown, and where it ended
+    shown_y: Option<f32>,
+    shown_end: Option<f32>,
+    /// Start of the current text line, and whether it moved since the last show
+    line_x: f32,
+    line_y: f32,
+    moved: bool,
+    /// Scale of the text matrix and the text state set by `Tf`, `Tc`, `Tw` and `Tz`
+    scale: (f32, f32),
+    font_size: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scale: f32,
+    undecoded: usize,
+}
+
+impl Default for TextWriter {
+    fn default() -> Self {
+        Self {
+            lines: Vec::new(),
+            line: String::new(),
+            shown_y: None,
+            shown_end: None,
+            line_x: 0.0,
+            line_y: 0.0,
+            moved: false,
+            scale: (1.0, 1.0),
+            font_size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            undecoded: 0,
+        }
+    }
+}
+
+impl TextWriter {
+    fn run(
+        &mut self,
+        document: &Document,
+        content: &[u8],
+        fonts: &HashMap<Vec<u8>, Font>,
+        resources: &[&Dictionary],
+        depth: usize,
+    ) -> Result<()> {
+        let content = Content::decode(content).context("Failed to parse a PDF content stream")?;
+        let mut font: Option<&Font> = None;
+        for operation in &content.operations {
+            let operands = &operation.operands;
+            let operand = |index: usize| operands.get(index).and_then(number);
+            match operation.operator.as_str() {
+                "BT" => {
+                    self.line_x = 0.0;
+                    self.line_y = 0.0;
+                    self.scale = (1.0, 1.0);
+                    self.moved = true;
+                }
+                "Tf" => {
+                    font = operands.first().and_then(|name| name.as_name().ok()).and_then(|name| fonts.get(name));
+                    self.font_size = operand(1).unwrap_or(self.font_size);
+                }
+                "Tc" => self.char_spacing = operand(0).unwrap_or(0.0),
+                "Tw" => self.word_spacing = operand(0).unwrap_or(0.0),
+                "Tz" => self.horizontal_scale = operand(0).unwrap_or(100.0) / 100.0,
+                "Td" | "TD" => {
+                    self.line_x += operand(0).unwrap_or(0.0) * self.scale.0;
+                    self.line_y += operand(1).unwrap_or(0.0) * self.scale.1;
+                    self.moved = true;
+                }
+                "Tm" => {
+                    self.scale = (operand(0).unwrap_or(1.0), operand(3).unwrap_or(1.0));
+                    self.line_x = operand(4).unwrap_or(0.0);
+                    self.line_y = operand(5).unwrap_or(0.0);
+                    self.moved = true;
+                }
+                "T*" => self.new_line(),
+                "Tj" | "TJ" => self.show(font, operands.first()),
+                "'" => {
+                    self.new_line();
+                    self.show(font, operands.first());
+                }
+                "\"" => {
+                    self.new_line();
+                    self.show(font, operands.get(2));
+                }
+                "Do" if depth < MAX_XOBJECT_DEPTH => {
+                    if let Some(name) = operands.first().and_then(|name| name.as_name().ok()) {
+                        self.run_form(document, name, resources, depth)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Follow a form XObject drawn with `Do`, using its own resources when it has them
+    fn run_form(&mut self, document: &Document, name: &[u8], resources: &[&Dictionary], depth: usize) -> Result<()> {
+        let form = resources.iter()
+            .filter_map(|resources| resolve(document, resources, b"XObject")?.as_dict().ok())
+            .find_map(|xobjects| resolve(document, xobjects, name)?.as_stream().ok());
+        let Some(form) = form else {
+            return Ok(());
+        };
+        if form.dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Form".as_slice()) {
+            return Ok(());
+        }
+
+        let own_resources = resolve(document, &form.dict, b"Resources").and_then(|resources| resources.as_dict().ok());
+        let form_resources: Vec<&Dictionary> = own_resources.into_iter().chain(resources.iter().copied()).collect();
+        let mut fonts = HashMap::new();
+        for resources in form_resources.iter().rev() {
+            if let Some(font_dict) = resolve(document, resources, b"Font").and_then(|fonts| fonts.as_dict().ok()) {
+                for (name, _) in font_dict.iter() {
+                    if let Some(font) = resolve(document, font_dict, name).and_then(|font| font.as_dict().ok()) {
+                        fonts.insert(name.clone(), Font::new(document, font));
+                    }
+                }
+            }
+        }
+        let content = form.get_plain_content().unwrap_or_else(|_| form.content.clone());
+        self.run(document, &content, &fonts, &form_resources, depth + 1)
+    }
+
+    fn new_line(&mut self) {
+        if !self.line.trim().is_empty() {
+            self.lines.push(std::mem::take(&mut self.line));
+        }
+        self.line.clear();
+        self.shown_y = None;
+        self.shown_end = None;
+        self.moved = false;
+    }
+
+    /// Width of a space-sized gap at the current font size, in user space
+    fn word_gap(&self) -> f32 {
+        WORD_GAP * self.font_size * self.horizontal_scale * self.scale.0.abs()
+    }
+
+    /// Show a string, or the strings and spacing of a `TJ` array
+    fn show(&mut self, font: Option<&Font>, operand: Option<&Object>) {
+        let Some(operand) = operand else {
+            return;
+        };
+        let mut pen = self.shown_end.unwrap_or(self.line_x);
+        if self.moved {
+            match (self.shown_y, self.shown_end) {
+                (Some(y), _) if (y - self.line_y).abs() > 0.01 => self.new_line(),
+                (Some(_), Some(end)) if self.line_x - end > self.word_gap() => self.space(),
+                _ => {}
+            }
+            pen = self.line_x;
+            self.moved = false;
+        }
+        self.shown_y = Some(self.line_y);
+
+        let Some(font) = font else {
+            self.undecoded += 1;
+            return;
+        };
+        let strings: Vec<&Object> = match operand {
+            Object::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+        // Advances are in thousandths of the font size, scaled into user space
+        let unit = self.font_size * self.horizontal_scale * self.scale.0 / 1000.0;
+        for item in strings {
+            match item {
+                Object::String(bytes, _) => {
+                    match font.decoder.decode(bytes) {
+                        Some(text) => self.line.push_str(&text),
+                        None => self.undecoded += 1,
+                    }
+                    for code in bytes.chunks(font.code_length) {
+                        let mut advance = font.width(code_value(code)) * unit
+                            + self.char_spacing * self.horizontal_scale * self.scale.0;
+                        if code == b" " {
+                            advance += self.word_spacing * self.horizontal_scale * self.scale.0;
+                        }
+                        pen += advance;
+                    }
+                }
+                adjustment => {
+                    if let Some(adjustment) = number(adjustment) {
+                        pen -= adjustment * unit;
+                        if -adjustment / 1000.0 > WORD_GAP {
+                            self.space();
+                        }
+                    }
+                }
+            }
+        }
+        self.shown_end = Some(pen);
+    }
+
+    fn space(&mut self) {
+        if !self.line.is_empty() && !self.line.ends_with(char::is_whitespace) {
+            self.line.push(' ');
+        }
+    }
+
+    fn finish(mut self) -> PageText {
+        self.new_line();
+        let text = self.lines.iter().map(|line| line.trim_end()).collect::<Vec<_>>().join("\n");
+        PageText { text, undecoded: self.undecoded }
+    }
+}
+
+/// A font of a page: how its codes decode to text and how far each advances
+#[derive(Debug)]
+struct Font {
+    decoder: FontDecoder,
+    /// Bytes per character code: 1 for simple fonts, 2 for composite ones
+    code_length: usize,
+    /// Glyph widths by code, in thousandths of the font size
+    widths: HashMap<u32, f32>,
+    default_width: f32,
+}
+
+impl Font {
+    fn new(document: &Document, font: &Dictionary) -> Self {
+        let decoder = FontDecoder::new(document, font);
+        let subtype = font.get(b"Subtype").and_then(Object::as_name).ok();
+        let mut widths = HashMap::new();
+        let number_at = |object: &Object| document.dereference(object).ok().and_then(|(_, object)| number(object));
+
+        if subtype == Some(b"Type0".as_slice()) {
+            let descendant = resolve(document, font, b"DescendantFonts")
+                .and_then(|fonts| fonts.as_array().ok()?.first())
+                .and_then(|descendant| document.dereference(descendant).ok()?.1.as_dict().ok());
+            let default_width = descendant.and_then(|descendant| number_at(descendant.get(b"DW").ok()?)).unwrap_or(1000.0);
+            let ranges = descendant.and_then(|descendant| resolve(document, descendant, b"W")?.as_array().ok());
+            // `W` holds runs of `first [w1 w2 ...]` and `first last w`
+            let mut items = ranges.into_iter().flatten().peekable();
+            while let Some(first) = items.next().and_then(number_at) {
+                let first = first as u32;
+                match items.next().map(|item| document.dereference(item).map(|(_, item)| item)) {
+                    Some(Ok(Object::Array(each))) => {
+                        for (offset, width) in each.iter().enumerate() {
+                            if let Some(width) = number_at(width) {
+                                widths.insert(first + offset as u32, width);
+                            }
+                        }
+                    }
+                    Some(Ok(last)) => {
+                        let (Some(last), Some(width)) = (number(last), items.next().and_then(number_at)) else {
+                            break;
+                        };
+                        for code in first..=(last as u32).min(first.saturating_add(0xFFFF)) {
+                            widths.insert(code, width);
+                        }
+                    }
+                    _ => break,
+                }
+            }
+            return Self { decoder, code_length: 2, widths, default_width };
+        }
+
+        // Type 3 widths are in glyph space, which only the font matrix relates to text space
+        if subtype != Some(b"Type3".as_slice()) {
+            let first_char = font.get(b"FirstChar").ok().and_then(number_at).unwrap_or(0.0) as u32;
+            for (offset, width) in resolve(document, font, b"Widths").and_then(|widths| widths.as_array().ok()).into_iter().flatten().enumerate() {
+                if let Some(width) = number_at(width) {
+                    widths.insert(first_char + offset as u32, width);
+                }
+            }
+        }
+        let missing_width = resolve(document, font, b"FontDescriptor")
+            .and_then(|descriptor| descriptor.as_dict().ok()?.get(b"MissingWidth").ok().and_then(number_at));
+        // Without widths (the standard 14 fonts), assume an average glyph
+        Self { decoder, code_length: 1, widths, default_width: missing_width.filter(|width| *width > 0.0).unwrap_or(500.0) }
+    }
+
+    fn width(&self, code: u32) -> f32 {
+        self.widths.get(&code).copied().unwrap_or(self.default_width)
+    }
+}
+
+/// Turns the character codes of a font into text
+#[derive(Debug)]
+enum FontDecoder {
+    /// A ToUnicode CMap, with the font's single-byte encoding for codes it leaves out
+    Unicode(ToUnicodeMap, Option<Box<ByteEncoding>>),
+    Simple(Box<ByteEncoding>),
+    /// Two-byte UCS-2 or UTF-16 codes of the predefined `Uni*` CJK CMaps
+    Utf16,
+    /// A composite font without a ToUnicode map, whose codes are glyph ids
+    Unknown,
+}
+
+/// Characters of the 256 codes of a single-byte encoding
+type ByteEncoding = [Option<char>; 256];
+
+impl FontDecoder {
+    fn new(document: &Document, font: &Dictionary) -> Self {
+        let composite = font.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Type0".as_slice());
+        let encoding = resolve(document, font, b"Encoding");
+        let simple = (!composite).then(|| Box::new(byte_encoding(document, encoding)));
+
+        let to_unicode = resolve(document, font, b"ToUnicode")
+            .and_then(|stream| stream.as_stream().ok())
+            .and_then(|stream| stream.get_plain_content().ok().or_else(|| Some(stream.content.clone())))
+            .map(|content| ToUnicodeMap::parse(&content, if composite { 2 } else { 1 }))
+            .filter(|map| !map.is_empty());
+        match (to_unicode, simple) {
+            (Some(map), simple) => Self::Unicode(map, simple),
+            (None, Some(simple)) => Self::Simple(simple),
+            (None, None) => match encoding.and_then(|name| name.as_name().ok()) {
+                Some(name) if name.starts_with(b"Uni") && (name.windows(4).any(|part| part == b"UCS2") || name.windows(5).any(|part| part == b"UTF16")) => Self::Utf16,
+                _ => Self::Unknown,
+            },
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Self::Unicode(map, fallback) => Some(map.decode(bytes, fallback.as_deref())),
+            Self::Simple(encoding) => Some(bytes.iter().filter_map(|&byte| encoding[byte as usize]).collect()),
+            Self::Utf16 => {
+                let units: Vec<u16> = bytes.chunks(2).map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)])).collect();
+                Some(String::from_utf16_lossy(&units))
+            }
+            Self::Unknown => None,
+        }
+    }
+}
+
+/// The single-byte encoding of a simple font: a named base encoding, with the
+/// glyph names of a `Differences` array applied on top
+fn byte_encoding(document: &Document, encoding: Option<&Object>) -> ByteEncoding {
+    let (base, differences) = match encoding {
+        Some(Object::Name(name)) => (Some(name.as_slice()), None),
+        Some(Object::Dictionary(dictionary)) => (
+            dictionary.get(b"BaseEncoding").and_then(Object::as_name).ok(),
+            resolve(document, dictionary, b"Differences").and_then(|differences| differences.as_array().ok()),
+        ),
+        _ => (None, None),
+    };
+
+    let decoder = match base {
+        Some(b"MacRomanEncoding") => encoding_rs::MACINTOSH,
+        _ => encoding_rs::WINDOWS_1252,
+    };
+    let mut table: ByteEncoding = [None; 256];
+    for (code, slot) in table.iter_mut().enumerate() {
+        if code >= 0x20 {
+            *slot = decoder.decode_without_bom_handling(&[code as u8]).0.chars().next();
+        }
+    }
+    // StandardEncoding, the default for Type 1 fonts, has curly quotes where ASCII has straight ones
+    if matches!(base, None | Some(b"StandardEncoding")) {
+        table[0x27] = Some('\u{2019}');
+        table[0x60] = Some('\u{2018}');
+    }
+
+    let mut code = 0usize;
+    for item in differences.into_iter().flatten() {
+        match item {
+            Object::Integer(start) => code = (*start).clamp(0, 255) as usize,
+            Object::Name(name) => {
+                if code < 256 && let Some(ch) = glyph_char(&String::from_utf8_lossy(name)) {
+                    table[code] = Some(ch);
+                }
+                code += 1;
+            }
+            _ => {}
+        }
+    }
+    table
+}
+
+/// The character of a glyph name used in `Differences` arrays, for the
+/// `uniXXXX` names and the common Latin glyph names
+fn glyph_char(name: &str) -> Option<char> {
+    if let Some(hex) = name.strip_prefix("uni").filter(|hex| hex.len() == 4) {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    }
+    let mut chars = name.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Some(ch);
+    }
+    const NAMES: [(&str, char); 40] = [
+        ("space", ' '), ("exclam", '!'), ("quotedbl", '"'), ("numbersign", '#'), ("dollar", '$'),
+        ("percent", '%'), ("ampersand", '&'), ("quotesingle", '\''), ("parenleft", '('), ("parenright", ')'),
+        ("asterisk", '*'), ("plus", '+'), ("comma", ','), ("hyphen", '-'), ("period", '.'),
+        ("slash", '/'), ("zero", '0'), ("one", '1'), ("two", '2'), ("three", '3'),
+        ("four", '4'), ("five", '5'), ("six", '6'), ("seven", '7'), ("eight", '8'),
+        ("nine", '9'), ("colon", ':'), ("semicolon", ';'), ("question", '?'), ("at", '@'),
+        ("quoteright", '\u{2019}'), ("quoteleft", '\u{2018}'), ("quotedblleft", '\u{201C}'), ("quotedblright", '\u{201D}'),
+        ("endash", '\u{2013}'), ("emdash", '\u{2014}'), ("bullet", '\u{2022}'), ("fi", '\u{FB01}'), ("fl", '\u{FB02}'),
+        ("underscore", '_'),
+    ];
+    NAMES.iter().find(|(glyph, _)| *glyph == name).map(|&(_, ch)| ch)
+}
+
+/// A ToUnicode CMap: the text of each character code, by code length in bytes
+#[derive(Debug, Default)]
+struct ToUnicodeMap {
+    /// Code lengths from the code space ranges, shortest first
+    code_lengths: Vec<usize>,
+    chars: HashMap<(usize, u32), String>,
+    /// Ranges of codes as `(length, first, last)`, mapped to consecutive text
+    ranges: BTreeMap<(usize, u32), (u32, RangeTarget)>,
+}
+
+#[derive(Debug)]
+enum RangeTarget {
+    /// UTF-16 text of the first code; later codes increment its last unit
+    Start(Vec<u16>),
+    /// Text of each code of the range
+    Each(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum CMapToken {
+    Hex(Vec<u8>),
+    Word(String),
+    ArrayStart,
+    ArrayEnd,
+}
+
+impl ToUnicodeMap {
+    /// Parse the `bfchar` and `bfrange` mappings of a CMap. Codes are taken to
+    /// be `default_length` bytes long when the CMap declares no code space.
+    fn parse(content: &[u8], default_length: usize) -> Self {
+        let tokens = cmap_tokens(content);
+        let mut map = Self::default();
+        let mut index = 0;
+        while index < tokens.len() {
+            let CMapToken::Word(word) = &tokens[index] else {
+                index += 1;
+                continue;
+            };
+            index += 1;
+            match word.as_str() {
+                "begincodespacerange" => {
+                    while let (Some(CMapToken::Hex(low)), Some(CMapToken::Hex(_))) = (tokens.get(index), tokens.get(index + 1)) {
+                        // Empty codes would match without consuming any bytes
+                        if !low.is_empty() {
+                            map.code_lengths.push(low.len());
+                        }
+                        index += 2;
+                    }
+                }
+                "beginbfchar" => {
+                    while let (Some(CMapToken::Hex(source)), Some(target)) = (tokens.get(index), tokens.get(index + 1)) {
+                        let text = match target {
+                            CMapToken::Hex(bytes) => utf16_text(bytes),
+                            CMapToken::Word(name) => name.strip_prefix('/').and_then(glyph_char).map(String::from).unwrap_or_default(),
+                            _ => break,
+                        };
+                        if !source.is_empty() {
+                            map.chars.insert((source.len(), code_value(source)), text);
+                        }
+                        index += 2;
+                    }
+                }
+                "beginbfrange" => {
+                    while let (Some(CMapToken::Hex(low)), Some(CMapToken::Hex(high))) = (tokens.get(index), tokens.get(index + 1)) {
+                        let (first, last) = (code_value(low), code_value(high));
+                        index += 2;
+                        let target = match tokens.get(index) {
+                            Some(CMapToken::Hex(bytes)) => {
+                                index += 1;
+                                RangeTarget::Start(utf16_units(bytes))
+                            }
+                            Some(CMapToken::ArrayStart) => {
+                                index += 1;
+                                let mut each = Vec::new();
+                                while let Some(CMapToken::Hex(bytes)) = tokens.get(index) {
+                                    each.push(utf16_text(bytes));
+                                    index += 1;
+                                }
+                                index += 1;
+                                RangeTarget::Each(each)
+                            }
+                            _ => break,
+                        };
+                        if !low.is_empty() && first <= last {
+                            map.ranges.insert((low.len(), first), (last, target));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if map.code_lengths.is_empty() {
+            map.code_lengths = map.chars.keys().map(|&(length, _)| length)
+                .chain(map.ranges.keys().map(|&(length, _)| length))
+                .collect();
+            if map.code_lengths.is_empty() {
+                map.code_lengths.push(default_length);
+            }
+        }
+        map.code_lengths.sort_unstable();
+        map.code_lengths.dedup();
+        map
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chars.is_empty() && self.ranges.is_empty()
+    }
+
+    fn lookup(&self, length: usize, code: u32) -> Option<String> {
+        if let Some(text) = self.chars.get(&(length, code)) {
+            return Some(text.clone());
+        }
+        let (&(range_length, first), (last, target)) = self.ranges.range(..=(length, code)).next_back()?;
+        if range_length != length || code > *last {
+            return None;
+        }
+        let offset = code - first;
+        match target {
+            RangeTarget::Start(units) => {
+                let mut units = units.clone();
+                let last_unit = units.last_mut()?;
+                *last_unit = last_unit.wrapping_add(offset as u16);
+                Some(String::from_utf16_lossy(&units))
+            }
+            RangeTarget::Each(each) => each.get(offset as usize).cloned(),
+        }
+    }
+
+    /// Decode a string of codes, trying the shortest code length first and
+    /// falling back to the font's own encoding for unmapped single bytes
+    fn decode(&self, bytes: &[u8], fallback: Option<&ByteEncoding>) -> String {
+        let mut text = String::new();
+        let mut position = 0;
+        while position < bytes.len() {
+            let found = self.code_lengths.iter()
+                .filter(|&&length| length > 0 && position + length <= bytes.len())
+                .find_map(|&length| {
+                    self.lookup(length, code_value(&bytes[position..position + length])).map(|mapped| (length, mapped))
+                });
+            match found {
+                Some((length, mapped)) => {
+                    text.push_str(&mapped);
+                    position += length;
+                }
+                None => {
+                    if let Some(ch) = fallback.and_then(|encoding| encoding[bytes[position] as usize]) {
+                        text.push(ch);
+                    }
+                    position += self.code_lengths.first().copied().unwrap_or(1).max(1);
+                }
+            }
+        }
+        text
+    }
+}
+
+fn code_value(bytes: &[u8]) -> u32 {
+    bytes.iter().take(4).fold(0u32, |value, &byte| (value << 8) | byte as u32)
+}
+
+fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks(2).map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)])).collect()
+}
+
+fn utf16_text(bytes: &[u8]) -> String {
+    String::from_utf16_lossy(&utf16_units(bytes))
+}
+
+/// Split a CMap into hex strings, words and array brackets, skipping comments
+/// and dictionaries
+fn cmap_tokens(content: &[u8]) -> Vec<CMapToken> {
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < content.len() {
+        match content[index] {
+            b'%' => {
+                while index < content.len() && content[index] != b'\n' && content[index] != b'\r' {
+                    index += 1;
+                }
+            }
+            b'<' if content.get(index + 1) == Some(&b'<') => index += 2,
+            b'>' if content.get(index + 1) == Some(&b'>') => index += 2,
+            b'<' => {
+                let end = content[index..].iter().position(|&byte| byte == b'>').map_or(content.len(), |end| index + end);
+                let digits: Vec<u8> = content[index + 1..end].iter().copied().filter(u8::is_ascii_hexdigit).collect();
+                let bytes = digits.chunks(2)
+                    .map(|pair| {
+                        let text = String::from_utf8_lossy(pair);
+                        // An odd final digit is followed by an implied zero
+                        u8::from_str_radix(&format!("{:0<2}", text), 16).unwrap_or(0)
+                    })
+                    .collect();
+                tokens.push(CMapToken::Hex(bytes));
+                index = end + 1;
+            }
+            b'[' => {
+                tokens.push(CMapToken::ArrayStart);
+                index += 1;
+            }
+            b']' => {
+                tokens.push(CMapToken::ArrayEnd);
+                index += 1;
+            }
+            byte if byte.is_ascii_whitespace() => index += 1,
+            _ => {
+                let start = index;
+                index += 1;
+                while index < content.len() && !content[index].is_ascii_whitespace() && !b"<>[]/%".contains(&content[index]) {
+                    index += 1;
+                }
+                tokens.push(CMapToken::Word(String::from_utf8_lossy(&content[start..index]).into_owned()));
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    #[test]
+    fn test_parse_to_unicode_map() {
+        let cmap = b"/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+            /CMapName /Adobe-Identity-UCS def\n1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n\
+            2 beginbfchar\n<0003> <0020>\n<0011> <00E9>\nendbfchar\n\
+            2 beginbfrange\n<0024> <0026> <0041>\n<0030> <0031> [<0066006C> <2014>]\nendbfrange\n\
+            endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend";
+        let map = ToUnicodeMap::parse(cmap, 2);
+        assert_eq!(map.code_lengths, vec![2]);
+        assert_eq!(map.decode(&[0, 0x24, 0, 0x26, 0, 3, 0, 0x11, 0, 0x30, 0, 0x31], None), "AC é\u{66}\u{6C}\u{2014}");
+        // Unmapped codes are dropped
+        assert_eq!(map.decode(&[0, 0x99, 0, 0x25], None), "B");
+
+        // Empty codes are ignored rather than matched at every position
+        let map = ToUnicodeMap::parse(b"1 begincodespacerange <> <FF> endcodespacerange 1 beginbfchar <> <0041> endbfchar", 1);
+        assert_eq!(map.code_lengths, vec![1]);
+        assert_eq!(map.decode(b"xy", None), "");
+        let map = ToUnicodeMap::parse(b"1 beginbfchar <> <0041> endbfchar 1 beginbfrange <> <01> <0042> endbfrange", 1);
+        assert!(map.is_empty());
+        assert_eq!(map.decode(b"xy", None), "");
+    }
+
+    #[test]
+    fn test_byte_encoding_differences() {
+        let document = Document::new();
+        let encoding = Object::Dictionary(dictionary! {
+            "BaseEncoding" => "WinAnsiEncoding",
+            "Differences" => vec![Object::Integer(0x41), "uni00C9".into(), "quoteright".into()],
+        });
+        let table = byte_encoding(&document, Some(&encoding));
+        assert_eq!(table[0x41], Some('\u{C9}'));
+        assert_eq!(table[0x42], Some('\u{2019}'));
+        assert_eq!(table[0x43], Some('C'));
+        assert_eq!(table[0x80], Some('\u{20AC}'));
+    }
+}
@@ -9,6 +9,7 @@ use quick_xml::Reader;
 use quick_xml::events::Event;
 use crate::cache_system::CacheManager;
 use crate::impl_cacheable_content;
+use crate::odf_parser::{is_odf_presentation, read_odp_slides};
 
 /// Cache for storing extracted PowerPoint content
 #[derive(Debug, Clone)]
@@ -247,6 +248,18 @@ fn extract_powerpoint_slides(file_path: &str, slide_numbers: &[usize]) -> Result
 
 /// Extract text from PowerPoint file by manually parsing PPTX structure
 pub fn extract_powerpoint_text_manual(file_path: &str) -> Result<(String, HashMap<usize, String>)> {
+    if let Some(slides) = read_odp_file(file_path)? {
+        let mut slide_texts = HashMap::new();
+        let mut all_text = String::new();
+        for (index, slide_text) in slides.into_iter().enumerate() {
+            if !slide_text.trim().is_empty() {
+                all_text.push_str(&format!("## Slide {}\n\n{}\n\n", index + 1, slide_text));
+            }
+            slide_texts.insert(index + 1, slide_text);
+        }
+        return Ok((all_text, slide_texts));
+    }
+    
     let file = File::open(file_path)
         .with_context(|| format!("Failed to open PowerPoint file: {}", file_path))?;
     
@@ -300,6 +313,22 @@ pub fn extract_powerpoint_text_manual(file_path: &str) -> Result<(String, HashMa
     Ok((all_text, slide_texts))
 }
 
+/// Read the slides of an OpenDocument presentation; `None` for other files
+fn read_odp_file(file_path: &str) -> Result<Option<Vec<String>>> {
+    let mut buffer = Vec::new();
+    File::open(file_path)
+        .with_context(|| format!("Failed to open PowerPoint file: {}", file_path))?
+        .read_to_end(&mut buffer)
+        .with_context(|| "Failed to read PowerPoint file content")?;
+    
+    if !is_odf_presentation(&buffer) {
+        return Ok(None);
+    }
+    read_odp_slides(&buffer)
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("Failed to parse ODP content: {}", e))
+}
+
 /// Extract slide number from slide file name
 fn extract_slide_number(filename: &str) -> usize {
     // Extract number from "ppt/slides/slide1.xml" format
@@ -357,6 +386,10 @@ fn extract_text_from_slide_xml(xml_content: &str) -> Result<String> {
 
 /// Get PowerPoint slide count
 pub fn get_powerpoint_slide_count(file_path: &str) -> Result<usize> {
+    if let Some(slides) = read_odp_file(file_path)? {
+        return Ok(slides.len());
+    }
+    
     let file = File::open(file_path)
         .with_context(|| format!("Failed to open PowerPoint file: {}", file_path))?;
     
@@ -786,10 +819,15 @@ pub fn get_powerpoint_slide_info(resolved_file_path: &str) -> PowerPointPageInfo
     match POWERPOINT_CACHE_MANAGER.get_or_cache(resolved_file_path, extract_powerpoint_content) {
         Ok(powerpoint_cache) => {
             let slide_count = powerpoint_cache.total_slides.unwrap_or(0);
+            let file_kind = if resolved_file_path.to_lowercase().ends_with(".odp") {
+                "OpenDocument presentation"
+            } else {
+                "PowerPoint file"
+            };
             PowerPointPageInfoResult::success(
                 file_path_string,
                 Some(slide_count),
-                format!("PowerPoint file with {} slides", slide_count),
+                format!("{} with {} slides", file_kind, slide_count),
             )
         },
         Err(e) => PowerPointPageInfoResult::error(
@@ -5,6 +5,24 @@ use crate::fast_pdf_extractor::FastPdfExtractor;
 use crate::cache_system::CacheManager;
 use crate::impl_cacheable_content;
 
+/// Markdown produced by a tool for one file, or the error that stopped it
+#[derive(Debug, Clone)]
+pub struct FileTextResult {
+    pub file_path: String,
+    pub content: String,
+    pub error: Option<String>,
+}
+
+impl FileTextResult {
+    pub fn success(file_path: String, content: String) -> Self {
+        Self { file_path, content, error: None }
+    }
+
+    pub fn error(file_path: String, error: String) -> Self {
+        Self { file_path, content: String::new(), error: Some(error) }
+    }
+}
+
 /// Cache for storing extracted PDF content to avoid re-parsing
 #[derive(Debug, Clone)]
 pub struct PdfCache {
@@ -88,6 +106,12 @@ pub fn clear_excel_cache() {
     EXCEL_CACHE_MANAGER.clear();
 }
 
+/// Clear the CSV cache
+pub fn clear_csv_cache() {
+    use crate::csv_parser::CSV_CACHE_MANAGER;
+    CSV_CACHE_MANAGER.clear();
+}
+
 /// Clear the DOCX cache
 pub fn clear_docx_cache() {
     use crate::document_parser::DOCX_CACHE_MANAGER;
@@ -104,6 +128,7 @@ pub fn clear_powerpoint_cache() {
 pub fn clear_all_caches() {
     clear_pdf_cache();
     clear_excel_cache();
+    clear_csv_cache();
     clear_docx_cache();
     clear_powerpoint_cache();
 }
@@ -117,14 +142,16 @@ pub fn get_cache_stats() -> (usize, usize) {
 pub fn get_all_cache_stats() -> (usize, usize) {
     use crate::document_parser::{EXCEL_CACHE_MANAGER, DOCX_CACHE_MANAGER};
     use crate::powerpoint_parser::POWERPOINT_CACHE_MANAGER;
+    use crate::csv_parser::CSV_CACHE_MANAGER;
     
     let (pdf_files, pdf_memory) = PDF_CACHE_MANAGER.get_stats();
     let (excel_files, excel_memory) = EXCEL_CACHE_MANAGER.get_stats();
+    let (csv_files, csv_memory) = CSV_CACHE_MANAGER.get_stats();
     let (docx_files, docx_memory) = DOCX_CACHE_MANAGER.get_stats();
     let (ppt_files, ppt_memory) = POWERPOINT_CACHE_MANAGER.get_stats();
     
-    let total_files = pdf_files + excel_files + docx_files + ppt_files;
-    let total_memory = pdf_memory + excel_memory + docx_memory + ppt_memory;
+    let total_files = pdf_files + excel_files + csv_files + docx_files + ppt_files;
+    let total_memory = pdf_memory + excel_memory + csv_memory + docx_memory + ppt_memory;
     
     (total_files, total_memory)
 }
@@ -245,7 +272,7 @@ pub fn validate_file_path(resolved_path: &str) -> Result<String, String> {
     match extension {
         Some(ext) => {
             match ext.as_str() {
-                "pdf" | "xlsx" | "xls" | "docx" | "doc" | "pptx" | "ppt" => Ok(ext),
+                "pdf" | "xlsx" | "xlsm" | "xlsb" | "xls" | "xla" | "ods" | "csv" | "tsv" | "docx" | "doc" | "odt" | "pptx" | "ppt" | "odp" => Ok(ext),
                 _ => Err(format!("Unsupported file type: .{}", ext)),
             }
         },
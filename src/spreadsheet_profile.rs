@@ -0,0 +1,298 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Result;
+use calamine::{Data, Range, Reader, SheetVisible};
+
+use crate::csv_parser::CsvTable;
+use crate::document_parser::open_excel_workbook;
+use crate::excel_parser::{column_name, sheet_title, sheet_visibility, used_area, CellFormatter, WorkbookFormats};
+use crate::markdown_table::text_table;
+use crate::number_format::format_general;
+use crate::shared_utils::{parse_pages_parameter, validate_file_path, FileTextResult};
+
+/// Number of distinct sample values shown per column
+const SAMPLE_COUNT: usize = 3;
+
+/// Longest sample value shown, in characters
+const SAMPLE_LENGTH: usize = 40;
+
+/// Kind of value a cell holds, in the order kinds are listed for mixed columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CellKind {
+    Number,
+    Date,
+    Boolean,
+    Text,
+    Error,
+}
+
+impl CellKind {
+    const ALL: [CellKind; 5] = [Self::Number, Self::Date, Self::Boolean, Self::Text, Self::Error];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Number => "number",
+            Self::Date => "date",
+            Self::Boolean => "boolean",
+            Self::Text => "text",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Statistics gathered for one column of a sheet, below its header row
+#[derive(Debug, Default)]
+struct ColumnProfile {
+    kinds: [usize; 5],
+    nulls: usize,
+    distinct: HashSet<String>,
+    /// Whether any number seen has a fractional part
+    fractional: bool,
+    number_min: Option<f64>,
+    number_max: Option<f64>,
+    number_sum: f64,
+    /// Earliest and latest dates as date serials with their ISO 8601 text
+    date_min: Option<(f64, String)>,
+    date_max: Option<(f64, String)>,
+    samples: Vec<String>,
+}
+
+impl ColumnProfile {
+    fn add(&mut self, formatter: &CellFormatter, position: (u32, u32), cell: &Data) {
+        let value = formatter.typed_value(position, cell);
+        if value.is_null() {
+            self.nulls += 1;
+            return;
+        }
+
+        let serial = match cell {
+            Data::Int(number) => Some(*number as f64),
+            Data::Float(number) => Some(*number),
+            Data::DateTime(datetime) => Some(datetime.as_f64()),
+            _ => None,
+        };
+        let kind = match cell {
+            _ if formatter.is_date(position, cell) => CellKind::Date,
+            Data::Int(_) | Data::Float(_) => CellKind::Number,
+            Data::Bool(_) => CellKind::Boolean,
+            Data::Error(_) => CellKind::Error,
+            _ => CellKind::Text,
+        };
+        self.kinds[kind as usize] += 1;
+
+        match (kind, serial) {
+            (CellKind::Number, Some(number)) => {
+                self.fractional |= number.fract() != 0.0;
+                self.number_min = Some(self.number_min.map_or(number, |min| min.min(number)));
+                self.number_max = Some(self.number_max.map_or(number, |max| max.max(number)));
+                self.number_sum += number;
+            }
+            (CellKind::Date, Some(serial)) => {
+                let text = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+                if self.date_min.as_ref().is_none_or(|(min, _)| serial < *min) {
+                    self.date_min = Some((serial, text.clone()));
+                }
+                if self.date_max.as_ref().is_none_or(|(max, _)| serial > *max) {
+                    self.date_max = Some((serial, text));
+                }
+            }
+            _ => {}
+        }
+
+        if self.distinct.insert(value.to_string()) && self.samples.len() < SAMPLE_COUNT {
+            let display = formatter.format(position, cell);
+            self.samples.push(match display.char_indices().nth(SAMPLE_LENGTH) {
+                Some((end, _)) => format!("{}…", &display[..end]),
+                None => display,
+            });
+        }
+    }
+
+    /// The inferred type: a single kind, or the mix of kinds with their counts
+    fn inferred_type(&self) -> String {
+        let present: Vec<(CellKind, usize)> = CellKind::ALL.iter()
+            .map(|&kind| (kind, self.kinds[kind as usize]))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        match present.as_slice() {
+            [] => "empty".to_string(),
+            [(CellKind::Number, _)] if !self.fractional => "integer".to_string(),
+            [(kind, _)] => kind.name().to_string(),
+            mixed => format!(
+                "mixed ({})",
+                mixed.iter().map(|(kind, count)| format!("{} {}", kind.name(), count)).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+
+    fn row(&self, column: String, name: String) -> Vec<String> {
+        let numbers = self.kinds[CellKind::Number as usize];
+        let (min, max, mean) = match (self.number_min, self.number_max, &self.date_min, &self.date_max) {
+            (Some(min), Some(max), _, _) => (
+                format_general(min),
+                format_general(max),
+                format_general(self.number_sum / numbers as f64),
+            ),
+            (None, None, Some((_, earliest)), Some((_, latest))) => (earliest.clone(), latest.clone(), String::new()),
+            _ => (String::new(), String::new(), String::new()),
+        };
+        vec![
+            column,
+            name,
+            self.inferred_type(),
+            self.nulls.to_string(),
+            self.distinct.len().to_string(),
+            min,
+            max,
+            mean,
+            self.samples.join(", "),
+        ]
+    }
+}
+
+/// Profile the columns of a sheet, taking its first used row as the header
+fn profile_range(range: &Range<Data>, formatter: &CellFormatter) -> String {
+    let Some(area) = used_area(range) else {
+        return "Empty sheet".to_string();
+    };
+    let (header_row, last_row) = (area.start.0, area.end.0);
+    let mut rows = vec![
+        ["Column", "Name", "Type", "Nulls", "Distinct", "Min", "Max", "Mean", "Samples"]
+            .map(str::to_string)
+            .to_vec(),
+    ];
+    for column in area.start.1..=area.end.1 {
+        let mut profile = ColumnProfile::default();
+        for row in header_row + 1..=last_row {
+            profile.add(formatter, (row, column), range.get_value((row, column)).unwrap_or(&Data::Empty));
+        }
+        let header = formatter.format((header_row, column), range.get_value((header_row, column)).unwrap_or(&Data::Empty));
+        rows.push(profile.row(column_name(column), header));
+    }
+
+    format!(
+        "{} data rows x {} columns, header in row {}\n\n{}",
+        last_row - header_row,
+        area.end.1 - area.start.1 + 1,
+        header_row + 1,
+        text_table(rows)
+    )
+}
+
+/// Profile each column of the selected sheets of a spreadsheet, or of a CSV or
+/// TSV file, without returning its rows: the inferred type, null and distinct
+/// counts, min/max/mean for numbers, the date span for dates and sample values.
+/// Hidden sheets are only profiled when selected by `pages`.
+pub fn profile_spreadsheet(resolved_file_path: &str, pages: Option<&str>) -> FileTextResult {
+    let file_path = resolved_file_path.to_string();
+    let pages = pages.unwrap_or("all");
+    let result = match validate_file_path(resolved_file_path) {
+        Ok(ext) if matches!(ext.as_str(), "xlsx" | "xlsm" | "xlsb" | "xls" | "xla" | "ods") => {
+            profile_workbook(resolved_file_path, pages)
+        }
+        Ok(ext) if matches!(ext.as_str(), "csv" | "tsv") => profile_delimited(resolved_file_path, pages),
+        Ok(ext) => return FileTextResult::error(
+            file_path,
+            format!("Column profiles are only available for spreadsheets and CSV/TSV files, not {} files", ext),
+        ),
+        Err(e) => return FileTextResult::error(file_path, e),
+    };
+
+    match result {
+        Ok(content) => FileTextResult::success(file_path, content),
+        Err(e) => FileTextResult::error(file_path, format!("Failed to profile spreadsheet: {}", e)),
+    }
+}
+
+fn profile_workbook(file_path: &str, pages: &str) -> Result<String> {
+    let mut workbook = open_excel_workbook(file_path)?;
+    let sheet_names = workbook.sheet_names().to_owned();
+    let visibility = sheet_visibility(&workbook);
+    let formats = WorkbookFormats::open(file_path);
+
+    let mut sheet_numbers = parse_pages_parameter(pages, sheet_names.len()).map_err(anyhow::Error::msg)?;
+    if pages.trim().is_empty() || pages.trim().eq_ignore_ascii_case("all") {
+        sheet_numbers.retain(|&sheet| visibility[sheet - 1] == SheetVisible::Visible);
+    }
+
+    let mut markdown = format!("# {}\n\n", file_name(file_path));
+    for sheet_number in sheet_numbers {
+        let sheet_name = &sheet_names[sheet_number - 1];
+        let range = workbook.worksheet_range(sheet_name)
+            .map_err(|e| anyhow::anyhow!("Failed to read sheet '{}': {}", sheet_name, e))?;
+        markdown.push_str(&format!(
+            "## Sheet {}: {}\n\n{}\n\n",
+            sheet_number,
+            sheet_title(sheet_name, visibility[sheet_number - 1]),
+            profile_range(&range, &formats.sheet(sheet_name))
+        ));
+    }
+    Ok(markdown)
+}
+
+fn profile_delimited(file_path: &str, pages: &str) -> Result<String> {
+    parse_pages_parameter(pages, 1).map_err(anyhow::Error::msg)?;
+    let table = CsvTable::read(file_path)?;
+    Ok(format!(
+        "# {}\n\n## Sheet 1: {}\n\n{}\n\n",
+        file_name(file_path),
+        table.name,
+        profile_range(&table.typed_range(), &CellFormatter::default())
+    ))
+}
+
+fn file_name(file_path: &str) -> String {
+    Path::new(file_path).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::excel_parser::tests::XlsxBuilder;
+    use std::io::Write;
+
+    #[test]
+    fn test_profile_workbook_columns() {
+        let file = XlsxBuilder::new()
+            .sheet("Orders", vec![
+                vec!["Item", "Qty", "Ordered", "Note"],
+                vec!["Pen", "3", "45122", "rush"],
+                vec!["Ink", "2.5", "45100", ""],
+                vec!["Pen", "4", "45130.5", "12"],
+            ])
+            .number_format("C2", "yyyy-mm-dd")
+            .number_format("C3", "yyyy-mm-dd")
+            .number_format("C4", "yyyy-mm-dd hh:mm")
+            .sheet("Secret", vec![vec!["Key"], vec!["x"]])
+            .sheet_state("hidden")
+            .file();
+        let path = file.path().to_str().unwrap();
+
+        let result = profile_spreadsheet(path, None);
+        assert!(result.error.is_none(), "{:?}", result.error);
+        let content = result.content;
+        assert!(content.contains("## Sheet 1: Orders\n\n3 data rows x 4 columns, header in row 1\n\n"), "{}", content);
+        assert!(content.contains("| A | Item | text | 0 | 2 |  |  |  | Pen, Ink |"), "{}", content);
+        assert!(content.contains("| B | Qty | number | 0 | 3 | 2.5 | 4 | 3.166666667 | 3, 2.5, 4 |"), "{}", content);
+        assert!(content.contains("| C | Ordered | date | 0 | 3 | 2023-06-23 | 2023-07-23T12:00:00 |  |"), "{}", content);
+        assert!(content.contains("| D | Note | mixed (number 1, text 1) | 1 | 2 |"), "{}", content);
+        assert!(!content.contains("Secret"));
+
+        let hidden = profile_spreadsheet(path, Some("2")).content;
+        assert!(hidden.contains("## Sheet 2: Secret (hidden)"), "{}", hidden);
+    }
+
+    #[test]
+    fn test_profile_csv_and_unsupported_files() {
+        let mut file = tempfile::NamedTempFile::with_suffix(".csv").unwrap();
+        file.write_all(b"id,active\n1,true\n2,\n3,false\n").unwrap();
+        let content = profile_spreadsheet(file.path().to_str().unwrap(), None).content;
+        assert!(content.contains("| A | id | integer | 0 | 3 | 1 | 3 | 2 | 1, 2, 3 |"), "{}", content);
+        assert!(content.contains("| B | active | boolean | 1 | 2 |  |  |  | TRUE, FALSE |"), "{}", content);
+
+        let docx = tempfile::NamedTempFile::with_suffix(".docx").unwrap();
+        let error = profile_spreadsheet(docx.path().to_str().unwrap(), None).error.unwrap();
+        assert!(error.contains("only available for spreadsheets"), "{}", error);
+    }
+}
@@ -0,0 +1,1395 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use calamine::{Data, Range, Reader, SheetVisible};
+use serde_json::Value;
+
+use crate::csv_parser::CsvTable;
+use crate::document_parser::open_excel_workbook;
+use crate::excel_parser::{
+    column_name, read_excel_tables, resolve_range_parameter, sheet_visibility, used_area, CellFormatter,
+    CellRange, WorkbookFormats,
+};
+use crate::markdown_table::text_table;
+use crate::shared_utils::{validate_file_path, FileTextResult};
+
+/// Most result rows returned; larger results are cut off with a note
+const MAX_RESULT_ROWS: usize = 500;
+
+/// Deepest nesting of parentheses, NOT and unary minus accepted in a query
+const MAX_NESTING_DEPTH: usize = 256;
+
+/// Words with a meaning in the query dialect, which cannot be used as bare
+/// column names or aliases
+const KEYWORDS: [&str; 23] = [
+    "SELECT", "DISTINCT", "FROM", "WHERE", "GROUP", "BY", "HAVING", "ORDER", "ASC", "DESC", "LIMIT",
+    "OFFSET", "AS", "AND", "OR", "NOT", "IS", "NULL", "LIKE", "IN", "BETWEEN", "TRUE", "FALSE",
+];
+
+/// A value of a cell or of an expression
+#[derive(Debug, Clone, PartialEq)]
+enum QueryValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    /// Text, including dates and times in ISO 8601
+    Text(String),
+}
+
+impl QueryValue {
+    fn from_json(value: Value) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::Bool(value) => Self::Bool(value),
+            Value::Number(number) => number.as_f64().map_or(Self::Null, Self::Number),
+            Value::String(text) => Self::Text(text),
+            other => Self::Text(other.to_string()),
+        }
+    }
+
+    fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// The value as a number; text is converted when it reads as one
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Number(number) => Some(*number),
+            Self::Text(text) => text.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The value in a boolean context; unknown for nulls and text
+    fn truth(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            Self::Number(number) => Some(*number != 0.0),
+            _ => None,
+        }
+    }
+
+    /// Display text, as shown in result tables
+    fn text(&self) -> String {
+        match self {
+            Self::Null => String::new(),
+            Self::Bool(value) => if *value { "TRUE" } else { "FALSE" }.to_string(),
+            Self::Number(number) => format_number(*number),
+            Self::Text(text) => text.clone(),
+        }
+    }
+
+    /// A key that is equal for equal values, for grouping and DISTINCT
+    fn key(&self) -> String {
+        match self {
+            Self::Null => "n".to_string(),
+            Self::Bool(value) => format!("b{}", value),
+            Self::Number(number) => format!("d{}", number),
+            Self::Text(text) => format!("t{}", text),
+        }
+    }
+
+    /// Compare two values for a condition; unknown when either is null or
+    /// the values cannot be compared
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Null, _) | (_, Self::Null) => None,
+            (Self::Number(a), Self::Number(b)) => a.partial_cmp(b),
+            (Self::Text(a), Self::Text(b)) => Some(a.cmp(b)),
+            (Self::Bool(a), Self::Bool(b)) => Some(a.cmp(b)),
+            (Self::Number(_), Self::Text(_)) | (Self::Text(_), Self::Number(_)) => {
+                self.as_number()?.partial_cmp(&other.as_number()?)
+            }
+            _ => None,
+        }
+    }
+
+    /// Total order for sorting: nulls first, then booleans, numbers and text
+    fn sort_order(&self, other: &Self) -> Ordering {
+        let rank = |value: &Self| match value {
+            Self::Null => 0,
+            Self::Bool(_) => 1,
+            Self::Number(_) => 2,
+            Self::Text(_) => 3,
+        };
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.total_cmp(b),
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+
+/// A number without trailing zeros, rounded to 10 decimals
+fn format_number(number: f64) -> String {
+    if number.fract() == 0.0 && number.abs() < 1e15 {
+        return format!("{}", number as i64);
+    }
+    let text = format!("{:.10}", number);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Concat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Year,
+    Month,
+    Day,
+    Quarter,
+    Lower,
+    Upper,
+    Length,
+    Trim,
+    Round,
+    Abs,
+    Coalesce,
+}
+
+impl Function {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_uppercase().as_str() {
+            "YEAR" => Self::Year,
+            "MONTH" => Self::Month,
+            "DAY" => Self::Day,
+            "QUARTER" => Self::Quarter,
+            "LOWER" => Self::Lower,
+            "UPPER" => Self::Upper,
+            "LENGTH" | "LEN" => Self::Length,
+            "TRIM" => Self::Trim,
+            "ROUND" => Self::Round,
+            "ABS" => Self::Abs,
+            "COALESCE" => Self::Coalesce,
+            _ => return None,
+        })
+    }
+
+    /// Smallest and largest number of arguments
+    fn arity(self) -> (usize, usize) {
+        match self {
+            Self::Round => (1, 2),
+            Self::Coalesce => (1, usize::MAX),
+            _ => (1, 1),
+        }
+    }
+
+    fn apply(self, args: &[QueryValue]) -> QueryValue {
+        let first = &args[0];
+        match self {
+            Self::Year | Self::Month | Self::Day | Self::Quarter => {
+                let QueryValue::Text(text) = first else {
+                    return QueryValue::Null;
+                };
+                let part = |range: std::ops::Range<usize>| text.get(range).and_then(|part| part.parse::<f64>().ok());
+                // Dates are ISO 8601 text such as `2024-07-15` or `2024-07-15T10:30:00`
+                if text.len() < 10 || text.as_bytes()[4] != b'-' || text.as_bytes()[7] != b'-' {
+                    return QueryValue::Null;
+                }
+                let value = match self {
+                    Self::Year => part(0..4),
+                    Self::Month => part(5..7),
+                    Self::Day => part(8..10),
+                    _ => part(5..7).map(|month| ((month - 1.0) / 3.0).floor() + 1.0),
+                };
+                value.map_or(QueryValue::Null, QueryValue::Number)
+            }
+            Self::Lower | Self::Upper | Self::Length | Self::Trim => {
+                if first.is_null() {
+                    return QueryValue::Null;
+                }
+                let text = first.text();
+                match self {
+                    Self::Lower => QueryValue::Text(text.to_lowercase()),
+                    Self::Upper => QueryValue::Text(text.to_uppercase()),
+                    Self::Length => QueryValue::Number(text.chars().count() as f64),
+                    _ => QueryValue::Text(text.trim().to_string()),
+                }
+            }
+            Self::Round => {
+                let digits = args.get(1).and_then(QueryValue::as_number).unwrap_or(0.0);
+                let scale = 10f64.powi(digits as i32);
+                first.as_number().map_or(QueryValue::Null, |number| QueryValue::Number((number * scale).round() / scale))
+            }
+            Self::Abs => first.as_number().map_or(QueryValue::Null, |number| QueryValue::Number(number.abs())),
+            Self::Coalesce => args.iter().find(|value| !value.is_null()).cloned().unwrap_or(QueryValue::Null),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    /// A column by name, before it is resolved to its index
+    Column(String),
+    Index(usize),
+    Literal(QueryValue),
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+    IsNull { expr: Box<Expr>, negated: bool },
+    Like { expr: Box<Expr>, pattern: Box<Expr>, negated: bool },
+    In { expr: Box<Expr>, list: Vec<Expr>, negated: bool },
+    Between { expr: Box<Expr>, low: Box<Expr>, high: Box<Expr>, negated: bool },
+    Function(Function, Vec<Expr>),
+    /// An aggregate over a group; no argument stands for `COUNT(*)`
+    Aggregate { function: Aggregate, arg: Option<Box<Expr>>, distinct: bool },
+}
+
+impl Expr {
+    fn children(&self) -> Vec<&Expr> {
+        match self {
+            Self::Column(_) | Self::Index(_) | Self::Literal(_) => Vec::new(),
+            Self::Negate(expr) | Self::Not(expr) | Self::IsNull { expr, .. } => vec![expr],
+            Self::Binary(left, _, right) => vec![left, right],
+            Self::Like { expr, pattern, .. } => vec![expr, pattern],
+            Self::In { expr, list, .. } => std::iter::once(&**expr).chain(list).collect(),
+            Self::Between { expr, low, high, .. } => vec![expr, low, high],
+            Self::Function(_, args) => args.iter().collect(),
+            Self::Aggregate { arg, .. } => arg.iter().map(|arg| &**arg).collect(),
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Expr> {
+        match self {
+            Self::Column(_) | Self::Index(_) | Self::Literal(_) => Vec::new(),
+            Self::Negate(expr) | Self::Not(expr) | Self::IsNull { expr, .. } => vec![expr],
+            Self::Binary(left, _, right) => vec![left, right],
+            Self::Like { expr, pattern, .. } => vec![expr, pattern],
+            Self::In { expr, list, .. } => std::iter::once(&mut **expr).chain(list.iter_mut()).collect(),
+            Self::Between { expr, low, high, .. } => vec![expr, low, high],
+            Self::Function(_, args) => args.iter_mut().collect(),
+            Self::Aggregate { arg, .. } => arg.iter_mut().map(|arg| &mut **arg).collect(),
+        }
+    }
+
+    fn contains_aggregate(&self) -> bool {
+        matches!(self, Self::Aggregate { .. }) || self.children().into_iter().any(Expr::contains_aggregate)
+    }
+
+    /// Replace column names by their index in the source table
+    fn resolve(&mut self, columns: &Columns) -> Result<()> {
+        if let Self::Column(name) = self {
+            *self = Self::Index(columns.find(name)?);
+            return Ok(());
+        }
+        if let Self::Aggregate { arg: Some(arg), .. } = self
+            && arg.contains_aggregate()
+        {
+            bail!("Aggregate functions cannot be nested");
+        }
+        self.children_mut().into_iter().try_for_each(|child| child.resolve(columns))
+    }
+}
+
+/// An expression of the SELECT list, with the text it was written as
+#[derive(Debug, Clone)]
+enum SelectItem {
+    Wildcard,
+    Expr { expr: Expr, label: String },
+}
+
+/// A sort key of ORDER BY: an output column, by alias or position, or an expression
+#[derive(Debug, Clone)]
+enum SortKey {
+    Output(usize),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Query {
+    distinct: bool,
+    select: Vec<SelectItem>,
+    from: Option<String>,
+    filter: Option<Expr>,
+    group_by: Vec<Expr>,
+    having: Option<Expr>,
+    order_by: Vec<(Expr, bool)>,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    /// A bare word: a keyword, column, alias or function name
+    Word(String),
+    /// A column or sheet name in double quotes, brackets or backticks
+    Quoted(String),
+    Text(String),
+    Number(f64),
+    Symbol(&'static str),
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+const SYMBOLS: [&str; 17] = ["<=", ">=", "<>", "!=", "||", "=", "<", ">", "+", "-", "*", "/", "%", ",", "(", ")", ";"];
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let kind = match ch {
+            '\'' | '"' | '`' | '[' => {
+                let close = match ch {
+                    '[' => ']',
+                    other => other,
+                };
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        // A doubled closing quote stands for the quote itself
+                        Some((_, c)) if c == close && close != ']' && chars.peek().map(|&(_, next)| next) == Some(close) => {
+                            chars.next();
+                            text.push(close);
+                        }
+                        Some((_, c)) if c == close => break,
+                        Some((_, c)) => text.push(c),
+                        None => bail!("Unterminated {} starting at position {}", if ch == '\'' { "string" } else { "quoted name" }, start + 1),
+                    }
+                }
+                if ch == '\'' { TokenKind::Text(text) } else { TokenKind::Quoted(text) }
+            }
+            c if c.is_ascii_digit() || (c == '.' && source[start + 1..].starts_with(|next: char| next.is_ascii_digit())) => {
+                let mut end = start;
+                while let Some(&(index, c)) = chars.peek() {
+                    let exponent_sign = (c == '+' || c == '-') && source[..index].ends_with(['e', 'E']);
+                    if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign {
+                        end = index + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let text = &source[start..end];
+                TokenKind::Number(text.parse().map_err(|_| anyhow!("Invalid number '{}'", text))?)
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut end = start;
+                while let Some(&(index, c)) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        end = index + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                TokenKind::Word(source[start..end].to_string())
+            }
+            _ => {
+                let symbol = SYMBOLS.iter()
+                    .find(|symbol| source[start..].starts_with(*symbol))
+                    .ok_or_else(|| anyhow!("Unexpected character '{}' at position {}", ch, start + 1))?;
+                for _ in 0..symbol.len() {
+                    chars.next();
+                }
+                TokenKind::Symbol(symbol)
+            }
+        };
+        let end = chars.peek().map_or(source.len(), |&(index, _)| index);
+        tokens.push(Token { kind, start, end });
+    }
+    tokens.push(Token { kind: TokenKind::End, start: source.len(), end: source.len() });
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+    /// Nesting level of the expression being parsed
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Result<Self> {
+        Ok(Self { source, tokens: tokenize(source)?, position: 0, depth: 0 })
+    }
+
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.position].kind
+    }
+
+    fn peek_at(&self, offset: usize) -> &TokenKind {
+        &self.tokens[(self.position + offset).min(self.tokens.len() - 1)].kind
+    }
+
+    fn advance(&mut self) -> TokenKind {
+        let kind = self.tokens[self.position].kind.clone();
+        if self.position < self.tokens.len() - 1 {
+            self.position += 1;
+        }
+        kind
+    }
+
+    fn is_keyword(kind: &TokenKind, keyword: &str) -> bool {
+        matches!(kind, TokenKind::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = Self::is_keyword(self.peek(), keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(keyword))
+        }
+    }
+
+    fn symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), TokenKind::Symbol(found) if *found == symbol);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+        if self.symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", symbol)))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> anyhow::Error {
+        let token = &self.tokens[self.position];
+        match token.kind {
+            TokenKind::End => anyhow!("Expected {} but the query ended", expected),
+            _ => anyhow!(
+                "Expected {} but found '{}' at position {}",
+                expected,
+                &self.source[token.start..token.end],
+                token.start + 1
+            ),
+        }
+    }
+
+    fn parse_query(&mut self) -> Result<Query> {
+        let mut query = Query::default();
+        self.expect_keyword("SELECT")?;
+        query.distinct = self.keyword("DISTINCT");
+        loop {
+            query.select.push(self.parse_select_item()?);
+            if !self.symbol(",") {
+                break;
+            }
+        }
+        if self.keyword("FROM") {
+            query.from = Some(self.parse_name("a sheet or table name")?);
+        }
+        if self.keyword("WHERE") {
+            query.filter = Some(self.parse_expr()?);
+        }
+        if self.keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            query.group_by = self.parse_expr_list()?;
+        }
+        if self.keyword("HAVING") {
+            query.having = Some(self.parse_expr()?);
+        }
+        if self.keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let expr = self.parse_expr()?;
+                let descending = self.keyword("DESC");
+                if !descending {
+                    self.keyword("ASC");
+                }
+                query.order_by.push((expr, descending));
+                if !self.symbol(",") {
+                    break;
+                }
+            }
+        }
+        if self.keyword("LIMIT") {
+            query.limit = Some(self.parse_count("LIMIT")?);
+            if self.keyword("OFFSET") {
+                query.offset = self.parse_count("OFFSET")?;
+            }
+        }
+        self.symbol(";");
+        if *self.peek() != TokenKind::End {
+            return Err(self.unexpected("the end of the query"));
+        }
+        Ok(query)
+    }
+
+    fn parse_count(&mut self, clause: &str) -> Result<usize> {
+        match self.advance() {
+            TokenKind::Number(number) if number >= 0.0 && number.fract() == 0.0 => Ok(number as usize),
+            _ => bail!("{} takes a whole number", clause),
+        }
+    }
+
+    fn parse_name(&mut self, expected: &str) -> Result<String> {
+        match self.peek().clone() {
+            TokenKind::Word(word) if !KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(&word)) => {
+                self.advance();
+                Ok(word)
+            }
+            TokenKind::Quoted(name) | TokenKind::Text(name) => {
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn parse_select_item(&mut self) -> Result<SelectItem> {
+        if self.symbol("*") {
+            return Ok(SelectItem::Wildcard);
+        }
+        let start = self.tokens[self.position].start;
+        let expr = self.parse_expr()?;
+        let end = self.tokens[self.position - 1].end;
+        let aliased = self.keyword("AS")
+            || (matches!(self.peek(), TokenKind::Word(_) | TokenKind::Quoted(_)) && !self.at_keyword());
+        let label = if aliased {
+            self.parse_name("an alias")?
+        } else {
+            match &expr {
+                Expr::Column(name) => name.clone(),
+                _ => self.source[start..end].to_string(),
+            }
+        };
+        Ok(SelectItem::Expr { expr, label })
+    }
+
+    fn at_keyword(&self) -> bool {
+        KEYWORDS.iter().any(|keyword| Self::is_keyword(self.peek(), keyword))
+    }
+
+    fn parse_expr_list(&mut self) -> Result<Vec<Expr>> {
+        let mut list = vec![self.parse_expr()?];
+        while self.symbol(",") {
+            list.push(self.parse_expr()?);
+        }
+        Ok(list)
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr> {
+        self.nested(|parser| {
+            let mut left = parser.parse_and()?;
+            while parser.keyword("OR") {
+                left = Expr::Binary(Box::new(left), BinaryOp::Or, Box::new(parser.parse_and()?));
+            }
+            Ok(left)
+        })
+    }
+
+    /// Parse one level deeper, refusing queries nested past `MAX_NESTING_DEPTH`
+    /// before they can exhaust the stack
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> Result<Expr>) -> Result<Expr> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            bail!("Query nests too deeply (more than {} levels)", MAX_NESTING_DEPTH);
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut left = self.parse_not()?;
+        while self.keyword("AND") {
+            left = Expr::Binary(Box::new(left), BinaryOp::And, Box::new(self.parse_not()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if self.keyword("NOT") {
+            return self.nested(|parser| Ok(Expr::Not(Box::new(parser.parse_not()?))));
+        }
+        self.parse_predicate()
+    }
+
+    fn parse_predicate(&mut self) -> Result<Expr> {
+        let expr = self.parse_additive()?;
+        let comparison = match self.peek() {
+            TokenKind::Symbol("=") => Some(BinaryOp::Equal),
+            TokenKind::Symbol("<>") | TokenKind::Symbol("!=") => Some(BinaryOp::NotEqual),
+            TokenKind::Symbol("<") => Some(BinaryOp::Less),
+            TokenKind::Symbol("<=") => Some(BinaryOp::LessOrEqual),
+            TokenKind::Symbol(">") => Some(BinaryOp::Greater),
+            TokenKind::Symbol(">=") => Some(BinaryOp::GreaterOrEqual),
+            _ => None,
+        };
+        if let Some(op) = comparison {
+            self.advance();
+            return Ok(Expr::Binary(Box::new(expr), op, Box::new(self.parse_additive()?)));
+        }
+        if self.keyword("IS") {
+            let negated = self.keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expr::IsNull { expr: Box::new(expr), negated });
+        }
+
+        let negated = Self::is_keyword(self.peek(), "NOT")
+            && ["LIKE", "IN", "BETWEEN"].iter().any(|keyword| Self::is_keyword(self.peek_at(1), keyword));
+        if negated {
+            self.advance();
+        }
+        let expr = Box::new(expr);
+        if self.keyword("LIKE") {
+            Ok(Expr::Like { expr, pattern: Box::new(self.parse_additive()?), negated })
+        } else if self.keyword("IN") {
+            self.expect_symbol("(")?;
+            let list = self.parse_expr_list()?;
+            self.expect_symbol(")")?;
+            Ok(Expr::In { expr, list, negated })
+        } else if self.keyword("BETWEEN") {
+            let low = Box::new(self.parse_additive()?);
+            self.expect_keyword("AND")?;
+            let high = Box::new(self.parse_additive()?);
+            Ok(Expr::Between { expr, low, high, negated })
+        } else {
+            Ok(*expr)
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                TokenKind::Symbol("+") => BinaryOp::Add,
+                TokenKind::Symbol("-") => BinaryOp::Subtract,
+                TokenKind::Symbol("||") => BinaryOp::Concat,
+                _ => return Ok(left),
+            };
+            self.advance();
+            left = Expr::Binary(Box::new(left), op, Box::new(self.parse_multiplicative()?));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                TokenKind::Symbol("*") => BinaryOp::Multiply,
+                TokenKind::Symbol("/") => BinaryOp::Divide,
+                TokenKind::Symbol("%") => BinaryOp::Modulo,
+                _ => return Ok(left),
+            };
+            self.advance();
+            left = Expr::Binary(Box::new(left), op, Box::new(self.parse_unary()?));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.symbol("-") {
+            return self.nested(|parser| Ok(Expr::Negate(Box::new(parser.parse_unary()?))));
+        }
+        self.symbol("+");
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        match self.peek().clone() {
+            TokenKind::Number(number) => {
+                self.advance();
+                Ok(Expr::Literal(QueryValue::Number(number)))
+            }
+            TokenKind::Text(text) => {
+                self.advance();
+                Ok(Expr::Literal(QueryValue::Text(text)))
+            }
+            TokenKind::Quoted(name) => {
+                self.advance();
+                Ok(Expr::Column(name))
+            }
+            TokenKind::Symbol("(") => {
+                self.advance();
+                let expr = self.parse_expr()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            TokenKind::Word(word) => {
+                if *self.peek_at(1) == TokenKind::Symbol("(") {
+                    self.advance();
+                    self.advance();
+                    return self.parse_call(&word);
+                }
+                match word.to_uppercase().as_str() {
+                    "NULL" => Ok(self.literal(QueryValue::Null)),
+                    "TRUE" => Ok(self.literal(QueryValue::Bool(true))),
+                    "FALSE" => Ok(self.literal(QueryValue::Bool(false))),
+                    _ if self.at_keyword() => Err(self.unexpected("a column, value or function")),
+                    _ => {
+                        self.advance();
+                        Ok(Expr::Column(word))
+                    }
+                }
+            }
+            _ => Err(self.unexpected("a column, value or function")),
+        }
+    }
+
+    fn literal(&mut self, value: QueryValue) -> Expr {
+        self.advance();
+        Expr::Literal(value)
+    }
+
+    /// Parse the arguments of a function call after its opening parenthesis
+    fn parse_call(&mut self, name: &str) -> Result<Expr> {
+        let aggregate = match name.to_uppercase().as_str() {
+            "COUNT" => Some(Aggregate::Count),
+            "SUM" => Some(Aggregate::Sum),
+            "AVG" => Some(Aggregate::Avg),
+            "MIN" => Some(Aggregate::Min),
+            "MAX" => Some(Aggregate::Max),
+            _ => None,
+        };
+        if let Some(function) = aggregate {
+            let distinct = self.keyword("DISTINCT");
+            let arg = if function == Aggregate::Count && !distinct && self.symbol("*") {
+                None
+            } else {
+                Some(Box::new(self.parse_expr()?))
+            };
+            self.expect_symbol(")")?;
+            return Ok(Expr::Aggregate { function, arg, distinct });
+        }
+
+        let function = Function::parse(name).ok_or_else(|| anyhow!(
+            "Unknown function '{}'. Available functions: COUNT, SUM, AVG, MIN, MAX, YEAR, MONTH, DAY, QUARTER, \
+             LOWER, UPPER, LENGTH, TRIM, ROUND, ABS, COALESCE",
+            name
+        ))?;
+        let args = if self.symbol(")") {
+            Vec::new()
+        } else {
+            let args = self.parse_expr_list()?;
+            self.expect_symbol(")")?;
+            args
+        };
+        let (min, max) = function.arity();
+        if args.len() < min || args.len() > max {
+            bail!("{} takes {} argument{}", name.to_uppercase(), if min == max { min.to_string() } else { format!("{} to {}", min, max) }, if max == 1 { "" } else { "s" });
+        }
+        Ok(Expr::Function(function, args))
+    }
+}
+
+/// Column names of a source table, from its header row
+#[derive(Debug, Clone)]
+struct Columns {
+    names: Vec<String>,
+    by_name: HashMap<String, usize>,
+}
+
+impl Columns {
+    fn new(names: Vec<String>) -> Self {
+        let mut by_name = HashMap::new();
+        for (index, name) in names.iter().enumerate() {
+            by_name.entry(name.to_lowercase()).or_insert(index);
+        }
+        Self { names, by_name }
+    }
+
+    /// The index of a column, matched case-insensitively
+    fn find(&self, name: &str) -> Result<usize> {
+        self.by_name.get(&name.to_lowercase()).copied().ok_or_else(|| anyhow!(
+            "Unknown column '{}'. Available columns: {}",
+            name,
+            self.names.join(", ")
+        ))
+    }
+}
+
+/// The rows a query runs over, with the header row giving the column names
+#[derive(Debug)]
+struct QueryTable {
+    title: String,
+    columns: Columns,
+    rows: Vec<Vec<QueryValue>>,
+}
+
+impl QueryTable {
+    /// Read the cells between two corners (absolute, inclusive); the first row is
+    /// the header, and rows without any value are left out
+    fn from_range(title: String, range: &Range<Data>, formatter: &CellFormatter, start: (u32, u32), end: (u32, u32)) -> Self {
+        let mut names: Vec<String> = Vec::new();
+        for column in start.1..=end.1 {
+            let header = formatter.format((start.0, column), range.get_value((start.0, column)).unwrap_or(&Data::Empty));
+            let base = match header.trim() {
+                "" => column_name(column),
+                name => name.to_string(),
+            };
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while names.iter().any(|existing| existing.eq_ignore_ascii_case(&name)) {
+                name = format!("{}_{}", base, suffix);
+                suffix += 1;
+            }
+            names.push(name);
+        }
+
+        let rows = (start.0 + 1..=end.0)
+            .map(|row| {
+                (start.1..=end.1)
+                    .map(|column| {
+                        let cell = range.get_value((row, column)).unwrap_or(&Data::Empty);
+                        QueryValue::from_json(formatter.typed_value((row, column), cell))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|row| row.iter().any(|value| !value.is_null()))
+            .collect();
+        Self { title, columns: Columns::new(names), rows }
+    }
+}
+
+/// What an expression is evaluated against: one row, or a group of rows
+#[derive(Clone, Copy)]
+enum Scope<'a> {
+    Row(&'a [QueryValue]),
+    Group(&'a [&'a Vec<QueryValue>]),
+}
+
+fn evaluate(expr: &Expr, scope: Scope) -> QueryValue {
+    match expr {
+        Expr::Column(_) => QueryValue::Null,
+        Expr::Index(index) => match scope {
+            Scope::Row(row) => row[*index].clone(),
+            // Columns outside aggregates take the value of the group's first row
+            Scope::Group(rows) => rows.first().map_or(QueryValue::Null, |row| row[*index].clone()),
+        },
+        Expr::Literal(value) => value.clone(),
+        Expr::Negate(expr) => evaluate(expr, scope).as_number().map_or(QueryValue::Null, |number| QueryValue::Number(-number)),
+        Expr::Not(expr) => evaluate(expr, scope).truth().map_or(QueryValue::Null, |value| QueryValue::Bool(!value)),
+        Expr::Binary(left, op, right) => evaluate_binary(evaluate(left, scope), *op, || evaluate(right, scope)),
+        Expr::IsNull { expr, negated } => QueryValue::Bool(evaluate(expr, scope).is_null() != *negated),
+        Expr::Like { expr, pattern, negated } => {
+            let (value, pattern) = (evaluate(expr, scope), evaluate(pattern, scope));
+            if value.is_null() || pattern.is_null() {
+                return QueryValue::Null;
+            }
+            QueryValue::Bool(like(&value.text().to_lowercase(), &pattern.text().to_lowercase()) != *negated)
+        }
+        Expr::In { expr, list, negated } => {
+            let value = evaluate(expr, scope);
+            if value.is_null() {
+                return QueryValue::Null;
+            }
+            let found = list.iter().any(|item| value.compare(&evaluate(item, scope)) == Some(Ordering::Equal));
+            QueryValue::Bool(found != *negated)
+        }
+        Expr::Between { expr, low, high, negated } => {
+            let value = evaluate(expr, scope);
+            match (value.compare(&evaluate(low, scope)), value.compare(&evaluate(high, scope))) {
+                (Some(low), Some(high)) => QueryValue::Bool((low != Ordering::Less && high != Ordering::Greater) != *negated),
+                _ => QueryValue::Null,
+            }
+        }
+        Expr::Function(function, args) => {
+            let args: Vec<QueryValue> = args.iter().map(|arg| evaluate(arg, scope)).collect();
+            function.apply(&args)
+        }
+        Expr::Aggregate { function, arg, distinct } => match scope {
+            Scope::Group(rows) => evaluate_aggregate(*function, arg.as_deref(), *distinct, rows),
+            Scope::Row(_) => QueryValue::Null,
+        },
+    }
+}
+
+fn evaluate_binary(left: QueryValue, op: BinaryOp, right: impl FnOnce() -> QueryValue) -> QueryValue {
+    match op {
+        BinaryOp::And => match left.truth() {
+            Some(false) => QueryValue::Bool(false),
+            left => match (left, right().truth()) {
+                (_, Some(false)) => QueryValue::Bool(false),
+                (Some(true), Some(true)) => QueryValue::Bool(true),
+                _ => QueryValue::Null,
+            },
+        },
+        BinaryOp::Or => match left.truth() {
+            Some(true) => QueryValue::Bool(true),
+            left => match (left, right().truth()) {
+                (_, Some(true)) => QueryValue::Bool(true),
+                (Some(false), Some(false)) => QueryValue::Bool(false),
+                _ => QueryValue::Null,
+            },
+        },
+        BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Less | BinaryOp::LessOrEqual
+        | BinaryOp::Greater | BinaryOp::GreaterOrEqual => {
+            let Some(ordering) = left.compare(&right()) else {
+                return QueryValue::Null;
+            };
+            QueryValue::Bool(match op {
+                BinaryOp::Equal => ordering == Ordering::Equal,
+                BinaryOp::NotEqual => ordering != Ordering::Equal,
+                BinaryOp::Less => ordering == Ordering::Less,
+                BinaryOp::LessOrEqual => ordering != Ordering::Greater,
+                BinaryOp::Greater => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            })
+        }
+        BinaryOp::Concat => {
+            let right = right();
+            if left.is_null() || right.is_null() {
+                return QueryValue::Null;
+            }
+            QueryValue::Text(format!("{}{}", left.text(), right.text()))
+        }
+        BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => {
+            let (Some(a), Some(b)) = (left.as_number(), right().as_number()) else {
+                return QueryValue::Null;
+            };
+            let result = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Subtract => a - b,
+                BinaryOp::Multiply => a * b,
+                _ if b == 0.0 => return QueryValue::Null,
+                BinaryOp::Divide => a / b,
+                _ => a % b,
+            };
+            QueryValue::Number(result)
+        }
+    }
+}
+
+fn evaluate_aggregate(function: Aggregate, arg: Option<&Expr>, distinct: bool, rows: &[&Vec<QueryValue>]) -> QueryValue {
+    let Some(arg) = arg else {
+        return QueryValue::Number(rows.len() as f64);
+    };
+    let mut values: Vec<QueryValue> = rows.iter()
+        .map(|row| evaluate(arg, Scope::Row(row)))
+        .filter(|value| !value.is_null())
+        .collect();
+    if distinct {
+        let mut seen = HashSet::new();
+        values.retain(|value| seen.insert(value.key()));
+    }
+
+    match function {
+        Aggregate::Count => QueryValue::Number(values.len() as f64),
+        Aggregate::Sum | Aggregate::Avg => {
+            let numbers: Vec<f64> = values.iter().filter_map(QueryValue::as_number).collect();
+            if numbers.is_empty() {
+                return QueryValue::Null;
+            }
+            let sum: f64 = numbers.iter().sum();
+            QueryValue::Number(if function == Aggregate::Sum { sum } else { sum / numbers.len() as f64 })
+        }
+        Aggregate::Min => values.into_iter().min_by(QueryValue::sort_order).unwrap_or(QueryValue::Null),
+        Aggregate::Max => values.into_iter().max_by(QueryValue::sort_order).unwrap_or(QueryValue::Null),
+    }
+}
+
+/// SQL LIKE matching, where `%` matches any run of characters and `_` one character
+fn like(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    // Positions in the text reachable after each pattern character
+    let mut reachable = vec![false; text.len() + 1];
+    reachable[0] = true;
+    for &p in &pattern {
+        let mut next = vec![false; text.len() + 1];
+        for index in 0..=text.len() {
+            if !reachable[index] {
+                continue;
+            }
+            match p {
+                '%' => next[index..].iter_mut().for_each(|reach| *reach = true),
+                '_' if index < text.len() => next[index + 1] = true,
+                c if index < text.len() && text[index] == c => next[index + 1] = true,
+                _ => {}
+            }
+        }
+        reachable = next;
+    }
+    reachable[text.len()]
+}
+
+/// Run a query against a table and render the result as a markdown table
+fn run_query(mut query: Query, table: &QueryTable) -> Result<String> {
+    let columns = &table.columns;
+    let mut items: Vec<(Expr, String)> = Vec::new();
+    for item in std::mem::take(&mut query.select) {
+        match item {
+            SelectItem::Wildcard => items.extend(
+                columns.names.iter().enumerate().map(|(index, name)| (Expr::Index(index), name.clone())),
+            ),
+            SelectItem::Expr { mut expr, label } => {
+                expr.resolve(columns)?;
+                items.push((expr, label));
+            }
+        }
+    }
+
+    if let Some(filter) = &mut query.filter {
+        if filter.contains_aggregate() {
+            bail!("Aggregate functions are not allowed in WHERE; use HAVING");
+        }
+        filter.resolve(columns)?;
+    }
+    for expr in &mut query.group_by {
+        if expr.contains_aggregate() {
+            bail!("Aggregate functions are not allowed in GROUP BY");
+        }
+        expr.resolve(columns)?;
+    }
+    if let Some(having) = &mut query.having {
+        having.resolve(columns)?;
+    }
+
+    // ORDER BY may name an output column by alias or 1-based position
+    let mut sort_keys = Vec::new();
+    for (mut expr, descending) in std::mem::take(&mut query.order_by) {
+        let key = match &expr {
+            Expr::Literal(QueryValue::Number(position)) => {
+                let index = *position as usize;
+                if position.fract() != 0.0 || index == 0 || index > items.len() {
+                    bail!("ORDER BY position {} is not in the select list", position);
+                }
+                SortKey::Output(index - 1)
+            }
+            Expr::Column(name) if columns.find(name).is_err() => {
+                let index = items.iter()
+                    .position(|(_, label)| label.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| columns.find(name).unwrap_err())?;
+                SortKey::Output(index)
+            }
+            _ => {
+                expr.resolve(columns)?;
+                SortKey::Expr(expr)
+            }
+        };
+        sort_keys.push((key, descending));
+    }
+
+    let filtered: Vec<&Vec<QueryValue>> = table.rows.iter()
+        .filter(|row| query.filter.as_ref().is_none_or(|filter| evaluate(filter, Scope::Row(row)).truth() == Some(true)))
+        .collect();
+
+    let aggregated = !query.group_by.is_empty()
+        || query.having.is_some()
+        || items.iter().any(|(expr, _)| expr.contains_aggregate())
+        || sort_keys.iter().any(|(key, _)| matches!(key, SortKey::Expr(expr) if expr.contains_aggregate()));
+
+    // Each output row with the values it is sorted by
+    let mut results: Vec<(Vec<QueryValue>, Vec<QueryValue>)> = Vec::new();
+    let mut emit = |scope: Scope| {
+        let values: Vec<QueryValue> = items.iter().map(|(expr, _)| evaluate(expr, scope)).collect();
+        let keys = sort_keys.iter()
+            .map(|(key, _)| match key {
+                SortKey::Output(index) => values[*index].clone(),
+                SortKey::Expr(expr) => evaluate(expr, scope),
+            })
+            .collect();
+        results.push((values, keys));
+    };
+
+    if aggregated {
+        let mut groups: Vec<Vec<&Vec<QueryValue>>> = Vec::new();
+        let mut group_index: HashMap<Vec<String>, usize> = HashMap::new();
+        for row in filtered {
+            let key: Vec<String> = query.group_by.iter().map(|expr| evaluate(expr, Scope::Row(row)).key()).collect();
+            let index = *group_index.entry(key).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[index].push(row);
+        }
+        // Without GROUP BY, aggregates cover all rows, even when there are none
+        if groups.is_empty() && query.group_by.is_empty() {
+            groups.push(Vec::new());
+        }
+        for group in &groups {
+            let scope = Scope::Group(group);
+            if query.having.as_ref().is_none_or(|having| evaluate(having, scope).truth() == Some(true)) {
+                emit(scope);
+            }
+        }
+    } else {
+        for row in filtered {
+            emit(Scope::Row(row));
+        }
+    }
+
+    if query.distinct {
+        let mut seen = HashSet::new();
+        results.retain(|(values, _)| seen.insert(values.iter().map(QueryValue::key).collect::<Vec<_>>()));
+    }
+    if !sort_keys.is_empty() {
+        results.sort_by(|(_, a), (_, b)| {
+            a.iter().zip(b).zip(&sort_keys)
+                .map(|((a, b), (_, descending))| if *descending { b.sort_order(a) } else { a.sort_order(b) })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+
+    let rows: Vec<Vec<QueryValue>> = results.into_iter()
+        .map(|(values, _)| values)
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect();
+    if rows.is_empty() {
+        return Ok("*No rows match the query*".to_string());
+    }
+
+    let header: Vec<String> = items.iter().map(|(_, label)| label.clone()).collect();
+    let shown = rows.len().min(MAX_RESULT_ROWS);
+    let body = rows[..shown].iter().map(|row| row.iter().map(QueryValue::text).collect());
+    let mut markdown = text_table(std::iter::once(header).chain(body));
+    if shown < rows.len() {
+        markdown.push_str(&format!("\n\n*Showing the first {} of {} rows; use LIMIT and OFFSET to page through the rest*", shown, rows.len()));
+    } else {
+        markdown.push_str(&format!("\n\n*{} row{}*", rows.len(), if rows.len() == 1 { "" } else { "s" }));
+    }
+    Ok(markdown)
+}
+
+/// Run a SQL-like query over a sheet, Excel table, defined name or A1 range of
+/// a spreadsheet, or over a CSV or TSV file, and render the result as a markdown
+/// table. The header row gives the column names. Supports SELECT [DISTINCT],
+/// FROM, WHERE, GROUP BY, HAVING, ORDER BY and LIMIT/OFFSET; without FROM the
+/// first visible sheet is queried.
+pub fn query_spreadsheet(resolved_file_path: &str, query: &str) -> FileTextResult {
+    let file_path = resolved_file_path.to_string();
+    let is_delimited = match validate_file_path(resolved_file_path) {
+        Ok(ext) if matches!(ext.as_str(), "xlsx" | "xlsm" | "xlsb" | "xls" | "xla" | "ods") => false,
+        Ok(ext) if matches!(ext.as_str(), "csv" | "tsv") => true,
+        Ok(ext) => return FileTextResult::error(
+            file_path,
+            format!("Queries are only supported for spreadsheets and CSV/TSV files, not {} files", ext),
+        ),
+        Err(e) => return FileTextResult::error(file_path, e),
+    };
+
+    let result = Parser::new(query)
+        .and_then(|mut parser| parser.parse_query())
+        .map_err(|e| format!("Invalid query: {}", e))
+        .and_then(|parsed| {
+            let table = if is_delimited {
+                load_delimited(resolved_file_path, parsed.from.as_deref())
+            } else {
+                load_workbook(resolved_file_path, parsed.from.as_deref())
+            }
+            .map_err(|e| format!("Failed to read query source: {}", e))?;
+            let result = run_query(parsed, &table).map_err(|e| format!("Invalid query: {}", e))?;
+            Ok(format!(
+                "# {}\n\n## Query on {}\n\n```sql\n{}\n```\n\n{}\n",
+                file_name(resolved_file_path),
+                table.title,
+                query.trim(),
+                result
+            ))
+        });
+
+    match result {
+        Ok(content) => FileTextResult::success(file_path, content),
+        Err(e) => FileTextResult::error(file_path, e),
+    }
+}
+
+/// Load the rows named by FROM: a sheet, an Excel table, a defined name or an
+/// A1 range, or the first visible sheet when there is no FROM
+fn load_workbook(file_path: &str, from: Option<&str>) -> Result<QueryTable> {
+    let mut workbook = open_excel_workbook(file_path)?;
+    let sheet_names = workbook.sheet_names().to_owned();
+    let visibility = sheet_visibility(&workbook);
+    let first_visible = visibility.iter().position(|&visible| visible == SheetVisible::Visible).unwrap_or(0);
+
+    let (sheet_index, area, title) = match from {
+        None => (first_visible, None, None),
+        Some(name) => match sheet_names.iter().position(|sheet| sheet.eq_ignore_ascii_case(name)) {
+            Some(index) => (index, None, None),
+            None => {
+                let tables = read_excel_tables(file_path).unwrap_or_default();
+                let ranges = resolve_range_parameter(&workbook, &tables, name).map_err(|e| anyhow!(
+                    "'{}' is not a sheet, table, defined name or A1 range ({}). Sheets: {}",
+                    name, e, sheet_names.join(", ")
+                ))?;
+                let [range] = ranges.as_slice() else {
+                    bail!("'{}' refers to {} areas; query a single rectangle of cells", name, ranges.len());
+                };
+                let index = match &range.sheet {
+                    Some(sheet) => sheet_names.iter()
+                        .position(|name| name.eq_ignore_ascii_case(sheet))
+                        .ok_or_else(|| anyhow!("Sheet '{}' not found. Available sheets: {}", sheet, sheet_names.join(", ")))?,
+                    None => first_visible,
+                };
+                let qualified = CellRange { sheet: Some(sheet_names[index].clone()), ..range.clone() };
+                (index, Some(qualified.clone()), Some(qualified.to_string()))
+            }
+        },
+    };
+
+    let sheet_name = sheet_names.get(sheet_index).ok_or_else(|| anyhow!("The workbook has no sheets"))?;
+    let range = workbook.worksheet_range(sheet_name)
+        .map_err(|e| anyhow!("Failed to read sheet '{}': {}", sheet_name, e))?;
+    let formats = WorkbookFormats::open(file_path);
+    let title = format!("Sheet {}: {}", sheet_index + 1, title.unwrap_or_else(|| sheet_name.clone()));
+    let bounds = match area {
+        Some(area) => area.clip_to(&range),
+        None => used_area(&range).map(|area| (area.start, area.end)),
+    };
+    let Some((start, end)) = bounds else {
+        bail!("{} has no data", title);
+    };
+    Ok(QueryTable::from_range(title, &range, &formats.sheet(sheet_name), start, end))
+}
+
+/// Load a CSV or TSV file, whose only sheet is named after the file
+fn load_delimited(file_path: &str, from: Option<&str>) -> Result<QueryTable> {
+    let table = CsvTable::read(file_path)?;
+    if let Some(name) = from.filter(|name| !name.eq_ignore_ascii_case(&table.name)) {
+        bail!("Sheet '{}' not found. Available sheets: {}", name, table.name);
+    }
+    let range = table.typed_range();
+    let Some(area) = used_area(&range) else {
+        bail!("The file has no data");
+    };
+    Ok(QueryTable::from_range(format!("Sheet 1: {}", table.name), &range, &CellFormatter::default(), area.start, area.end))
+}
+
+fn file_name(file_path: &str) -> String {
+    Path::new(file_path).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::excel_parser::tests::XlsxBuilder;
+
+    fn sales_workbook() -> tempfile::NamedTempFile {
+        XlsxBuilder::new()
+            .sheet("Notes", vec![vec!["See Sales"]])
+            .sheet("Sales", vec![
+                vec!["Region", "Date", "Revenue", "Rep"],
+                vec!["North", "45110", "100", "Ann"],
+                vec!["South", "45120", "250.5", "Bo"],
+                vec!["North", "45200", "40", ""],
+                vec!["West", "45000", "75", "Cy"],
+                vec!["North", "45130", "60", "Ann"],
+            ])
+            .number_format("B2", "yyyy-mm-dd")
+            .number_format("B3", "yyyy-mm-dd")
+            .number_format("B4", "yyyy-mm-dd")
+            .number_format("B5", "yyyy-mm-dd")
+            .number_format("B6", "yyyy-mm-dd")
+            .table("SalesTable", "A1:D6")
+            .file()
+    }
+
+    fn run(file: &tempfile::NamedTempFile, query: &str) -> String {
+        let result = query_spreadsheet(file.path().to_str().unwrap(), query);
+        match result.error {
+            Some(error) => error,
+            None => result.content,
+        }
+    }
+
+    #[test]
+    fn test_group_by_with_date_filter() {
+        let file = sales_workbook();
+        let output = run(&file, "SELECT Region, SUM(Revenue) AS total, COUNT(*) AS orders FROM Sales \
+                                 WHERE QUARTER(Date) = 3 AND YEAR(Date) = 2023 GROUP BY Region ORDER BY total DESC");
+        assert!(output.contains("## Query on Sheet 2: Sales\n\n```sql\nSELECT Region"), "{}", output);
+        assert!(output.contains("| Region | total | orders |\n| --- | --- | --- |\n| South | 250.5 | 1 |\n| North | 160 | 2 |\n\n*2 rows*"), "{}", output);
+    }
+
+    #[test]
+    fn test_filters_sorting_and_limits() {
+        let file = sales_workbook();
+        let output = run(&file, "select Rep, Revenue * 2 doubled from SalesTable where Rep is not null and Region in ('North', 'West') order by 2 limit 2 offset 1");
+        assert!(output.contains("## Query on Sheet 2: Sales!A1:D6"), "{}", output);
+        assert!(output.contains("| Rep | doubled |\n| --- | --- |\n| Cy | 150 |\n| Ann | 200 |\n\n*2 rows*"), "{}", output);
+
+        let output = run(&file, "SELECT DISTINCT Region FROM \"Sales\" WHERE Region LIKE '%th' ORDER BY Region");
+        assert!(output.contains("| Region |\n| --- |\n| North |\n| South |\n"), "{}", output);
+
+        let output = run(&file, "SELECT MIN(Date), MAX(Date), AVG(Revenue) FROM [Sales] WHERE Revenue BETWEEN 50 AND 200");
+        assert!(output.contains("| MIN(Date) | MAX(Date) | AVG(Revenue) |\n| --- | --- | --- |\n| 2023-03-15 | 2023-07-23 | 78.3333333333 |"), "{}", output);
+
+        let output = run(&file, "SELECT Region, COUNT(*) n FROM Sales GROUP BY Region HAVING COUNT(*) > 1");
+        assert!(output.contains("| North | 3 |\n\n*1 row*"), "{}", output);
+        assert!(run(&file, "SELECT * FROM Sales WHERE Revenue > 1000").contains("*No rows match the query*"));
+        assert!(run(&file, "SELECT *").contains("## Query on Sheet 1: Notes"));
+    }
+
+    #[test]
+    fn test_query_errors() {
+        let file = sales_workbook();
+        assert!(run(&file, "SELECT Revnue FROM Sales").contains("Unknown column 'Revnue'. Available columns: Region, Date, Revenue, Rep"));
+        assert!(run(&file, "SELECT * FROM Missing").contains("'Missing' is not a sheet, table, defined name or A1 range"));
+        assert!(run(&file, "SELECT Region FROM Sales WHERE SUM(Revenue) > 1").contains("not allowed in WHERE"));
+        assert!(run(&file, "SELECT Region FROM Sales ORDER").contains("Expected BY but the query ended"));
+        assert!(run(&file, "SELECT Region FROM Sales WHERE Region = 'North").contains("Unterminated string"));
+        assert!(run(&file, "SELECT MEDIAN(Revenue) FROM Sales").contains("Unknown function 'MEDIAN'"));
+
+        // Deep nesting is refused instead of overflowing the stack
+        let nested = |open: &str, close: &str, depth: usize| {
+            format!("SELECT Region FROM Sales WHERE {}Revenue > 1{}", open.repeat(depth), close.repeat(depth))
+        };
+        assert!(run(&file, &nested("(", ")", 100_000)).contains("Query nests too deeply"));
+        assert!(run(&file, &nested("NOT ", "", 100_000)).contains("Query nests too deeply"));
+        assert!(run(&file, &nested("- ", "", 100_000)).contains("Query nests too deeply"));
+        assert!(run(&file, &nested("(", ")", 200)).contains("| Region |"));
+    }
+
+    #[test]
+    fn test_query_csv_file() {
+        use std::io::Write;
+        let mut file = tempfile::NamedTempFile::with_suffix(".csv").unwrap();
+        file.write_all(b"city,temp\nOslo,4\nRome,18\nOslo,6\n").unwrap();
+        let output = run(&file, "SELECT city, AVG(temp) AS avg_temp GROUP BY city ORDER BY avg_temp");
+        assert!(output.contains("| city | avg_temp |\n| --- | --- |\n| Oslo | 5 |\n| Rome | 18 |"), "{}", output);
+    }
+
+    #[test]
+    fn test_like_patterns() {
+        assert!(like("north", "n%"));
+        assert!(like("north", "%rt_"));
+        assert!(!like("north", "s%"));
+        assert!(like("", "%"));
+        assert!(!like("ab", "a"));
+    }
+}
@@ -1,5 +1,9 @@
+use std::fs::File;
+use std::io::BufReader;
 use std::path::Path;
-use anyhow::{Result, Context};
+use std::sync::{Arc, Mutex, PoisonError};
+use anyhow::Result;
+use calamine::Sheets;
 use futures::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
 use crate::shared_utils::{
@@ -7,6 +11,7 @@ use crate::shared_utils::{
     generate_file_header, generate_chunk_header,
     break_at_word_boundary
 };
+use crate::excel_parser::WorkbookFormats;
 
 /// Progress information for streaming document processing
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -149,19 +154,19 @@ pub fn stream_excel_to_markdown(
     let file_path = file_path.to_string();
     
     stream::unfold(
-        (0usize, false, config),
-        move |(current_sheet, is_complete, config)| {
+        (0usize, false, config, None::<Arc<Mutex<ExcelSource>>>),
+        move |(current_sheet, is_complete, config, source)| {
             let file_path = file_path.clone();
             async move {
                 if is_complete {
                     return None;
                 }
 
-                match process_excel_chunk(&file_path, current_sheet, &config).await {
-                    Ok(progress) => {
+                match process_excel_chunk(&file_path, source, current_sheet, &config).await {
+                    Ok((progress, source)) => {
                         let next_sheet = current_sheet + 1;
                         let is_done = progress.is_complete;
-                        Some((progress, (next_sheet, is_done, config)))
+                        Some((progress, (next_sheet, is_done, config, Some(source))))
                     }
                     Err(e) => {
                         let error_progress = ProcessingProgress {
@@ -171,7 +176,7 @@ pub fn stream_excel_to_markdown(
                             is_complete: true,
                             error: Some(e.to_string()),
                         };
-                        Some((error_progress, (current_sheet, true, config)))
+                        Some((error_progress, (current_sheet, true, config, None)))
                     }
                 }
             }
@@ -179,31 +184,47 @@ pub fn stream_excel_to_markdown(
     )
 }
 
-/// Process a chunk of Excel sheets
+/// A workbook and its number formats, opened once for all the chunks of a stream
+struct ExcelSource {
+    workbook: Sheets<BufReader<File>>,
+    formats: WorkbookFormats,
+}
+
+/// Process a chunk of Excel sheets, opening the workbook on the first chunk
 async fn process_excel_chunk(
     file_path: &str,
+    source: Option<Arc<Mutex<ExcelSource>>>,
     sheet_index: usize,
     _config: &StreamingConfig,
-) -> Result<ProcessingProgress> {
-    use calamine::{Reader, open_workbook, Xlsx};
+) -> Result<(ProcessingProgress, Arc<Mutex<ExcelSource>>)> {
+    use calamine::Reader;
     
     let file_path = file_path.to_string();
     
     tokio::task::spawn_blocking(move || {
-        let mut workbook: Xlsx<_> = open_workbook(&file_path)
-            .with_context(|| format!("Failed to open Excel file: {}", file_path))?;
+        let source = match source {
+            Some(source) => source,
+            None => Arc::new(Mutex::new(ExcelSource {
+                workbook: crate::document_parser::open_excel_workbook(&file_path)?,
+                formats: WorkbookFormats::open(&file_path),
+            })),
+        };
+        let mut guard = source.lock().unwrap_or_else(PoisonError::into_inner);
+        let ExcelSource { workbook, formats } = &mut *guard;
         
         let sheet_names = workbook.sheet_names().to_owned();
         let total_sheets = sheet_names.len();
         
         if sheet_index >= total_sheets {
-            return Ok(ProcessingProgress {
+            let progress = ProcessingProgress {
                 current_page: sheet_index,
                 total_pages: Some(total_sheets),
                 current_chunk: String::new(),
                 is_complete: true,
                 error: None,
-            });
+            };
+            drop(guard);
+            return Ok((progress, source));
         }
         
         let mut chunk_content = String::new();
@@ -221,19 +242,139 @@ async fn process_excel_chunk(
         let sheet_name = &sheet_names[sheet_index];
         chunk_content.push_str(&format!("## Sheet: {}\n\n", sheet_name));
         
-        if let Ok(range) = workbook.worksheet_range(sheet_name) {
-            chunk_content.push_str(&crate::document_parser::range_to_markdown_table(&range));
+        let visible = workbook.sheets_metadata()[sheet_index].visible;
+        if visible != calamine::SheetVisible::Visible {
+            chunk_content.push_str("*Hidden sheet not shown*\n\n");
+        } else if let Ok(range) = workbook.worksheet_range(sheet_name) {
+            chunk_content.push_str(&crate::document_parser::range_to_markdown_table(&range, &formats.sheet(sheet_name)));
             chunk_content.push_str("\n\n");
         }
         
         let is_complete = sheet_index + 1 >= total_sheets;
         
-        Ok(ProcessingProgress {
+        let progress = ProcessingProgress {
             current_page: sheet_index + 1,
             total_pages: Some(total_sheets),
             current_chunk: chunk_content,
             is_complete,
             error: None,
-        })
+        };
+        drop(guard);
+        Ok((progress, source))
     }).await?
-} 
\ No newline at end of file
+}
+
+/// Stream CSV and TSV content in chunks of rows, repeating the header row in
+/// every chunk so each one is a complete markdown table. Only the output is
+/// chunked: the file is read and decoded whole before the first chunk, since
+/// its encoding and dialect are detected from the full text.
+pub fn stream_csv_to_markdown(
+    file_path: &str,
+    config: StreamingConfig,
+) -> impl Stream<Item = ProcessingProgress> {
+    let file_path = file_path.to_string();
+    let filename = Path::new(&file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    stream::unfold(
+        (
+            1usize,
+            false,
+            config,
+            None::<Arc<crate::csv_parser::CsvTable>>,
+        ),
+        move |(next_row, is_complete, config, table)| {
+            let file_path = file_path.clone();
+            let filename = filename.clone();
+            async move {
+                if is_complete {
+                    return None;
+                }
+
+                let table = match table {
+                    Some(table) => table,
+                    None => {
+                        let read = tokio::task::spawn_blocking(move || {
+                            crate::csv_parser::CsvTable::read(&file_path)
+                        })
+                        .await;
+                        match read.map_err(anyhow::Error::from).and_then(|table| table) {
+                            Ok(table) => Arc::new(table),
+                            Err(e) => {
+                                let error_progress = ProcessingProgress {
+                                    current_page: 0,
+                                    total_pages: None,
+                                    current_chunk: String::new(),
+                                    is_complete: true,
+                                    error: Some(e.to_string()),
+                                };
+                                return Some((error_progress, (next_row, true, config, None)));
+                            }
+                        }
+                    }
+                };
+
+                let (progress, end_row) = csv_chunk(&filename, &table, next_row, &config);
+                let is_done = progress.is_complete;
+                Some((progress, (end_row, is_done, config, Some(table))))
+            }
+        },
+    )
+}
+
+/// Render the rows of a CSV table starting at `start_row` that fit in one chunk,
+/// returning the progress and the row to continue from
+fn csv_chunk(
+    filename: &str,
+    table: &crate::csv_parser::CsvTable,
+    start_row: usize,
+    config: &StreamingConfig,
+) -> (ProcessingProgress, usize) {
+    let data_rows = table.records.len().saturating_sub(1);
+    let mut chunk_content = String::new();
+
+    // Add file and sheet headers to the first chunk
+    if start_row == 1 {
+        chunk_content.push_str(&format!("# {}\n\n## Sheet: {}\n\n", filename, table.name));
+    }
+
+    let header = table.records.first().cloned().unwrap_or_default();
+    let mut size = header.iter().map(|cell| cell.len() + 3).sum::<usize>();
+    let mut end_row = start_row;
+    while end_row < table.records.len() {
+        let row_size = table.records[end_row]
+            .iter()
+            .map(|cell| cell.len() + 3)
+            .sum::<usize>();
+        // Always take at least one row so the stream makes progress
+        if end_row > start_row && size + row_size > config.max_chunk_size_chars {
+            break;
+        }
+        size += row_size;
+        end_row += 1;
+    }
+
+    let rows = std::iter::once(header).chain(
+        table.records[start_row.min(table.records.len())..end_row]
+            .iter()
+            .cloned(),
+    );
+    let markdown = crate::markdown_table::text_table(rows);
+    if markdown.is_empty() {
+        chunk_content.push_str("Empty sheet");
+    } else {
+        chunk_content.push_str(&markdown);
+    }
+    chunk_content.push_str("\n\n");
+
+    let progress = ProcessingProgress {
+        current_page: end_row.saturating_sub(1),
+        total_pages: Some(data_rows),
+        current_chunk: chunk_content,
+        is_complete: end_row >= table.records.len(),
+        error: None,
+    };
+    (progress, end_row)
+}
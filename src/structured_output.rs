@@ -0,0 +1,169 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::markdown_table::text_table;
+
+/// How spreadsheet cells are returned when reading a sheet, range or row window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// A markdown table of the cells as Excel displays them
+    #[default]
+    Markdown,
+    /// Comma-separated values, with the header row first
+    Csv,
+    /// A JSON array with one object per data row, keyed by the header row
+    JsonRecords,
+    /// A JSON object with one array per column, keyed by the header row
+    JsonColumns,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "markdown" => Ok(Self::Markdown),
+            "csv" => Ok(Self::Csv),
+            "json_records" => Ok(Self::JsonRecords),
+            "json_columns" => Ok(Self::JsonColumns),
+            other => Err(format!(
+                "Invalid output_format '{}': expected 'markdown', 'csv', 'json_records' or 'json_columns'",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Markdown => "markdown",
+            Self::Csv => "csv",
+            Self::JsonRecords => "json_records",
+            Self::JsonColumns => "json_columns",
+        })
+    }
+}
+
+/// Render rows of typed cell values, the first of which is the header row, in
+/// the given format. CSV and JSON are returned as a fenced code block; header
+/// cells that are empty are named after the matching entry of `columns`.
+pub fn render_structured(format: OutputFormat, columns: &[String], rows: &[Vec<Value>]) -> String {
+    let Some((header, data)) = rows.split_first() else {
+        return "*No data in this range*".to_string();
+    };
+    match format {
+        OutputFormat::Markdown => text_table(rows.iter().map(|row| row.iter().map(value_text).collect())),
+        OutputFormat::Csv => {
+            let lines: Vec<String> = rows.iter()
+                .map(|row| row.iter().map(|value| csv_field(&value_text(value))).collect::<Vec<_>>().join(","))
+                .collect();
+            format!("```csv\n{}\n```", lines.join("\n"))
+        }
+        OutputFormat::JsonRecords => {
+            let keys = record_keys(header, columns);
+            let records: Vec<String> = data.iter()
+                .map(|row| {
+                    let fields: Vec<String> = keys.iter().enumerate()
+                        .map(|(index, key)| format!("{}: {}", key, row.get(index).unwrap_or(&Value::Null)))
+                        .collect();
+                    format!("  {{{}}}", fields.join(", "))
+                })
+                .collect();
+            let json = if records.is_empty() { "[]".to_string() } else { format!("[\n{}\n]", records.join(",\n")) };
+            format!("```json\n{}\n```", json)
+        }
+        OutputFormat::JsonColumns => {
+            let keys = record_keys(header, columns);
+            let arrays: Vec<String> = keys.iter().enumerate()
+                .map(|(index, key)| {
+                    let values: Vec<String> = data.iter()
+                        .map(|row| row.get(index).unwrap_or(&Value::Null).to_string())
+                        .collect();
+                    format!("  {}: [{}]", key, values.join(", "))
+                })
+                .collect();
+            let json = if arrays.is_empty() { "{}".to_string() } else { format!("{{\n{}\n}}", arrays.join(",\n")) };
+            format!("```json\n{}\n```", json)
+        }
+    }
+}
+
+/// Plain text of a value: strings without quotes, and nothing for empty cells
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Quote a CSV field when it contains a comma, quote or line break
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// JSON-encoded object keys from the header row. Empty header cells take the
+/// column name, and repeated names get a `_2`, `_3`, ... suffix.
+fn record_keys(header: &[Value], columns: &[String]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::with_capacity(header.len());
+    for (index, cell) in header.iter().enumerate() {
+        let mut name = value_text(cell).trim().to_string();
+        if name.is_empty() {
+            name = columns.get(index).cloned().unwrap_or_else(|| format!("Column {}", index + 1));
+        }
+        let mut key = name.clone();
+        let mut suffix = 2;
+        while keys.contains(&key) {
+            key = format!("{}_{}", name, suffix);
+            suffix += 1;
+        }
+        keys.push(key);
+    }
+    keys.into_iter().map(|key| Value::String(key).to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rows() -> Vec<Vec<Value>> {
+        vec![
+            vec![json!("Item"), json!(null), json!("Item")],
+            vec![json!("Pen, blue"), json!(3), json!(true)],
+            vec![json!("Ink"), json!(1.5), json!("2024-01-31")],
+        ]
+    }
+
+    #[test]
+    fn test_parse_output_format() {
+        assert_eq!("JSON_records".parse::<OutputFormat>(), Ok(OutputFormat::JsonRecords));
+        assert_eq!(OutputFormat::JsonColumns.to_string(), "json_columns");
+        assert!("xml".parse::<OutputFormat>().unwrap_err().contains("expected 'markdown'"));
+    }
+
+    #[test]
+    fn test_render_structured() {
+        let columns = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        assert_eq!(
+            render_structured(OutputFormat::Csv, &columns, &rows()),
+            "```csv\nItem,,Item\n\"Pen, blue\",3,true\nInk,1.5,2024-01-31\n```"
+        );
+        assert_eq!(
+            render_structured(OutputFormat::JsonRecords, &columns, &rows()),
+            "```json\n[\n  {\"Item\": \"Pen, blue\", \"B\": 3, \"Item_2\": true},\n  {\"Item\": \"Ink\", \"B\": 1.5, \"Item_2\": \"2024-01-31\"}\n]\n```"
+        );
+        assert_eq!(
+            render_structured(OutputFormat::JsonColumns, &columns, &rows()),
+            "```json\n{\n  \"Item\": [\"Pen, blue\", \"Ink\"],\n  \"B\": [3, 1.5],\n  \"Item_2\": [true, \"2024-01-31\"]\n}\n```"
+        );
+        assert_eq!(render_structured(OutputFormat::JsonRecords, &columns, &rows()[..1]), "```json\n[]\n```");
+    }
+}
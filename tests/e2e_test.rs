@@ -885,7 +885,11 @@ async fn test_tool_discovery_and_metadata() {
         "stream_office_document",
         "read_powerpoint_slides",
         "get_powerpoint_slide_info",
-        "generate_powerpoint_slide_snapshot"
+        "generate_powerpoint_slide_snapshot",
+        "list_spreadsheet_tables",
+        "profile_spreadsheet",
+        "query_spreadsheet",
+        "get_pdf_outline"
     ];
     
     for expected_tool in expected_tools {